@@ -0,0 +1,55 @@
+//! Compare the SIMD search used by Gear32 and FastCdc against the scalar one
+//!
+//! Both are given the entire buffer. SIMD is disabled for the scalar numbers, so the difference is
+//! only in the search.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use hash_roll::Chunk;
+use rand::RngCore;
+use rand::SeedableRng;
+
+/* 4 MiB */
+const BENCH_BYTES: usize = 4 * 1024 * 1024;
+
+/// Find every edge in `data`
+fn edges<C: Chunk>(chunker: &C, data: &[u8]) -> usize {
+    let mut state = chunker.to_search_state();
+    let mut count = 0;
+    let mut discard = 0;
+    while discard < data.len() {
+        let (edge, discard_ct) = chunker.find_chunk_edge(&mut state, &data[discard..]);
+        if edge.is_some() {
+            count += 1;
+        }
+        discard += discard_ct;
+    }
+    count
+}
+
+fn bench_search<C: Chunk>(c: &mut Criterion, name: &str, chunker: C) {
+    let mut rng = rand_pcg::Pcg64::from_rng(rand::thread_rng()).unwrap();
+    let mut d = vec![0u8; BENCH_BYTES];
+    rng.fill_bytes(&mut d);
+
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes(BENCH_BYTES as u64));
+    for &simd in &[true, false] {
+        hash_roll::__set_gear_simd_enabled(simd);
+        let id = if simd { "simd" } else { "scalar" };
+        group.bench_with_input(BenchmarkId::new(id, BENCH_BYTES), &d, |b, d| {
+            b.iter(|| edges(&chunker, black_box(d)))
+        });
+    }
+    hash_roll::__set_gear_simd_enabled(true);
+    group.finish();
+}
+
+fn bench_gear32(c: &mut Criterion) {
+    bench_search(c, "gear32", hash_roll::gear::Gear32::default());
+}
+
+fn bench_fastcdc(c: &mut Criterion) {
+    bench_search(c, "fastcdc", hash_roll::fastcdc::FastCdc::default());
+}
+
+criterion_group!(benches, bench_gear32, bench_fastcdc);
+criterion_main!(benches);
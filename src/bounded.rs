@@ -0,0 +1,336 @@
+//! Adding minimum and maximum chunk sizes to any algorithm
+//!
+//! Several algorithms ([`crate::gear`], [`crate::pigz`], [`crate::gzip`], [`crate::zstd`],
+//! [`crate::bup`], [`crate::mii`]) place no limits on the size of the chunks they emit. [`Bounded`]
+//! (and [`BoundedIncr`]) wrap any of them and:
+//!
+//!  - ignore chunk edges that would result in a chunk smaller than the minimum size
+//!  - place a chunk edge once a chunk reaches the maximum size
+//!
+//! The wrapped algorithm is reset at every chunk edge, so each chunk is examined independently of
+//! the data before it.
+//!
+//! When the wrapped algorithm only examines a fixed number of trailing bytes (its window) to
+//! decide on an edge, [`Bounded::with_window()`] can be used to skip hashing the start of each
+//! chunk: only the last `window` bytes before the minimum size are passed to the wrapped
+//! algorithm. This finds the same edges as [`Bounded::new()`], except where the wrapped algorithm
+//! finds an edge in those `window` bytes (which is ignored as it is below the minimum size, but
+//! still resets the wrapped algorithm). The window sizes of the algorithms in this crate are:
+//!
+//! | algorithm | window |
+//! |---|---|
+//! | [`crate::bup::RollSum`] | the `window_len` (64 by default) |
+//! | [`crate::buzhash::BuzHash`] | `k` |
+//! | [`crate::gear::Gear32`] | 32 |
+//! | [`crate::gzip::GzipRsyncable`] | the `window` (8192 by default) |
+//! | [`crate::pigz::PigzRsyncable`] | `bits` (12 by default) |
+//! | [`crate::zstd::Zstd`] | 32 |
+//!
+//! Others either depend on all the data since the start of the chunk ([`crate::mii`],
+//! [`crate::ram`], [`crate::zpaq`]), or already support minimum sizes themselves
+//! ([`crate::fastcdc`]). Skipping data changes the edges of those algorithms, but the result is
+//! still deterministic.
+//!
+//! ```rust
+//! # #[cfg(feature = "mii")] {
+//! use hash_roll::bounded::Bounded;
+//! use hash_roll::Chunk;
+//!
+//! let data = vec![1u8; 100_000];
+//! let chunker = Bounded::new(hash_roll::mii::Mii::default(), 1024..=16384);
+//! for r in chunker.chunk_ranges(&data) {
+//!     assert!(r.len() <= 16384);
+//! }
+//! # }
+//! ```
+use crate::config::ConfigError;
+use crate::{Chunk, ChunkIncr, ToChunkIncr};
+use std::ops::RangeBounds;
+
+/// Wrap a chunking algorithm to enforce minimum and maximum chunk sizes
+///
+/// See the [module documentation](self) for details.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bounded<C> {
+    inner: C,
+    bounds: Bounds,
+}
+
+/// Chunk size limits shared by [`Bounded`] and [`BoundedIncr`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    /// Smallest chunk that may be emitted (other than the last)
+    min: u64,
+
+    /// Largest chunk that may be emitted
+    max: u64,
+
+    /// Bytes at the start of each chunk that are not passed to the wrapped algorithm
+    skip: u64,
+}
+
+impl Bounds {
+    fn new(range: impl RangeBounds<u64>, window: Option<u64>) -> Result<Self, ConfigError> {
+        let (min, max) = crate::config::size_range("bounded", "range", range)?;
+        Ok(Bounds {
+            min,
+            max,
+            skip: window.map_or(0, |w| min.saturating_sub(w)),
+        })
+    }
+}
+
+impl<C> Bounded<C> {
+    /// Wrap `inner`, limiting the size of chunks to `range`
+    ///
+    /// Every byte of each chunk is passed to `inner`.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty or only contains 0. See [`Bounded::try_new()`].
+    pub fn new(inner: C, range: impl RangeBounds<u64>) -> Self {
+        Self::try_new(inner, range).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Wrap `inner`, limiting the size of chunks to `range`, or return an error if `range` is
+    /// empty or only contains 0
+    pub fn try_new(inner: C, range: impl RangeBounds<u64>) -> Result<Self, ConfigError> {
+        Ok(Bounded {
+            inner,
+            bounds: Bounds::new(range, None)?,
+        })
+    }
+
+    /// Wrap `inner`, limiting the size of chunks to `range`, and skipping all but the last `window`
+    /// bytes before the minimum size of each chunk
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty or only contains 0. See [`Bounded::try_with_window()`].
+    pub fn with_window(inner: C, range: impl RangeBounds<u64>, window: u64) -> Self {
+        Self::try_with_window(inner, range, window).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Wrap `inner`, limiting the size of chunks to `range`, and skipping all but the last `window`
+    /// bytes before the minimum size of each chunk, or return an error if `range` is empty or only
+    /// contains 0
+    pub fn try_with_window(
+        inner: C,
+        range: impl RangeBounds<u64>,
+        window: u64,
+    ) -> Result<Self, ConfigError> {
+        Ok(Bounded {
+            inner,
+            bounds: Bounds::new(range, Some(window))?,
+        })
+    }
+
+    /// The wrapped algorithm
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+impl<C: Chunk> Chunk for Bounded<C> {
+    type SearchState = BoundedSearchState<C::SearchState>;
+
+    fn to_search_state(&self) -> Self::SearchState {
+        BoundedSearchState {
+            inner: self.inner.to_search_state(),
+            discarded: 0,
+            inner_start: self.bounds.skip,
+        }
+    }
+
+    fn reset_search_state(&self, state: &mut Self::SearchState) {
+        self.inner.reset_search_state(&mut state.inner);
+        state.discarded = 0;
+        state.inner_start = self.bounds.skip;
+    }
+
+    fn force_boundary(&self, state: &mut Self::SearchState) {
+        self.inner.force_boundary(&mut state.inner);
+        state.discarded = 0;
+        state.inner_start = self.bounds.skip;
+    }
+
+    fn find_chunk_edge(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+    ) -> (Option<usize>, usize) {
+        let b = &self.bounds;
+        // offset in the chunk of the end of `data`, limited to the maximum size
+        let end = std::cmp::min(state.discarded + data.len() as u64, b.max);
+
+        while state.inner_start < end {
+            let from = (state.inner_start - state.discarded) as usize;
+            let to = (end - state.discarded) as usize;
+            let (cut, discard_ct) = self
+                .inner
+                .find_chunk_edge(&mut state.inner, &data[from..to]);
+            let cut = cut.map(|i| state.inner_start + i as u64);
+            state.inner_start += discard_ct as u64;
+            match cut {
+                // too small: `inner` has already reset itself, continue after the edge
+                Some(e) if e < b.min => continue,
+                Some(e) => return self.cut(state, e),
+                None => break,
+            }
+        }
+
+        if end == b.max {
+            return self.cut(state, end);
+        }
+
+        // keep the data `inner` hasn't discarded
+        let discard_ct = std::cmp::min(state.inner_start, end) - state.discarded;
+        state.discarded += discard_ct;
+        (None, discard_ct as usize)
+    }
+}
+
+impl<C: Chunk> Bounded<C> {
+    /// Emit a chunk edge at offset `e` in the current chunk
+    fn cut(
+        &self,
+        state: &mut BoundedSearchState<C::SearchState>,
+        e: u64,
+    ) -> (Option<usize>, usize) {
+        let i = (e - state.discarded) as usize;
+        *state = self.to_search_state();
+        (Some(i), i)
+    }
+}
+
+/// Intermediate state for [`Bounded::find_chunk_edge()`]
+#[derive(Debug, Clone)]
+pub struct BoundedSearchState<S> {
+    inner: S,
+
+    /// Number of bytes of the current chunk that were discarded before the next `data`
+    discarded: u64,
+
+    /// Offset in the current chunk of the start of the data `inner` expects next
+    inner_start: u64,
+}
+
+impl<C: ToChunkIncr> ToChunkIncr for Bounded<C> {
+    type Incr = BoundedIncr<C::Incr>;
+
+    fn to_chunk_incr(&self) -> Self::Incr {
+        BoundedIncr {
+            inner: self.inner.to_chunk_incr(),
+            bounds: self.bounds,
+            len: 0,
+        }
+    }
+}
+
+/// Incremental interface to [`Bounded`]
+///
+/// `I` is reset (with [`ChunkIncr::reset()`]) at each chunk edge.
+#[derive(Debug, Clone)]
+pub struct BoundedIncr<I> {
+    inner: I,
+    bounds: Bounds,
+
+    /// Bytes in the current chunk
+    len: u64,
+}
+
+impl<I: ChunkIncr> BoundedIncr<I> {
+    /// Wrap a newly created `inner`, limiting the size of chunks to `range`
+    ///
+    /// See [`Bounded::new()`].
+    pub fn new(inner: I, range: impl RangeBounds<u64>) -> Self {
+        Self::try_new(inner, range).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Wrap a newly created `inner`, limiting the size of chunks to `range`
+    ///
+    /// See [`Bounded::try_new()`].
+    pub fn try_new(inner: I, range: impl RangeBounds<u64>) -> Result<Self, ConfigError> {
+        Ok(BoundedIncr {
+            bounds: Bounds::new(range, None)?,
+            inner,
+            len: 0,
+        })
+    }
+
+    /// Wrap a newly created `inner`, limiting the size of chunks to `range`, and skipping all but
+    /// the last `window` bytes before the minimum size of each chunk
+    ///
+    /// See [`Bounded::with_window()`].
+    pub fn with_window(inner: I, range: impl RangeBounds<u64>, window: u64) -> Self {
+        Self::try_with_window(inner, range, window).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Wrap a newly created `inner`, limiting the size of chunks to `range`, and skipping all but
+    /// the last `window` bytes before the minimum size of each chunk
+    ///
+    /// See [`Bounded::try_with_window()`].
+    pub fn try_with_window(
+        inner: I,
+        range: impl RangeBounds<u64>,
+        window: u64,
+    ) -> Result<Self, ConfigError> {
+        Ok(BoundedIncr {
+            bounds: Bounds::new(range, Some(window))?,
+            inner,
+            len: 0,
+        })
+    }
+
+    fn cut(&mut self, i: usize) -> Option<usize> {
+        self.inner.reset();
+        self.len = 0;
+        Some(i)
+    }
+}
+
+impl<I: ChunkIncr> ChunkIncr for BoundedIncr<I> {
+    fn push(&mut self, data: &[u8]) -> Option<usize> {
+        let b = self.bounds;
+        let mut pos = 0;
+
+        if self.len < b.skip {
+            let n = std::cmp::min(b.skip - self.len, data.len() as u64);
+            self.len += n;
+            pos += n as usize;
+        }
+
+        let end = std::cmp::min(data.len() as u64, pos as u64 + (b.max - self.len)) as usize;
+        while pos < end {
+            match self.inner.push(&data[pos..end]) {
+                Some(i) => {
+                    pos += i;
+                    self.len += i as u64;
+                    if self.len >= b.min {
+                        return self.cut(pos);
+                    }
+                }
+                None => {
+                    self.len += (end - pos) as u64;
+                    pos = end;
+                }
+            }
+        }
+
+        if self.len == b.max {
+            return self.cut(pos);
+        }
+
+        None
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.len = 0;
+    }
+
+    fn force_boundary(&mut self) {
+        self.inner.force_boundary();
+        self.len = 0;
+    }
+}
@@ -0,0 +1,298 @@
+//! Buffering input for [`Chunk::find_chunk_edge()`]
+//!
+//! [`Chunk::find_chunk_edge()`] is the fastest interface to most algorithms, but requires the
+//! caller to keep the data it has not discarded, append new data to it, and map the edges it
+//! returns back to the input. [`ChunkBuffer`] does this bookkeeping: data is appended with
+//! [`ChunkBuffer::push()`], and complete chunks are returned as slices by
+//! [`ChunkBuffer::next_chunk()`].
+//!
+//! ```rust
+//! # #[cfg(feature = "mii")] {
+//! use hash_roll::buffer::ChunkBuffer;
+//!
+//! let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+//! let mut buf = ChunkBuffer::new(hash_roll::mii::Mii::default());
+//! let mut total = 0;
+//! for piece in data.chunks(1000) {
+//!     buf.push(piece);
+//!     while let Some(chunk) = buf.next_chunk() {
+//!         total += chunk.len();
+//!     }
+//! }
+//! if let Some(last) = buf.finish() {
+//!     total += last.len();
+//! }
+//! assert_eq!(total, data.len());
+//! # }
+//! ```
+//!
+//! [`ChunkIncrFromChunk`] instead provides a [`ChunkIncr`] for any [`Chunk`] algorithm, buffering
+//! only the data [`Chunk::find_chunk_edge()`] needs to keep.
+use crate::{Chunk, ChunkIncr};
+
+/// Owns the data being chunked by a [`Chunk`] algorithm, returning each chunk as a slice
+///
+/// Data is only copied once when it is appended, and the data before the current chunk is
+/// released by moving the remainder to the start of the buffer when more space is needed (so only
+/// the data that must be kept is moved). Chunks which span many calls to
+/// [`ChunkBuffer::push()`] are kept in the buffer until they are complete, so the buffer grows to
+/// hold the largest chunk.
+///
+/// See the [module documentation](self) for an example.
+pub struct ChunkBuffer<C: Chunk> {
+    chunker: C,
+    state: C::SearchState,
+    buf: Vec<u8>,
+
+    /// index in `buf` of the start of the chunk not yet returned
+    chunk_start: usize,
+
+    /// index in `buf` of the first byte we still need to pass to `find_chunk_edge()`
+    discard: usize,
+
+    /// whether data has been pushed since `find_chunk_edge()` last found no edge
+    pending: bool,
+}
+
+impl<C: Chunk + std::fmt::Debug> std::fmt::Debug for ChunkBuffer<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkBuffer")
+            .field("chunker", &self.chunker)
+            .field("buf_len", &self.buf.len())
+            .field("chunk_start", &self.chunk_start)
+            .field("discard", &self.discard)
+            .finish()
+    }
+}
+
+impl<C: Chunk> ChunkBuffer<C> {
+    /// Create an empty buffer for chunking a new input with `chunker`
+    pub fn new(chunker: C) -> Self {
+        Self::with_capacity(chunker, 0)
+    }
+
+    /// Create an empty buffer for chunking a new input with `chunker`, with space for `capacity`
+    /// bytes before it needs to grow
+    pub fn with_capacity(chunker: C, capacity: usize) -> Self {
+        ChunkBuffer {
+            state: chunker.to_search_state(),
+            chunker,
+            buf: Vec::with_capacity(capacity),
+            chunk_start: 0,
+            discard: 0,
+            pending: false,
+        }
+    }
+
+    /// The configured algorithm instance
+    pub fn chunker(&self) -> &C {
+        &self.chunker
+    }
+
+    /// Number of bytes held in the buffer that have not been returned as part of a chunk
+    pub fn len(&self) -> usize {
+        self.buf.len() - self.chunk_start
+    }
+
+    /// Whether all bytes pushed have been returned as part of a chunk
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of bytes the buffer can hold without growing
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    /// Append `data` to the input
+    ///
+    /// Chunks ending in `data` are returned by subsequent calls to [`ChunkBuffer::next_chunk()`].
+    /// Any that were not yet returned remain available.
+    pub fn push(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+
+        // only move the retained data when we'd otherwise need to grow (or there is none)
+        let release = std::cmp::min(self.chunk_start, self.discard);
+        if release == self.buf.len() || self.buf.len() + data.len() > self.buf.capacity() {
+            self.compact(release);
+        }
+        self.buf.extend_from_slice(data);
+        self.pending = true;
+    }
+
+    /// Return the next complete chunk, if one ends in the data pushed so far
+    ///
+    /// Returns `None` once the remaining data does not contain a chunk edge. That data is kept
+    /// until more is pushed, or it is returned by [`ChunkBuffer::finish()`].
+    pub fn next_chunk(&mut self) -> Option<&[u8]> {
+        if !self.pending {
+            return None;
+        }
+
+        let (cut, discard_ct) = self
+            .chunker
+            .find_chunk_edge(&mut self.state, &self.buf[self.discard..]);
+        match cut {
+            Some(cut_point) => {
+                // map `cut_point` from the slice we passed back into `buf`
+                let cut_point = self.discard + cut_point;
+                let start = self.chunk_start;
+                self.chunk_start = cut_point;
+                self.discard += discard_ct;
+                Some(&self.buf[start..cut_point])
+            }
+            None => {
+                self.discard += discard_ct;
+                self.pending = false;
+                None
+            }
+        }
+    }
+
+    /// End the input, returning the data following the last chunk (if any) as the final chunk
+    ///
+    /// Chunks not yet returned by [`ChunkBuffer::next_chunk()`] are included in the returned data,
+    /// so `next_chunk()` should be called until it returns `None` first. Afterwards, the buffer is
+    /// empty and may be used for a new input (keeping its allocation).
+    pub fn finish(&mut self) -> Option<&[u8]> {
+        self.chunker.reset_search_state(&mut self.state);
+        self.take_pending()
+    }
+
+    /// End the current chunk after the data pushed so far, restarting the chunker as if a new
+    /// source started there
+    ///
+    /// Like [`ChunkBuffer::finish()`], this returns the data following the last chunk (if any),
+    /// but data pushed afterwards is chunked as part of the same stream. See
+    /// [`Chunk::force_boundary()`].
+    pub fn force_boundary(&mut self) -> Option<&[u8]> {
+        self.chunker.force_boundary(&mut self.state);
+        self.take_pending()
+    }
+
+    /// Return all data not yet returned as a chunk, after the search state has been reset
+    fn take_pending(&mut self) -> Option<&[u8]> {
+        let start = self.chunk_start;
+        let end = self.buf.len();
+        self.chunk_start = end;
+        self.discard = end;
+        self.pending = false;
+        if start == end {
+            None
+        } else {
+            Some(&self.buf[start..end])
+        }
+    }
+
+    /// Release the first `release` bytes of `buf` (which must have been both returned in a chunk
+    /// and discarded by `find_chunk_edge()`), moving the remainder to the start of `buf`
+    fn compact(&mut self, release: usize) {
+        if release == 0 {
+            return;
+        }
+
+        self.buf.copy_within(release.., 0);
+        self.buf.truncate(self.buf.len() - release);
+        self.chunk_start -= release;
+        self.discard -= release;
+    }
+}
+
+/// A [`ChunkIncr`] for any [`Chunk`] algorithm
+///
+/// Data pushed is passed to [`Chunk::find_chunk_edge()`], and only the bytes it does not discard
+/// are kept (for most algorithms, the window of the rolling hash). Data is passed directly
+/// from the slice given to [`ChunkIncr::push()`] while no bytes are kept, and otherwise appended
+/// to the kept bytes first.
+///
+/// This finds the same edges as [`Chunk::find_chunk_edge()`], so new algorithms only need to
+/// implement [`Chunk`] to get a correct incremental interface. Hand-written [`ChunkIncr`]
+/// implementations are checked against it by the `conformance` module (with the `testing` feature).
+///
+/// ```rust
+/// # #[cfg(feature = "mii")] {
+/// use hash_roll::buffer::ChunkIncrFromChunk;
+/// use hash_roll::{ChunkIncr, ToChunkIncr};
+///
+/// let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+/// let mii = hash_roll::mii::Mii::default();
+/// let mut edges = Vec::new();
+/// let mut expected = Vec::new();
+/// let mut adapter = ChunkIncrFromChunk::new(&mii);
+/// let mut incr = mii.to_chunk_incr();
+/// for piece in data.chunks(1000) {
+///     adapter.push_all(piece, &mut edges);
+///     incr.push_all(piece, &mut expected);
+/// }
+/// assert_eq!(edges, expected);
+/// # }
+/// ```
+pub struct ChunkIncrFromChunk<C: Chunk> {
+    chunker: C,
+    state: C::SearchState,
+
+    /// data passed to `find_chunk_edge()` that it has not discarded
+    buf: Vec<u8>,
+}
+
+impl<C: Chunk + std::fmt::Debug> std::fmt::Debug for ChunkIncrFromChunk<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkIncrFromChunk")
+            .field("chunker", &self.chunker)
+            .field("buf_len", &self.buf.len())
+            .finish()
+    }
+}
+
+impl<C: Chunk> ChunkIncrFromChunk<C> {
+    /// Create a [`ChunkIncr`] for a new input chunked by `chunker`
+    pub fn new(chunker: C) -> Self {
+        ChunkIncrFromChunk {
+            state: chunker.to_search_state(),
+            chunker,
+            buf: Vec::new(),
+        }
+    }
+
+    /// The configured algorithm instance
+    pub fn chunker(&self) -> &C {
+        &self.chunker
+    }
+
+    /// Obtain the configured algorithm instance, dropping any state
+    pub fn into_inner(self) -> C {
+        self.chunker
+    }
+}
+
+impl<C: Chunk> ChunkIncr for ChunkIncrFromChunk<C> {
+    fn push(&mut self, data: &[u8]) -> Option<usize> {
+        let prev = self.buf.len();
+        if prev == 0 {
+            let (cut, discard_ct) = self.chunker.find_chunk_edge(&mut self.state, data);
+            let end = cut.unwrap_or(data.len());
+            self.buf.extend_from_slice(&data[discard_ct..end]);
+            return cut;
+        }
+
+        self.buf.extend_from_slice(data);
+        let (cut, discard_ct) = self.chunker.find_chunk_edge(&mut self.state, &self.buf);
+        // the data following an edge is passed again by the caller
+        self.buf.truncate(cut.unwrap_or(self.buf.len()));
+        self.buf.drain(..discard_ct);
+        // `find_chunk_edge()` examined the kept bytes without finding an edge in them
+        cut.map(|i| i - prev)
+    }
+
+    fn reset(&mut self) {
+        self.chunker.reset_search_state(&mut self.state);
+        self.buf.clear();
+    }
+
+    fn force_boundary(&mut self) {
+        self.chunker.force_boundary(&mut self.state);
+        self.buf.clear();
+    }
+}
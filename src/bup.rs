@@ -1,6 +1,12 @@
+#![cfg(feature = "bup")]
+use crate::config::ConfigError;
+use crate::lookback::Lookback;
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SizeDisplay, SpecError};
 use crate::{Chunk, ChunkIncr, ToChunkIncr};
 use std::fmt;
 use std::num::Wrapping;
+use std::str::FromStr;
 
 const BLOBBITS: u8 = 13;
 const BLOBSIZE: u32 = 1 << (BLOBBITS as u32);
@@ -16,10 +22,39 @@ const ROLLSUM_CHAR_OFFSET: usize = 31;
 /// - https://github.com/bup/bup/blob/0ab7c3a958729b4723e6fe254da771aff608c2bf/lib/bup/bupsplit.h
 ///
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RollSumSerde")
+)]
 pub struct RollSum {
     window_len: usize,
 }
 
+/// Serialized form of [`RollSum`], checked by [`RollSum::try_with_window()`] when deserialized
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "RollSum")]
+struct RollSumSerde {
+    window_len: usize,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<RollSumSerde> for RollSum {
+    type Error = ConfigError;
+
+    fn try_from(s: RollSumSerde) -> Result<Self, Self::Error> {
+        RollSum::try_with_window(s.window_len)
+    }
+}
+
+impl CutReasons for RollSum {
+    /// Always [`CutReason::Hash`]
+    fn cut_reason(&self, _len: u64) -> CutReason {
+        CutReason::Hash
+    }
+}
+
 impl ToChunkIncr for RollSum {
     type Incr = RollSumIncr;
 
@@ -29,8 +64,60 @@ impl ToChunkIncr for RollSum {
 }
 
 impl RollSum {
+    /// Create an instance summing the last `window_len` bytes
+    ///
+    /// # Panics
+    ///
+    /// If `window_len` is not a power of 2, or is larger than 2 GiB. See
+    /// [`RollSum::try_with_window()`].
     pub fn with_window(window_len: usize) -> Self {
-        Self { window_len }
+        Self::try_with_window(window_len).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create an instance summing the last `window_len` bytes, or return an error if `window_len`
+    /// is not a power of 2 (like bup's `BUP_WINDOWSIZE`, and as required by the `bup`
+    /// [specification](crate::spec)), or is larger than 2 GiB
+    pub fn try_with_window(window_len: usize) -> Result<Self, ConfigError> {
+        if !window_len.is_power_of_two() || window_len as u64 > 1 << 31 {
+            return Err(ConfigError::invalid(
+                "bup",
+                "window_len",
+                window_len as u64,
+                "must be a power of 2 no larger than 2GiB",
+            ));
+        }
+        Ok(Self { window_len })
+    }
+}
+
+/// Formats as `bup:window=<size>`
+///
+/// See [`crate::spec`] for details.
+impl fmt::Display for RollSum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "bup:window={}", SizeDisplay(self.window_len as u64))
+    }
+}
+
+/// Parses the format emitted by [`RollSum`]'s `Display` impl
+///
+/// `window` must be a power of 2, no larger than [`crate::spec::MAX_WINDOW`].
+impl FromStr for RollSum {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("bup", s)?;
+        let window_len = match p.take("window") {
+            None => WINDOW_SIZE,
+            Some(v) => match crate::spec::parse_size(v) {
+                Some(w) if w.is_power_of_two() && w <= crate::spec::MAX_WINDOW => w as usize,
+                _ => {
+                    return Err(p.invalid("window", v, "must be a power of 2 no larger than 1MiB"))
+                }
+            },
+        };
+        p.finish()?;
+        Ok(RollSum::with_window(window_len))
     }
 }
 
@@ -129,24 +216,21 @@ impl Default for RollSum {
 
 /// Incrimental instance of [`RollSum`]
 ///
-/// Performance note: Bup's Roll sum algorithm requires tracking the entire window. As a result,
-/// this includes a circular buffer which all inputs are copied through. If your use case allows
-/// it, use the non-incrimental variant for improved performance.
+/// Bytes leaving the window are read from the data passed to `push()` where possible, so only the
+/// last `window_len` bytes of each slice are copied into an internal buffer.
 #[derive(Clone, PartialEq, Eq)]
 pub struct RollSumIncr {
     state: RollSumState,
 
-    /// window offset
-    wofs: Wrapping<usize>,
-    window: Box<[u8]>,
+    /// the window is initially all zeros, so bytes before the start are dropped as `0`
+    lookback: Lookback,
 }
 
 impl fmt::Debug for RollSumIncr {
     fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> Result<(), ::std::fmt::Error> {
         f.debug_struct("RollSumIncr")
             .field("state", &self.state)
-            .field("window", &::fmt_extra::Hs(&self.window[..]))
-            .field("wofs", &self.wofs)
+            .field("lookback", &self.lookback)
             .finish()
     }
 }
@@ -155,8 +239,7 @@ impl From<&RollSum> for RollSumIncr {
     fn from(params: &RollSum) -> Self {
         Self {
             state: params.into(),
-            window: vec![0; params.window_len].into_boxed_slice(),
-            wofs: Wrapping(0),
+            lookback: Lookback::new(params.window_len),
         }
     }
 }
@@ -191,14 +274,14 @@ impl RollSumIncr {
     }
 
     fn add(&mut self, drop: u8, add: u8) {
-        self.state.add(self.window.len(), drop, add);
+        self.state.add(self.lookback.window_len(), drop, add);
     }
 
     pub fn roll_byte(&mut self, ch: u8) {
-        let w = self.window[self.wofs.0];
+        let b = [ch];
+        let w = self.lookback.dropped(&b, 0).unwrap_or(0);
         self.add(w, ch);
-        self.window[self.wofs.0] = ch;
-        self.wofs = Wrapping((self.wofs + Wrapping(1)).0 & (self.window.len() - 1));
+        self.lookback.extend(&b);
     }
 
     #[cfg(test)]
@@ -224,14 +307,30 @@ impl RollSumIncr {
 impl ChunkIncr for RollSumIncr {
     fn push(&mut self, data: &[u8]) -> Option<usize> {
         for (i, &v) in data.iter().enumerate() {
-            self.roll_byte(v);
+            let w = self.lookback.dropped(data, i).unwrap_or(0);
+            self.add(w, v);
             if self.at_split() {
+                // like `RollSum::find_chunk_edge()`, the next chunk starts with an empty window
+                self.state = (&RollSum {
+                    window_len: self.lookback.window_len(),
+                })
+                    .into();
+                self.lookback.clear();
                 return Some(i + 1);
             }
         }
 
+        self.lookback.extend(data);
         None
     }
+
+    fn reset(&mut self) {
+        self.state = (&RollSum {
+            window_len: self.lookback.window_len(),
+        })
+            .into();
+        self.lookback.clear();
+    }
 }
 
 #[cfg(test)]
@@ -17,9 +17,13 @@
 //! - [Recursive Hashing Functions for n-Grams, JONATHAN D. COHEN](https://www.csee.umbc.edu/courses/graduate/676/recursivehashingp291-cohen)
 //! - ["Cyclic Polynomial", Rolling Hashes, Wikipedia](https://en.wikipedia.org/wiki/Rolling_hash#cite_ref-3)
 //!
+use crate::config::ConfigError;
+use crate::lookback::Lookback;
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SizeDisplay, SpecError};
 use crate::{Chunk, ChunkIncr, ToChunkIncr};
 use std::fmt;
-use std::num::Wrapping;
+use std::str::FromStr;
 /* Cyclic polynomial (buzhash)
  *
  * H = s ** (k -1) (h(c_1)) ^ s**(k-2)(h(c_2)) ^ ... ^ s(h(c_(k-1))) ^ h(c_k)
@@ -62,9 +66,15 @@ use std::num::Wrapping;
 ///
 /// # Performance
 ///
-/// [`BuzHash`] requires storing bytes equal to it's window size (`k`). Because of this,
-/// [`BuzHashIncr`] may have poor performance compared to [`BuzHash::find_chunk_edge()`].
+/// [`BuzHash`] needs the byte leaving it's window (`k` bytes back) for each byte added.
+/// [`BuzHashIncr`] reads it from the data passed to `push()` where possible, and only keeps the
+/// last `k` bytes of each slice.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "BuzHashSerde<H>")
+)]
 pub struct BuzHash<H: BuzHashHash> {
     /// number of characters to consider at once
     k: usize,
@@ -87,20 +97,96 @@ impl<H: BuzHashHash> BuzHash<H> {
     /// `capacity` is the number of bytes that are taken into account for a given hash.
     /// `mask` affects how chunk edges are determined.
     /// `hash` is applied to each byte of input prior to mixing into the rolling hash.
+    ///
+    /// # Panics
+    ///
+    /// If `capacity` is 0. See [`BuzHash::try_new()`].
     pub fn new(capacity: usize, mask: u32, hash: H, max_chunk_size: u64) -> Self {
-        assert!(capacity > 0);
-        BuzHash {
+        Self::try_new(capacity, mask, hash, max_chunk_size).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create an instance with the given capacity (k) and chunk termination `mask`, and a internal
+    /// `hash` function, or return an error if `capacity` is 0
+    ///
+    /// See [`BuzHash::new()`] for the meaning of the parameters.
+    pub fn try_new(
+        capacity: usize,
+        mask: u32,
+        hash: H,
+        max_chunk_size: u64,
+    ) -> Result<Self, ConfigError> {
+        if capacity == 0 {
+            return Err(ConfigError::invalid(
+                "buzhash",
+                "capacity",
+                0,
+                "must not be zero",
+            ));
+        }
+        Ok(BuzHash {
             k: capacity,
             h: hash,
             mask,
             max_chunk_size,
-        }
+        })
     }
 
     // fn new_attic()
     // fn new_bup()
 }
 
+/// Serialized form of [`BuzHash`], checked by [`BuzHash::try_new()`] when deserialized
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "BuzHash")]
+struct BuzHashSerde<H> {
+    k: usize,
+    h: H,
+    mask: u32,
+    max_chunk_size: u64,
+}
+
+#[cfg(feature = "serde")]
+impl<H: BuzHashHash> std::convert::TryFrom<BuzHashSerde<H>> for BuzHash<H> {
+    type Error = ConfigError;
+
+    fn try_from(s: BuzHashSerde<H>) -> Result<Self, Self::Error> {
+        BuzHash::try_new(s.k, s.mask, s.h, s.max_chunk_size)
+    }
+}
+
+/// Parameters shared by [`BuzHash`] and [`BuzHashN`], so both use the same hashing and chunking
+/// code
+trait BuzHashParams {
+    type Hash: BuzHashHash;
+
+    /// Window size
+    fn k(&self) -> usize;
+    fn hash(&self) -> &Self::Hash;
+    fn mask(&self) -> u32;
+    fn max_chunk_size(&self) -> u64;
+}
+
+impl<H: BuzHashHash> BuzHashParams for BuzHash<H> {
+    type Hash = H;
+
+    fn k(&self) -> usize {
+        self.k
+    }
+
+    fn hash(&self) -> &H {
+        &self.h
+    }
+
+    fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    fn max_chunk_size(&self) -> u64 {
+        self.max_chunk_size
+    }
+}
+
 impl<'a> BuzHash<BuzHashTableByteSaltHash<'a>> {
     /// Create a buzhash instance using defaults from attic-labs/nom version 7.17
     ///
@@ -118,6 +204,65 @@ impl<'a> BuzHash<BuzHashTableByteSaltHash<'a>> {
     }
 }
 
+/// Formats as `buzhash:k=<k>,mask=<mask>,max=<size>,table=<table>,salt=<salt>`
+///
+/// The only named table is `nom` ([`crate::buzhash_table::GO_BUZHASH`]).
+///
+/// See [`crate::spec`] for details.
+impl<'a> fmt::Display for BuzHash<BuzHashTableByteSaltHash<'a>> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let table = if self.h.table[..] == crate::buzhash_table::GO_BUZHASH[..] {
+            "nom"
+        } else {
+            "custom"
+        };
+        write!(
+            f,
+            "buzhash:k={},mask={:#x},max={},table={},salt={}",
+            self.k,
+            self.mask,
+            SizeDisplay(self.max_chunk_size),
+            table,
+            self.h.salt
+        )
+    }
+}
+
+/// Parses the format emitted by [`BuzHash`]'s `Display` impl
+///
+/// Parameters that are not given use the values from [`BuzHash::new_nom()`] (with a salt of `0`).
+/// `k` must not be zero, or larger than [`crate::spec::MAX_WINDOW`].
+impl<'a> FromStr for BuzHash<BuzHashTableByteSaltHash<'a>> {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("buzhash", s)?;
+        let d = BuzHash::new_nom(0);
+        let k = match p.take_int("k", crate::spec::MAX_WINDOW)? {
+            None => d.k,
+            Some(0) => return Err(p.invalid("k", "0", "must not be zero")),
+            Some(k) => k as usize,
+        };
+        let mask = p
+            .take_int("mask", u32::MAX as u64)?
+            .map_or(d.mask, |m| m as u32);
+        let max_chunk_size = p.take_size("max")?.unwrap_or(d.max_chunk_size);
+        match p.take("table") {
+            None | Some("nom") => {}
+            Some(v) => return Err(p.invalid("table", v, "unknown table")),
+        }
+        let salt = p.take_int("salt", u8::MAX as u64)?.unwrap_or(0) as u8;
+        p.finish()?;
+
+        Ok(BuzHash::new(
+            k,
+            mask,
+            BuzHashTableByteSaltHash::from((salt, &crate::buzhash_table::GO_BUZHASH)),
+            max_chunk_size,
+        ))
+    }
+}
+
 impl<H: BuzHashHash + Clone> Chunk for BuzHash<H> {
     type SearchState = BuzHashSearchState;
 
@@ -130,29 +275,179 @@ impl<H: BuzHashHash + Clone> Chunk for BuzHash<H> {
         state: &mut Self::SearchState,
         data: &[u8],
     ) -> (Option<usize>, usize) {
-        for i in state.offset..data.len() {
-            state.state.add_buf(data, self, i);
+        state.find_chunk_edge(self, data)
+    }
+}
 
-            if (state.state.h & self.mask) == self.mask {
-                state.reset();
-                return (Some(i + 1), i + 1);
-            }
+#[cfg(feature = "buzhash-n")]
+/// [`BuzHash`] with a window size (`K`) fixed at compile time
+///
+/// Produces the same chunk edges as a [`BuzHash`] with `k = K` and the same `mask`, hash, and
+/// `max_chunk_size`, and can be converted into one with `From`.
+///
+/// [`BuzHashNIncr`] stores its window in an array, so creating one does not allocate. Rotation
+/// amounts derived from the window size are constants.
+///
+/// ```rust
+/// use hash_roll::buzhash::{BuzHash, BuzHashN};
+/// use hash_roll::Chunk;
+///
+/// let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+/// let fixed = BuzHashN::<67, _>::new_nom(0);
+/// let dynamic = BuzHash::new_nom(0);
+/// assert!(fixed.chunk_ranges(&data).eq(dynamic.chunk_ranges(&data)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuzHashN<const K: usize, H: BuzHashHash> {
+    /// A hash function over a single byte that emits a 32-bit value
+    h: H,
 
-            /*
-             * broken: `i` is not the number of bytes since prev chunk.
-             * need to track internal last chunk
-            if i as u64 > self.max_chunk_size {
-                state.reset();
-                println!(" <- CHUNK: {}", i + 1);
-                return (Some(i + 1), i + 1);
-            }
-            */
+    /// the 1 bits indicates the bit in the hash which must be 1 to form a chunk edge
+    mask: u32,
+
+    /// if the index grows _above_ this size, a chunk edge is formed
+    max_chunk_size: u64,
+}
+
+#[cfg(feature = "buzhash-n")]
+impl<const K: usize, H: BuzHashHash> BuzHashN<K, H> {
+    /// Create an instance with the chunk termination `mask` and internal `hash` function
+    ///
+    /// See [`BuzHash::new()`] for the meaning of the parameters.
+    ///
+    /// # Panics
+    ///
+    /// If `K` is 0. See [`BuzHashN::try_new()`].
+    pub fn new(mask: u32, hash: H, max_chunk_size: u64) -> Self {
+        Self::try_new(mask, hash, max_chunk_size).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create an instance with the chunk termination `mask` and internal `hash` function, or
+    /// return an error if `K` is 0
+    pub fn try_new(mask: u32, hash: H, max_chunk_size: u64) -> Result<Self, ConfigError> {
+        if K == 0 {
+            return Err(ConfigError::invalid("buzhash", "K", 0, "must not be zero"));
         }
+        Ok(BuzHashN {
+            h: hash,
+            mask,
+            max_chunk_size,
+        })
+    }
+}
 
-        // keep k elements = discard all but k
-        let discard_ct = data.len().saturating_sub(self.k);
-        state.offset = data.len() - discard_ct;
-        (None, discard_ct)
+#[cfg(feature = "buzhash-n")]
+impl<'a> BuzHashN<67, BuzHashTableByteSaltHash<'a>> {
+    /// Create a buzhash instance using defaults from attic-labs/nom version 7.17
+    ///
+    /// Equivalent to [`BuzHash::new_nom()`].
+    pub fn new_nom(salt: u8) -> Self {
+        BuzHashN::new(
+            (1 << 12u32) - 1,
+            BuzHashTableByteSaltHash::from((salt, &crate::buzhash_table::GO_BUZHASH)),
+            1 << 24,
+        )
+    }
+}
+
+#[cfg(feature = "buzhash-n")]
+impl<const K: usize, H: BuzHashHash> From<BuzHashN<K, H>> for BuzHash<H> {
+    fn from(src: BuzHashN<K, H>) -> Self {
+        BuzHash::new(K, src.mask, src.h, src.max_chunk_size)
+    }
+}
+
+#[cfg(feature = "buzhash-n")]
+impl<const K: usize, H: BuzHashHash> BuzHashParams for BuzHashN<K, H> {
+    type Hash = H;
+
+    fn k(&self) -> usize {
+        K
+    }
+
+    fn hash(&self) -> &H {
+        &self.h
+    }
+
+    fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    fn max_chunk_size(&self) -> u64 {
+        self.max_chunk_size
+    }
+}
+
+#[cfg(feature = "buzhash-n")]
+impl<const K: usize, H: BuzHashHash + Clone> Chunk for BuzHashN<K, H> {
+    type SearchState = BuzHashSearchState;
+
+    fn to_search_state(&self) -> Self::SearchState {
+        Self::SearchState::default()
+    }
+
+    fn find_chunk_edge(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+    ) -> (Option<usize>, usize) {
+        state.find_chunk_edge(self, data)
+    }
+}
+
+#[cfg(feature = "buzhash-n")]
+impl<const K: usize, H: BuzHashHash> CutReasons for BuzHashN<K, H> {
+    fn cut_reason(&self, len: u64) -> CutReason {
+        if len > self.max_chunk_size {
+            CutReason::MaxSize
+        } else {
+            CutReason::Hash
+        }
+    }
+}
+
+#[cfg(feature = "buzhash-n")]
+impl<const K: usize, H: BuzHashHash + Clone> ToChunkIncr for BuzHashN<K, H> {
+    type Incr = BuzHashNIncr<K, H>;
+    fn to_chunk_incr(&self) -> Self::Incr {
+        self.clone().into()
+    }
+}
+
+#[cfg(feature = "buzhash-n")]
+/// Incremental interface to [`BuzHashN`], keeping its window in an array
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuzHashNIncr<const K: usize, H: BuzHashHash> {
+    params: BuzHashN<K, H>,
+    state: BuzHashState,
+    lookback: Lookback<[u8; K]>,
+    input_idx: u64,
+}
+
+#[cfg(feature = "buzhash-n")]
+impl<const K: usize, H: BuzHashHash> ChunkIncr for BuzHashNIncr<K, H> {
+    /// Return the index in `data` immeidately following the hash matching.
+    fn push(&mut self, data: &[u8]) -> Option<usize> {
+        self.state
+            .push(&self.params, &mut self.lookback, &mut self.input_idx, data)
+    }
+
+    fn reset(&mut self) {
+        self.state.reset();
+        self.lookback.clear();
+        self.input_idx = 0;
+    }
+}
+
+#[cfg(feature = "buzhash-n")]
+impl<const K: usize, H: BuzHashHash> From<BuzHashN<K, H>> for BuzHashNIncr<K, H> {
+    fn from(params: BuzHashN<K, H>) -> Self {
+        Self {
+            params,
+            state: Default::default(),
+            lookback: Lookback::with_buf([0; K]),
+            input_idx: 0,
+        }
     }
 }
 
@@ -162,6 +457,16 @@ impl<H: BuzHashHash + Clone> From<&BuzHash<H>> for BuzHashIncr<H> {
     }
 }
 
+impl<H: BuzHashHash> CutReasons for BuzHash<H> {
+    fn cut_reason(&self, len: u64) -> CutReason {
+        if len > self.max_chunk_size {
+            CutReason::MaxSize
+        } else {
+            CutReason::Hash
+        }
+    }
+}
+
 impl<H: BuzHashHash + Clone> ToChunkIncr for BuzHash<H> {
     type Incr = BuzHashIncr<H>;
     fn to_chunk_incr(&self) -> Self::Incr {
@@ -173,13 +478,40 @@ impl<H: BuzHashHash + Clone> ToChunkIncr for BuzHash<H> {
 pub struct BuzHashSearchState {
     offset: usize,
     state: BuzHashState,
+
+    /// number of bytes since the last edge
+    input_idx: u64,
 }
 
 impl BuzHashSearchState {
     fn reset(&mut self) {
         self.offset = 0;
+        self.input_idx = 0;
         self.state.reset();
     }
+
+    fn find_chunk_edge<P: BuzHashParams>(
+        &mut self,
+        params: &P,
+        data: &[u8],
+    ) -> (Option<usize>, usize) {
+        for i in self.offset..data.len() {
+            self.state.add_buf(data, params, i);
+            self.input_idx += 1;
+
+            if (self.state.h & params.mask()) == params.mask()
+                || self.input_idx > params.max_chunk_size()
+            {
+                self.reset();
+                return (Some(i + 1), i + 1);
+            }
+        }
+
+        // keep k elements = discard all but k
+        let discard_ct = data.len().saturating_sub(params.k());
+        self.offset = data.len() - discard_ct;
+        (None, discard_ct)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
@@ -193,10 +525,10 @@ impl BuzHashState {
         self.h = 0;
     }
 
-    fn add_buf<H: BuzHashHash>(&mut self, data: &[u8], params: &BuzHash<H>, i: usize) {
-        if i >= params.k {
+    fn add_buf<P: BuzHashParams>(&mut self, data: &[u8], params: &P, i: usize) {
+        if i >= params.k() {
             // need to find and "remove" a entry
-            let drop_i = i - params.k;
+            let drop_i = i - params.k();
             let drop = data[drop_i];
             self.add_overflow(params, data[i], drop);
         } else {
@@ -205,17 +537,49 @@ impl BuzHashState {
         }
     }
 
+    /// [`ChunkIncr::push()`] for [`BuzHashIncr`] and [`BuzHashNIncr`]
+    ///
+    /// `input_idx` is the number of bytes since the last edge.
+    fn push<P: BuzHashParams, B: AsRef<[u8]> + AsMut<[u8]>>(
+        &mut self,
+        params: &P,
+        lookback: &mut Lookback<B>,
+        input_idx: &mut u64,
+        data: &[u8],
+    ) -> Option<usize> {
+        for (i, &v) in data.iter().enumerate() {
+            match lookback.dropped(data, i) {
+                Some(o) => self.add_overflow(params, v, o),
+                None => self.add(params, v),
+            }
+            *input_idx += 1;
+
+            if (self.h & params.mask()) == params.mask() || *input_idx > params.max_chunk_size() {
+                lookback.clear();
+                *input_idx = 0;
+                self.reset();
+                return Some(i + 1);
+            }
+        }
+
+        lookback.extend(data);
+        None
+    }
+
     // insert, assuming no overflow
-    fn add<H: BuzHashHash>(&mut self, params: &BuzHash<H>, v: u8) {
-        self.h = self.h.rotate_left(1) ^ params.h.hash(v);
+    fn add<P: BuzHashParams>(&mut self, params: &P, v: u8) {
+        self.h = self.h.rotate_left(1) ^ params.hash().hash(v);
     }
 
     // insert with overflow
-    fn add_overflow<H: BuzHashHash>(&mut self, params: &BuzHash<H>, add_v: u8, remove_v: u8) {
+    fn add_overflow<P: BuzHashParams>(&mut self, params: &P, add_v: u8, remove_v: u8) {
         let h = self.h.rotate_left(1);
         // need to find and "remove" a entry
-        let drop = params.h.hash(remove_v).rotate_left((params.k % 8) as u32);
-        self.h = h ^ drop ^ params.h.hash(add_v);
+        let drop = params
+            .hash()
+            .hash(remove_v)
+            .rotate_left((params.k() % 8) as u32);
+        self.h = h ^ drop ^ params.hash().hash(add_v);
     }
 }
 
@@ -227,8 +591,7 @@ impl BuzHashState {
 pub struct BuzHashIncr<H: BuzHashHash> {
     params: BuzHash<H>,
     state: BuzHashState,
-    buf: Box<[u8]>,
-    buf_idx: Wrapping<usize>,
+    lookback: Lookback,
     input_idx: u64,
 }
 
@@ -238,54 +601,24 @@ impl<H: BuzHashHash> ChunkIncr for BuzHashIncr<H> {
     /// Note that you can call this multiple times to examine "subsequent" `data` slices, but the
     /// index returned will always refer to the current `data` slice.
     fn push(&mut self, data: &[u8]) -> Option<usize> {
-        for (i, &v) in data.iter().enumerate() {
-            self.push_byte(v);
-            if (self.state.h & self.params.mask) == self.params.mask {
-                self.reset();
-                return Some(i + 1);
-            }
-
-            if self.input_idx > self.params.max_chunk_size {
-                self.reset();
-                return Some(i + 1);
-            }
-        }
-
-        None
+        self.state
+            .push(&self.params, &mut self.lookback, &mut self.input_idx, data)
     }
-}
 
-impl<H: BuzHashHash> BuzHashIncr<H> {
     fn reset(&mut self) {
-        self.buf_idx = Wrapping(0);
-        self.input_idx = 0;
         self.state.reset();
-    }
-
-    fn push_byte(&mut self, val: u8) {
-        if self.input_idx >= self.params.k as u64 {
-            let o = self.buf[self.buf_idx.0];
-            self.state.add_overflow(&self.params, val, o);
-        } else {
-            self.state.add(&self.params, val);
-        }
-
-        self.buf[self.buf_idx.0] = val;
-
-        self.buf_idx += Wrapping(1);
-        self.buf_idx.0 %= self.params.k;
-        self.input_idx += 1;
+        self.lookback.clear();
+        self.input_idx = 0;
     }
 }
 
 impl<H: BuzHashHash> From<BuzHash<H>> for BuzHashIncr<H> {
     fn from(params: BuzHash<H>) -> Self {
-        let buf = vec![0; params.k].into_boxed_slice();
+        let lookback = Lookback::new(params.k);
         Self {
             params,
             state: Default::default(),
-            buf,
-            buf_idx: Wrapping(0),
+            lookback,
             input_idx: 0,
         }
     }
@@ -370,3 +703,100 @@ impl<'a> BuzHashHash for BuzHashTableByteSaltHash<'a> {
         self.table[(data ^ self.salt) as usize]
     }
 }
+
+#[cfg(feature = "serde")]
+const KNOWN_TABLES: &[crate::serde_table::KnownTable<u32>] =
+    &[("nom", &crate::buzhash_table::GO_BUZHASH)];
+
+/// Serialized form of the table based [`BuzHashHash`] implimentations
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct BuzHashTableSerde {
+    table: crate::serde_table::TableRepr<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    salt: Option<u8>,
+}
+
+/// The table is serialized by name (`nom`) if it is [`crate::buzhash_table::GO_BUZHASH`], and by
+/// value otherwise. Only [`crate::buzhash_table::GO_BUZHASH`] can be deserialized, as the table is
+/// borrowed.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for BuzHashTableHash<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BuzHashTableSerde {
+            table: crate::serde_table::TableRepr::from_table(self.table, KNOWN_TABLES),
+            salt: None,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'a> serde::Deserialize<'de> for BuzHashTableHash<'a> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let s = BuzHashTableSerde::deserialize(deserializer)?;
+        if s.salt.is_some() {
+            return Err(D::Error::unknown_field("salt", &["table"]));
+        }
+        Ok(Self {
+            table: s.table.to_static(KNOWN_TABLES).map_err(D::Error::custom)?,
+        })
+    }
+}
+
+/// The table is serialized by name (`nom`) if it is [`crate::buzhash_table::GO_BUZHASH`], and by
+/// value otherwise. Any table can be deserialized.
+#[cfg(feature = "serde")]
+impl serde::Serialize for BuzHashTableBufHash {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BuzHashTableSerde {
+            table: crate::serde_table::TableRepr::from_table(&self.table, KNOWN_TABLES),
+            salt: None,
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BuzHashTableBufHash {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let s = BuzHashTableSerde::deserialize(deserializer)?;
+        if s.salt.is_some() {
+            return Err(D::Error::unknown_field("salt", &["table"]));
+        }
+        Ok(Self {
+            table: s
+                .table
+                .to_owned_table(KNOWN_TABLES)
+                .map_err(D::Error::custom)?,
+        })
+    }
+}
+
+/// The table is serialized by name (`nom`) if it is [`crate::buzhash_table::GO_BUZHASH`], and by
+/// value otherwise. Only [`crate::buzhash_table::GO_BUZHASH`] can be deserialized, as the table is
+/// borrowed.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for BuzHashTableByteSaltHash<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BuzHashTableSerde {
+            table: crate::serde_table::TableRepr::from_table(self.table, KNOWN_TABLES),
+            salt: Some(self.salt),
+        }
+        .serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, 'a> serde::Deserialize<'de> for BuzHashTableByteSaltHash<'a> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let s = BuzHashTableSerde::deserialize(deserializer)?;
+        Ok(Self {
+            table: s.table.to_static(KNOWN_TABLES).map_err(D::Error::custom)?,
+            salt: s.salt.unwrap_or(0),
+        })
+    }
+}
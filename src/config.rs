@@ -0,0 +1,116 @@
+//! Errors from configuring algorithm instances
+//!
+//! Constructors that take parameters an algorithm can't use panic. Each of them has a `try_`
+//! prefixed equivalent (like [`crate::zstd::Zstd::try_with_target_section_size()`]) that returns a
+//! [`ConfigError`] naming the parameter instead, for use with parameters that aren't known to be
+//! valid (for example, ones read from a configuration file).
+//!
+//! ```rust
+//! # #[cfg(feature = "gear")] {
+//! use hash_roll::gear::Gear32;
+//!
+//! let err = Gear32::try_with_average_size_log2(32).unwrap_err();
+//! assert_eq!(err.param(), "average_size_log2");
+//! assert!(Gear32::try_with_average_size_log2(13).is_ok());
+//! # }
+//! ```
+use std::fmt;
+use std::ops::{Bound, RangeBounds};
+
+/// Error from configuring an algorithm instance with parameters it can't use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// A parameter is outside of the values the algorithm supports
+    InvalidValue {
+        algorithm: &'static str,
+        param: &'static str,
+        value: u64,
+        reason: &'static str,
+    },
+
+    /// A range of chunk sizes is empty, or only contains 0
+    EmptyRange {
+        algorithm: &'static str,
+        param: &'static str,
+    },
+}
+
+impl ConfigError {
+    /// The algorithm being configured
+    pub fn algorithm(&self) -> &'static str {
+        match self {
+            ConfigError::InvalidValue { algorithm, .. }
+            | ConfigError::EmptyRange { algorithm, .. } => algorithm,
+        }
+    }
+
+    /// The name of the parameter that can't be used, as named by the constructor
+    pub fn param(&self) -> &'static str {
+        match self {
+            ConfigError::InvalidValue { param, .. } | ConfigError::EmptyRange { param, .. } => {
+                param
+            }
+        }
+    }
+
+    pub(crate) fn invalid(
+        algorithm: &'static str,
+        param: &'static str,
+        value: u64,
+        reason: &'static str,
+    ) -> Self {
+        ConfigError::InvalidValue {
+            algorithm,
+            param,
+            value,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::InvalidValue {
+                algorithm,
+                param,
+                value,
+                reason,
+            } => write!(
+                f,
+                "{}: invalid value `{}` for `{}`: {}",
+                algorithm, value, param, reason
+            ),
+            ConfigError::EmptyRange { algorithm, param } => write!(
+                f,
+                "{}: `{}` must contain a chunk size other than 0",
+                algorithm, param
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Convert `range` into inclusive minimum and maximum chunk sizes, rejecting ranges which contain
+/// no sizes other than 0
+pub(crate) fn size_range(
+    algorithm: &'static str,
+    param: &'static str,
+    range: impl RangeBounds<u64>,
+) -> Result<(u64, u64), ConfigError> {
+    let min = match range.start_bound() {
+        Bound::Included(&v) => Some(v),
+        Bound::Excluded(&v) => v.checked_add(1),
+        Bound::Unbounded => Some(0),
+    };
+    let max = match range.end_bound() {
+        Bound::Included(&v) => Some(v),
+        Bound::Excluded(&v) => v.checked_sub(1),
+        Bound::Unbounded => Some(u64::MAX),
+    };
+    match (min, max) {
+        (Some(min), Some(max)) if max > 0 && min <= max => Ok((min, max)),
+        _ => Err(ConfigError::EmptyRange { algorithm, param }),
+    }
+}
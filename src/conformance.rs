@@ -0,0 +1,349 @@
+#![cfg(feature = "testing")]
+
+//! Checks that an algorithm's interfaces agree with each other
+//!
+//! Every algorithm in this crate (and any implemented outside of it) should find the same chunk
+//! edges regardless of how its input is provided. This module checks three properties:
+//!
+//!  1. The same edges are found however the input is split into buffers, both by [`Chunk`]
+//!     (appending each buffer to the data not yet discarded) and by [`ChunkIncr`] (pushing each
+//!     buffer in turn).
+//!  2. [`Chunk`] and [`ChunkIncr`] find the same edges, including through their
+//!     [`Chunk::find_chunk_edges()`] and [`ChunkIncr::push_all()`] variants. The algorithm's own
+//!     [`ChunkIncr`] also finds the same edges as [`ChunkIncrFromChunk`], which drives
+//!     [`Chunk::find_chunk_edge()`] directly.
+//!  3. State is reset after each edge: chunking the data following an edge with a new instance
+//!     finds the same edges.
+//!
+//! Algorithms which deliberately carry state from one chunk to the next (like [`crate::zstd`],
+//! whose rolling hash continues across edges, and [`crate::runs::Runs`], which remains in a run
+//! across the edges it places within it) don't have the third property. They return `false` from
+//! [`Chunk::resets_at_edges()`]. For those with a [`Chunk::edge_window()`], [`check_reset()`]
+//! instead checks that a new instance given the data from that many bytes before the end of a
+//! chunk finds the same edges, and skips the others.
+//!
+//! Each check panics with a description of the divergence, so they are intended to be called
+//! from tests. [`check()`] runs all of them.
+//!
+//! ```rust
+//! let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+//! hash_roll::conformance::check(&hash_roll::mii::Mii::default(), &data);
+//! ```
+//!
+//! Edges are reported as offsets in the entire input, and each edge is the end of a chunk (so an
+//! edge at `n` means the chunk ends after `data[n - 1]`).
+use crate::buffer::ChunkIncrFromChunk;
+use crate::{Chunk, ChunkIncr, ToChunkIncr};
+
+/// Largest buffer sizes used by [`check_splits()`]
+const PIECE_MAX: &[usize] = &[1, 7, 300, 5000, 1 << 16];
+
+/// Number of edges [`check_reset()`] restarts from
+const RESET_EDGES: usize = 16;
+
+/// Check all of the properties on `data`
+pub fn check<C: Chunk + ToChunkIncr>(chunker: &C, data: &[u8]) {
+    check_incr_matches_chunk(chunker, data);
+    check_splits(chunker, data);
+    check_reset(chunker, data);
+}
+
+/// Check that splitting `data` into buffers of various sizes (including empty ones) doesn't
+/// change the edges found by any of the interfaces
+pub fn check_splits<C: Chunk + ToChunkIncr>(chunker: &C, data: &[u8]) {
+    let chunk = chunk_edges(chunker, &[data]);
+    let chunk_all = chunk_edges_all(chunker, &[data]);
+    let incr = incr_edges(chunker.to_chunk_incr(), &[data]);
+    let incr_all = incr_edges_all(chunker.to_chunk_incr(), &[data]);
+
+    for (seed, &max) in PIECE_MAX.iter().enumerate() {
+        let p = pieces(data, max, seed as u64);
+        assert_eq!(
+            chunk_edges(chunker, &p),
+            chunk,
+            "find_chunk_edge() with buffers of up to {} bytes",
+            max
+        );
+        assert_eq!(
+            chunk_edges_all(chunker, &p),
+            chunk_all,
+            "find_chunk_edges() with buffers of up to {} bytes",
+            max
+        );
+        assert_eq!(
+            incr_edges(chunker.to_chunk_incr(), &p),
+            incr,
+            "push() with buffers of up to {} bytes",
+            max
+        );
+        assert_eq!(
+            incr_edges_all(chunker.to_chunk_incr(), &p),
+            incr_all,
+            "push_all() with buffers of up to {} bytes",
+            max
+        );
+        assert_eq!(
+            incr_edges(ChunkIncrFromChunk::new(chunker), &p),
+            incr,
+            "ChunkIncrFromChunk differs from push() with buffers of up to {} bytes",
+            max
+        );
+    }
+}
+
+/// Check that [`Chunk`] and [`ChunkIncr`] find the same edges in `data`
+pub fn check_incr_matches_chunk<C: Chunk + ToChunkIncr>(chunker: &C, data: &[u8]) {
+    let expected = chunk_edges(chunker, &[data]);
+    assert_eq!(
+        chunk_edges_all(chunker, &[data]),
+        expected,
+        "find_chunk_edges() differs from find_chunk_edge()"
+    );
+    assert_eq!(
+        incr_edges(chunker.to_chunk_incr(), &[data]),
+        expected,
+        "push() differs from find_chunk_edge()"
+    );
+    assert_eq!(
+        incr_edges_all(chunker.to_chunk_incr(), &[data]),
+        expected,
+        "push_all() differs from find_chunk_edge()"
+    );
+    assert_eq!(
+        incr_edges(ChunkIncrFromChunk::new(chunker), &[data]),
+        expected,
+        "ChunkIncrFromChunk differs from find_chunk_edge()"
+    );
+
+    let ends: Vec<_> = chunker.chunk_ranges(data).map(|r| r.end).collect();
+    let complete = if expected.last() == Some(&data.len()) || data.is_empty() {
+        &ends[..]
+    } else {
+        &ends[..ends.len() - 1]
+    };
+    assert_eq!(complete, &expected[..], "chunk_ranges() differs");
+}
+
+/// Check that the edges following each of the first few edges in `data` are found by new
+/// instances given only the data following that edge
+///
+/// If [`Chunk::resets_at_edges()`] is `false`, new instances are instead given the data from
+/// [`Chunk::edge_window()`] bytes before the last byte of each chunk, and nothing is checked if
+/// that is `None`.
+pub fn check_reset<C: Chunk + ToChunkIncr>(chunker: &C, data: &[u8]) {
+    if !chunker.resets_at_edges() {
+        if let Some(window) = chunker.edge_window() {
+            check_window(chunker, data, window);
+        }
+        return;
+    }
+
+    let chunk = chunk_edges(chunker, &[data]);
+    let incr = incr_edges(chunker.to_chunk_incr(), &[data]);
+
+    for (n, &e) in chunk.iter().enumerate().take(RESET_EDGES) {
+        let rest: Vec<_> = chunk[n + 1..].iter().map(|x| x - e).collect();
+        assert_eq!(
+            chunk_edges(chunker, &[&data[e..]]),
+            rest,
+            "find_chunk_edge() state is not reset after the edge at {}",
+            e
+        );
+    }
+
+    for (n, &e) in incr.iter().enumerate().take(RESET_EDGES) {
+        let rest: Vec<_> = incr[n + 1..].iter().map(|x| x - e).collect();
+        assert_eq!(
+            incr_edges(chunker.to_chunk_incr(), &[&data[e..]]),
+            rest,
+            "push() state is not reset after the edge at {}",
+            e
+        );
+    }
+}
+
+/// Check that each of the first few edges more than `window` bytes into `data`, and the edges
+/// following it, are found by new instances starting `window` bytes before the last byte of the
+/// chunk it ends
+fn check_window<C: Chunk + ToChunkIncr>(chunker: &C, data: &[u8], window: usize) {
+    let chunk = chunk_edges(chunker, &[data]);
+    for (n, &e) in chunk
+        .iter()
+        .enumerate()
+        .filter(|&(_, &e)| e > window)
+        .take(RESET_EDGES)
+    {
+        let from = e - 1 - window;
+        let rest: Vec<_> = chunk[n..].iter().map(|x| x - from).collect();
+        let found: Vec<_> = chunk_edges(chunker, &[&data[from..]])
+            .into_iter()
+            .filter(|&x| x > window)
+            .collect();
+        assert_eq!(
+            found, rest,
+            "find_chunk_edge() depends on more than the {} bytes before the edge at {}",
+            window, e
+        );
+    }
+}
+
+/// Split `data` into pieces of between 0 and `max` bytes
+///
+/// The sizes are pseudo-random, determined by `seed`.
+pub fn pieces(data: &[u8], max: usize, seed: u64) -> Vec<&[u8]> {
+    // xorshift64*, which is plenty for picking sizes
+    let mut x = seed.wrapping_mul(0x9e37_79b9_7f4a_7c15) | 1;
+    let mut pieces = Vec::new();
+    let mut rem = data;
+    while !rem.is_empty() {
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        let r = x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32;
+        let l = std::cmp::min((r % (max as u64 + 1)) as usize, rem.len());
+        pieces.push(&rem[..l]);
+        rem = &rem[l..];
+    }
+    pieces
+}
+
+/// Edges found by calling [`Chunk::find_chunk_edge()`], appending each piece to a buffer of the
+/// data not yet discarded
+///
+/// # Panics
+///
+/// If an edge or `discard_ct` is outside the data, or an edge does not follow the previous one
+pub fn chunk_edges<C: Chunk>(chunker: &C, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut state = chunker.to_search_state();
+    let mut edges = Vec::new();
+    let mut buf = Vec::new();
+    // offset in the entire input of `buf[0]`
+    let mut buf_offset = 0;
+    for piece in pieces {
+        buf.extend_from_slice(piece);
+        loop {
+            let (cut, discard_ct) = chunker.find_chunk_edge(&mut state, &buf);
+            assert!(
+                discard_ct <= buf.len(),
+                "discard_ct {} exceeds the {} bytes passed",
+                discard_ct,
+                buf.len()
+            );
+            if let Some(cut) = cut {
+                assert!(
+                    cut <= buf.len(),
+                    "edge {} exceeds the {} bytes passed",
+                    cut,
+                    buf.len()
+                );
+                push_edge(&mut edges, buf_offset + cut);
+            }
+            buf.drain(..discard_ct);
+            buf_offset += discard_ct;
+            if cut.is_none() {
+                break;
+            }
+        }
+    }
+    edges
+}
+
+/// Edges found by calling [`Chunk::find_chunk_edges()`], appending each piece to a buffer of the
+/// data not yet discarded
+///
+/// # Panics
+///
+/// If an edge or `discard_ct` is outside the data, or an edge does not follow the previous one
+pub fn chunk_edges_all<C: Chunk>(chunker: &C, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut state = chunker.to_search_state();
+    let mut edges = Vec::new();
+    let mut buf = Vec::new();
+    let mut buf_offset = 0;
+    let mut out = Vec::new();
+    for piece in pieces {
+        buf.extend_from_slice(piece);
+        out.clear();
+        let discard_ct = chunker.find_chunk_edges(&mut state, &buf, &mut out);
+        assert!(
+            discard_ct <= buf.len(),
+            "discard_ct {} exceeds the {} bytes passed",
+            discard_ct,
+            buf.len()
+        );
+        for &cut in &out {
+            assert!(
+                cut <= buf.len(),
+                "edge {} exceeds the {} bytes passed",
+                cut,
+                buf.len()
+            );
+            push_edge(&mut edges, buf_offset + cut);
+        }
+        buf.drain(..discard_ct);
+        buf_offset += discard_ct;
+    }
+    edges
+}
+
+/// Edges found by calling [`ChunkIncr::push()`] repeatedly on each piece
+///
+/// # Panics
+///
+/// If an edge is outside the data, or does not follow the previous one
+pub fn incr_edges<I: ChunkIncr>(mut incr: I, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut edges = Vec::new();
+    let mut offset = 0;
+    for piece in pieces {
+        let mut pos = 0;
+        while let Some(i) = incr.push(&piece[pos..]) {
+            assert!(
+                i <= piece.len() - pos,
+                "edge {} exceeds the {} bytes passed",
+                i,
+                piece.len() - pos
+            );
+            pos += i;
+            push_edge(&mut edges, offset + pos);
+        }
+        offset += piece.len();
+    }
+    edges
+}
+
+/// Edges found by calling [`ChunkIncr::push_all()`] on each piece
+///
+/// # Panics
+///
+/// If an edge is outside the data, or does not follow the previous one
+pub fn incr_edges_all<I: ChunkIncr>(mut incr: I, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut edges = Vec::new();
+    let mut offset = 0;
+    let mut out = Vec::new();
+    for piece in pieces {
+        out.clear();
+        incr.push_all(piece, &mut out);
+        for &i in &out {
+            assert!(
+                i <= piece.len(),
+                "edge {} exceeds the {} bytes passed",
+                i,
+                piece.len()
+            );
+            push_edge(&mut edges, offset + i);
+        }
+        offset += piece.len();
+    }
+    edges
+}
+
+/// Append `e` to `edges`, checking that it doesn't form an empty chunk
+fn push_edge(edges: &mut Vec<usize>, e: usize) {
+    let prev = edges.last().cloned().unwrap_or(0);
+    assert!(
+        e > prev,
+        "edge at {} follows the edge at {}, forming an empty chunk",
+        e,
+        prev
+    );
+    edges.push(e);
+}
@@ -0,0 +1,241 @@
+//! Compute a digest of each chunk as it is found
+//!
+//! Most users of content defined chunking hash each chunk once it is emitted (for example, to
+//! deduplicate chunks by their digest). [`HashingChunker`] wraps a [`ChunkIncr`] and feeds the
+//! data passed to it into a [`ChunkDigest`] as it arrives, so chunks that span multiple calls to
+//! [`HashingChunker::push()`] never need to be buffered.
+//!
+//! Digest backends are enabled with features:
+//!
+//!  - `blake3`: [`blake3::Hasher`]
+//!  - `sha256`: [`sha2::Sha256`]
+//!  - `xxh3`: [`xxhash_rust::xxh3::Xxh3`] (64-bit output)
+//!
+//! Other hashes can be used by implimenting [`ChunkDigest`].
+//!
+//! ```rust
+//! # #[cfg(feature = "mii")] {
+//! use hash_roll::digest::{ChunkDigest, HashingChunker};
+//! use hash_roll::ToChunkIncr;
+//!
+//! /// A (bad) example digest: the sum of all bytes
+//! #[derive(Default)]
+//! struct Sum(u64);
+//!
+//! impl ChunkDigest for Sum {
+//!     type Output = u64;
+//!     fn update(&mut self, data: &[u8]) {
+//!         self.0 += data.iter().map(|&b| b as u64).sum::<u64>();
+//!     }
+//!     fn finish_reset(&mut self) -> u64 {
+//!         std::mem::take(&mut self.0)
+//!     }
+//! }
+//!
+//! let incr = hash_roll::mii::Mii::default().to_chunk_incr();
+//! let mut hc = HashingChunker::new(incr, Sum::default());
+//! for piece in [&b"hello "[..], &b"world, hello"[..]].iter() {
+//!     for chunk in hc.push_all(piece) {
+//!         println!("{}+{}: {}", chunk.offset, chunk.len, chunk.digest);
+//!     }
+//! }
+//! if let Some(last) = hc.finish() {
+//!     println!("{}+{}: {}", last.offset, last.len, last.digest);
+//! }
+//! # }
+//! ```
+use crate::ChunkIncr;
+
+/// An incrimental hash used to compute a digest of each chunk
+pub trait ChunkDigest {
+    /// The digest of a chunk
+    type Output;
+
+    /// Add `data` to the current chunk
+    fn update(&mut self, data: &[u8]);
+
+    /// Return the digest of all data passed to [`ChunkDigest::update()`] since the last call to
+    /// `finish_reset()`, and reset to the initial state
+    fn finish_reset(&mut self) -> Self::Output;
+}
+
+/// A chunk and its digest, emitted by [`HashingChunker`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashedChunk<O> {
+    /// Offset of the first byte of the chunk, counting from the first byte passed to the
+    /// [`HashingChunker`]
+    pub offset: u64,
+
+    /// Number of bytes in the chunk
+    pub len: u64,
+
+    /// Digest of the chunk's data
+    pub digest: O,
+}
+
+/// Chunks data using a [`ChunkIncr`] while computing a [`ChunkDigest`] of each chunk
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone)]
+pub struct HashingChunker<I, D> {
+    incr: I,
+    digest: D,
+
+    /// offset of the start of the current chunk
+    offset: u64,
+
+    /// number of bytes in the current chunk
+    len: u64,
+}
+
+impl<I: ChunkIncr, D: ChunkDigest> HashingChunker<I, D> {
+    /// Create a [`HashingChunker`] from a fresh [`ChunkIncr`] (typically obtained via
+    /// [`ToChunkIncr`](crate::ToChunkIncr)) and [`ChunkDigest`]
+    pub fn new(incr: I, digest: D) -> Self {
+        HashingChunker {
+            incr,
+            digest,
+            offset: 0,
+            len: 0,
+        }
+    }
+
+    /// Feed `data` into the chunker and digest
+    ///
+    /// If a chunk ends in `data`, returns the index in `data` immediately following the chunk and
+    /// the completed chunk. Only the data prior to the returned index has been consumed: call
+    /// `push()` again with the remainder of `data` to continue.
+    ///
+    /// If no chunk ends in `data`, all of `data` is consumed and `None` is returned.
+    pub fn push(&mut self, data: &[u8]) -> Option<(usize, HashedChunk<D::Output>)> {
+        match self.incr.push(data) {
+            Some(i) => {
+                self.digest.update(&data[..i]);
+                self.len += i as u64;
+                Some((i, self.emit()))
+            }
+            None => {
+                self.digest.update(data);
+                self.len += data.len() as u64;
+                None
+            }
+        }
+    }
+
+    /// Feed all of `data` into the chunker and digest, iterating over the chunks that are
+    /// completed
+    ///
+    /// All of `data` is consumed, even if the iterator is not fully used.
+    pub fn push_all<'b>(&'b mut self, data: &'b [u8]) -> PushAll<'b, I, D> {
+        PushAll {
+            hc: self,
+            rem: data,
+        }
+    }
+
+    /// Emit the data passed since the last chunk (if any) as the final chunk
+    ///
+    /// This is used at the end of the input, where the trailing data does not end on a chunk
+    /// edge.
+    pub fn finish(mut self) -> Option<HashedChunk<D::Output>> {
+        if self.len == 0 {
+            None
+        } else {
+            Some(self.emit())
+        }
+    }
+
+    /// Obtain the internal [`ChunkIncr`] and [`ChunkDigest`]
+    pub fn into_parts(self) -> (I, D) {
+        (self.incr, self.digest)
+    }
+
+    fn emit(&mut self) -> HashedChunk<D::Output> {
+        let c = HashedChunk {
+            offset: self.offset,
+            len: self.len,
+            digest: self.digest.finish_reset(),
+        };
+        self.offset += self.len;
+        self.len = 0;
+        c
+    }
+}
+
+/// Returned by [`HashingChunker::push_all()`]
+#[derive(Debug)]
+pub struct PushAll<'b, I: ChunkIncr, D: ChunkDigest> {
+    hc: &'b mut HashingChunker<I, D>,
+    rem: &'b [u8],
+}
+
+impl<'b, I: ChunkIncr, D: ChunkDigest> Iterator for PushAll<'b, I, D> {
+    type Item = HashedChunk<D::Output>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rem.is_empty() {
+            return None;
+        }
+
+        match self.hc.push(self.rem) {
+            Some((i, c)) => {
+                self.rem = &self.rem[i..];
+                Some(c)
+            }
+            None => {
+                self.rem = &[];
+                None
+            }
+        }
+    }
+}
+
+impl<'b, I: ChunkIncr, D: ChunkDigest> Drop for PushAll<'b, I, D> {
+    fn drop(&mut self) {
+        // consume the rest of the data
+        for _ in self {}
+    }
+}
+
+#[cfg(feature = "blake3")]
+impl ChunkDigest for blake3::Hasher {
+    type Output = blake3::Hash;
+
+    fn update(&mut self, data: &[u8]) {
+        blake3::Hasher::update(self, data);
+    }
+
+    fn finish_reset(&mut self) -> Self::Output {
+        let h = self.finalize();
+        self.reset();
+        h
+    }
+}
+
+#[cfg(feature = "sha256")]
+impl ChunkDigest for sha2::Sha256 {
+    type Output = [u8; 32];
+
+    fn update(&mut self, data: &[u8]) {
+        sha2::Digest::update(self, data);
+    }
+
+    fn finish_reset(&mut self) -> Self::Output {
+        sha2::Digest::finalize_reset(self).into()
+    }
+}
+
+#[cfg(feature = "xxh3")]
+impl ChunkDigest for xxhash_rust::xxh3::Xxh3 {
+    type Output = u64;
+
+    fn update(&mut self, data: &[u8]) {
+        xxhash_rust::xxh3::Xxh3::update(self, data);
+    }
+
+    fn finish_reset(&mut self) -> Self::Output {
+        let h = self.digest();
+        self.reset();
+        h
+    }
+}
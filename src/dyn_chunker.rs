@@ -0,0 +1,376 @@
+//! Runtime selection of a chunking algorithm
+//!
+//! [`DynChunker`] is an enum over each (enabled) configured algorithm instance, allowing the
+//! algorithm to be chosen at runtime (for example, from a config file) without boxing. It
+//! implements [`Chunk`], [`ToChunkIncr`] and [`CutReasons`] by dispatching to the contained
+//! algorithm.
+//!
+//! For algorithms that are not known to hash-roll, use [`DynChunk`](crate::DynChunk) instead.
+//!
+//! [`DynChunker`] also implements [`FromStr`] and [`Display`](fmt::Display) using the textual
+//! specifications described in [`crate::spec`], with the algorithm selected by name.
+//!
+//! ```rust
+//! # #[cfg(all(feature = "fastcdc", feature = "zpaq"))] {
+//! use hash_roll::{Chunk, ToChunkIncr, dyn_chunker::DynChunker};
+//!
+//! let chunker: DynChunker<'_> = if std::env::var_os("USE_ZPAQ").is_some() {
+//!     hash_roll::zpaq::Zpaq::default().into()
+//! } else {
+//!     hash_roll::fastcdc::FastCdc::default().into()
+//! };
+//!
+//! let data = [0u8; 1024];
+//! let chunks = chunker.chunk_slices(&data[..]).count();
+//! assert_eq!(chunks, 1);
+//! let _incr = chunker.to_chunk_incr();
+//! # }
+//! ```
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{split_name, SpecError};
+use crate::{Chunk, ChunkIncr, ToChunkIncr};
+use std::convert::Infallible;
+use std::fmt;
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+macro_rules! dyn_chunker {
+    ($(
+        $(#[$attr:meta])*
+        $variant:ident($name:literal, $chunk:ty, $search_state:ty, $incr:ty),
+    )*) => {
+        /// A configured algorithm instance, selected at runtime
+        ///
+        /// See the [module documentation](self) for details.
+        #[derive(Debug, Clone)]
+        #[non_exhaustive]
+        pub enum DynChunker<'a> {
+            $(
+                $(#[$attr])*
+                $variant($chunk),
+            )*
+            #[doc(hidden)]
+            __Unused(Infallible, PhantomData<&'a ()>),
+        }
+
+        /// [`Chunk::SearchState`] for [`DynChunker`]
+        // the zpaq state is large, but avoiding an allocation per state is preferable
+        #[allow(clippy::large_enum_variant)]
+        #[derive(Debug)]
+        #[non_exhaustive]
+        pub enum DynChunkerSearchState {
+            $(
+                $(#[$attr])*
+                $variant($search_state),
+            )*
+            #[doc(hidden)]
+            __Unused(Infallible),
+        }
+
+        /// [`ChunkIncr`] for [`DynChunker`]
+        #[allow(clippy::large_enum_variant)]
+        #[derive(Debug)]
+        #[non_exhaustive]
+        pub enum DynChunkerIncr<'a> {
+            $(
+                $(#[$attr])*
+                $variant($incr),
+            )*
+            #[doc(hidden)]
+            __Unused(Infallible, PhantomData<&'a ()>),
+        }
+
+        $(
+            $(#[$attr])*
+            impl<'a> From<$chunk> for DynChunker<'a> {
+                fn from(chunk: $chunk) -> Self {
+                    DynChunker::$variant(chunk)
+                }
+            }
+        )*
+
+        impl<'a> fmt::Display for DynChunker<'a> {
+            #[allow(unused_variables)]
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunker::$variant(c) => c.fmt(f),
+                    )*
+                    DynChunker::__Unused(n, _) => match *n {},
+                }
+            }
+        }
+
+        impl<'a> FromStr for DynChunker<'a> {
+            type Err = SpecError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match split_name(s).0 {
+                    $(
+                        $(#[$attr])*
+                        $name => Ok(DynChunker::$variant(s.parse()?)),
+                    )*
+                    name => Err(SpecError::UnknownAlgorithm(name.to_owned())),
+                }
+            }
+        }
+
+        impl<'a> Chunk for DynChunker<'a> {
+            type SearchState = DynChunkerSearchState;
+
+            fn to_search_state(&self) -> Self::SearchState {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunker::$variant(c) => DynChunkerSearchState::$variant(c.to_search_state()),
+                    )*
+                    DynChunker::__Unused(n, _) => match *n {},
+                }
+            }
+
+            /// # Panics
+            ///
+            /// If `state` was created by a [`DynChunker`] using a different algorithm
+            fn reset_search_state(&self, state: &mut Self::SearchState) {
+                match (self, state) {
+                    $(
+                        $(#[$attr])*
+                        (DynChunker::$variant(c), DynChunkerSearchState::$variant(s)) => c.reset_search_state(s),
+                    )*
+                    (DynChunker::__Unused(n, _), _) => match *n {},
+                    #[allow(unreachable_patterns)]
+                    _ => panic!("search state is from a different chunker"),
+                }
+            }
+
+            /// # Panics
+            ///
+            /// If `state` was created by a [`DynChunker`] using a different algorithm
+            fn force_boundary(&self, state: &mut Self::SearchState) {
+                match (self, state) {
+                    $(
+                        $(#[$attr])*
+                        (DynChunker::$variant(c), DynChunkerSearchState::$variant(s)) => c.force_boundary(s),
+                    )*
+                    (DynChunker::__Unused(n, _), _) => match *n {},
+                    #[allow(unreachable_patterns)]
+                    _ => panic!("search state is from a different chunker"),
+                }
+            }
+
+            fn resets_at_edges(&self) -> bool {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunker::$variant(c) => c.resets_at_edges(),
+                    )*
+                    DynChunker::__Unused(n, _) => match *n {},
+                }
+            }
+
+            fn edge_window(&self) -> Option<usize> {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunker::$variant(c) => c.edge_window(),
+                    )*
+                    DynChunker::__Unused(n, _) => match *n {},
+                }
+            }
+
+            /// # Panics
+            ///
+            /// If `state` was created by a [`DynChunker`] using a different algorithm
+            // `data` is unused when no algorithms are enabled
+            #[allow(unused_variables)]
+            fn find_chunk_edge(
+                &self,
+                state: &mut Self::SearchState,
+                data: &[u8],
+            ) -> (Option<usize>, usize) {
+                match (self, state) {
+                    $(
+                        $(#[$attr])*
+                        (DynChunker::$variant(c), DynChunkerSearchState::$variant(s)) => c.find_chunk_edge(s, data),
+                    )*
+                    (DynChunker::__Unused(n, _), _) => match *n {},
+                    #[allow(unreachable_patterns)]
+                    _ => panic!("search state is from a different chunker"),
+                }
+            }
+
+            /// # Panics
+            ///
+            /// If `state` was created by a [`DynChunker`] using a different algorithm
+            #[allow(unused_variables)]
+            fn find_chunk_edges(
+                &self,
+                state: &mut Self::SearchState,
+                data: &[u8],
+                out: &mut Vec<usize>,
+            ) -> usize {
+                match (self, state) {
+                    $(
+                        $(#[$attr])*
+                        (DynChunker::$variant(c), DynChunkerSearchState::$variant(s)) => c.find_chunk_edges(s, data, out),
+                    )*
+                    (DynChunker::__Unused(n, _), _) => match *n {},
+                    #[allow(unreachable_patterns)]
+                    _ => panic!("search state is from a different chunker"),
+                }
+            }
+        }
+
+        impl<'a> CutReasons for DynChunker<'a> {
+            #[allow(unused_variables)]
+            fn cut_reason(&self, len: u64) -> CutReason {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunker::$variant(c) => c.cut_reason(len),
+                    )*
+                    DynChunker::__Unused(n, _) => match *n {},
+                }
+            }
+        }
+
+        impl<'a> ToChunkIncr for DynChunker<'a> {
+            type Incr = DynChunkerIncr<'a>;
+
+            fn to_chunk_incr(&self) -> Self::Incr {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunker::$variant(c) => DynChunkerIncr::$variant(c.to_chunk_incr()),
+                    )*
+                    DynChunker::__Unused(n, _) => match *n {},
+                }
+            }
+        }
+
+        impl<'a> ChunkIncr for DynChunkerIncr<'a> {
+            #[allow(unused_variables)]
+            fn push(&mut self, data: &[u8]) -> Option<usize> {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunkerIncr::$variant(i) => i.push(data),
+                    )*
+                    DynChunkerIncr::__Unused(n, _) => match *n {},
+                }
+            }
+
+            #[allow(unused_variables)]
+            fn push_all(&mut self, data: &[u8], out: &mut Vec<usize>) {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunkerIncr::$variant(i) => i.push_all(data, out),
+                    )*
+                    DynChunkerIncr::__Unused(n, _) => match *n {},
+                }
+            }
+
+            fn reset(&mut self) {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunkerIncr::$variant(i) => i.reset(),
+                    )*
+                    DynChunkerIncr::__Unused(n, _) => match *n {},
+                }
+            }
+
+            fn force_boundary(&mut self) {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        DynChunkerIncr::$variant(i) => i.force_boundary(),
+                    )*
+                    DynChunkerIncr::__Unused(n, _) => match *n {},
+                }
+            }
+        }
+    };
+}
+
+dyn_chunker! {
+    #[cfg(feature = "bup")]
+    RollSum(
+        "bup",
+        crate::bup::RollSum,
+        crate::bup::RollSumSearchState,
+        crate::bup::RollSumIncr
+    ),
+    #[cfg(feature = "buzhash")]
+    BuzHash(
+        "buzhash",
+        crate::buzhash::BuzHash<crate::buzhash::BuzHashTableByteSaltHash<'a>>,
+        crate::buzhash::BuzHashSearchState,
+        crate::buzhash::BuzHashIncr<crate::buzhash::BuzHashTableByteSaltHash<'a>>
+    ),
+    #[cfg(feature = "fastcdc")]
+    FastCdc(
+        "fastcdc",
+        crate::fastcdc::FastCdc<'a>,
+        crate::fastcdc::FastCdcState,
+        crate::fastcdc::FastCdcIncr<'a>
+    ),
+    #[cfg(feature = "gear")]
+    Gear32(
+        "gear",
+        crate::gear::Gear32<'a>,
+        crate::gear::GearState32,
+        crate::gear::GearIncr32<'a>
+    ),
+    #[cfg(feature = "gzip")]
+    GzipRsyncable(
+        "gzip",
+        crate::gzip::GzipRsyncable,
+        crate::gzip::GzipRsyncableSearchState,
+        crate::gzip::GzipRsyncableIncr
+    ),
+    #[cfg(feature = "mii")]
+    Mii(
+        "mii",
+        crate::mii::Mii,
+        crate::mii::MiiSearchState,
+        crate::mii::MiiIncr
+    ),
+    #[cfg(feature = "pigz")]
+    PigzRsyncable(
+        "pigz",
+        crate::pigz::PigzRsyncable,
+        crate::pigz::PigzRsyncableSearchState,
+        crate::pigz::PigzRsyncableIncr
+    ),
+    #[cfg(feature = "ram")]
+    Ram(
+        "ram",
+        crate::ram::Ram,
+        crate::ram::RamState,
+        crate::ram::RamIncr
+    ),
+    #[cfg(feature = "ram")]
+    RamL(
+        "raml",
+        crate::ram::RamL,
+        crate::ram::RamState,
+        crate::ram::RamLIncr
+    ),
+    #[cfg(feature = "zpaq")]
+    Zpaq(
+        "zpaq",
+        crate::zpaq::Zpaq,
+        crate::zpaq::ZpaqSearchState,
+        crate::zpaq::ZpaqIncr
+    ),
+    #[cfg(feature = "zstd")]
+    Zstd(
+        "zstd",
+        crate::zstd::Zstd,
+        crate::zstd::ZstdSearchState,
+        crate::zstd::ZstdIncr
+    ),
+}
@@ -5,9 +5,12 @@
 //! Reference:
 //!  - https://www.usenix.org/system/files/conference/atc16/atc16-paper-xia.pdf
 
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SizeDisplay, SpecError};
 use crate::{Chunk, ChunkIncr, ToChunkIncr};
 use std::fmt;
 use std::num::Wrapping;
+use std::str::FromStr;
 
 // these masks are taken from the paper and could be adjusted/adjustable.
 const MASK_S: u64 = 0x0003590703530000;
@@ -22,12 +25,17 @@ const MASK_L: u64 = 0x0000d90003530000;
 ///  - Normal size: 8 KiB
 ///  - internal 64-bit gear table: [`super::gear_table::GEAR_64`]
 ///
+/// The byte whose hash matches is the last byte of its chunk. When no match is found, a chunk
+/// ends after the byte following the first `max_size` bytes, so chunks are between
+/// `min_size + 1` and `max_size + 1` bytes long. [`FastCdc::with_cut_before_match()`] instead cuts
+/// like the reference algorithm.
 #[derive(Clone, Copy)]
 pub struct FastCdc<'a> {
     gear: &'a [u64; 256],
     min_size: u64,
     max_size: u64,
     normal_size: u64,
+    cut_before_match: bool,
 }
 
 impl<'a> PartialEq for FastCdc<'a> {
@@ -35,6 +43,7 @@ impl<'a> PartialEq for FastCdc<'a> {
         self.min_size == other.min_size
             && self.max_size == other.max_size
             && self.normal_size == other.normal_size
+            && self.cut_before_match == other.cut_before_match
             && self.gear[..] == other.gear[..]
     }
 }
@@ -48,6 +57,7 @@ impl<'a> Default for FastCdc<'a> {
             max_size: 64 * 1024,   // 64 KiB
             normal_size: 8 * 1024, // 8 KiB
             gear: &super::gear_table::GEAR_64,
+            cut_before_match: false,
         }
     }
 }
@@ -59,10 +69,111 @@ impl<'a> fmt::Debug for FastCdc<'a> {
             .field("min_size", &self.min_size)
             .field("max_size", &self.max_size)
             .field("normal_size", &self.normal_size)
+            .field("cut_before_match", &self.cut_before_match)
             .finish()
     }
 }
 
+/// Formats as `fastcdc:min=<size>,avg=<size>,max=<size>`
+///
+/// [`FastCdc::with_cut_before_match()`] is not part of the format, so it is not shown.
+///
+/// See [`crate::spec`] for details.
+impl<'a> fmt::Display for FastCdc<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "fastcdc:min={},avg={},max={}",
+            SizeDisplay(self.min_size),
+            SizeDisplay(self.normal_size),
+            SizeDisplay(self.max_size)
+        )?;
+        if self.gear[..] != super::gear_table::GEAR_64[..] {
+            write!(f, ",table=custom")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the format emitted by [`FastCdc`]'s `Display` impl
+///
+/// `table` may be given as `rsroll` to select [`super::gear_table::GEAR_64`] (the default).
+impl<'a> FromStr for FastCdc<'a> {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("fastcdc", s)?;
+        let d = FastCdc::default();
+        let min_size = p.take_size("min")?.unwrap_or(d.min_size);
+        let normal_size = p.take_size("avg")?.unwrap_or(d.normal_size);
+        let max_size = p.take_size("max")?.unwrap_or(d.max_size);
+        match p.take("table") {
+            None | Some("rsroll") => {}
+            Some(v) => return Err(p.invalid("table", v, "unknown table")),
+        }
+        p.finish()?;
+
+        Ok(FastCdc::new(
+            &super::gear_table::GEAR_64,
+            min_size,
+            normal_size,
+            max_size,
+        ))
+    }
+}
+
+#[cfg(feature = "serde")]
+const KNOWN_TABLES: &[crate::serde_table::KnownTable<u64>] =
+    &[("rsroll", &super::gear_table::GEAR_64)];
+
+/// Serialized form of [`FastCdc`]
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "FastCdc")]
+struct FastCdcSerde {
+    table: crate::serde_table::TableRepr<u64>,
+    min_size: u64,
+    normal_size: u64,
+    max_size: u64,
+}
+
+/// The gear table is serialized by name (`rsroll`) if it is [`super::gear_table::GEAR_64`], and
+/// by value otherwise.
+///
+/// Instances using [`FastCdc::with_cut_before_match()`] can't be serialized.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for FastCdc<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.cut_before_match {
+            return Err(serde::ser::Error::custom(
+                "FastCdc::with_cut_before_match() can't be serialized",
+            ));
+        }
+        FastCdcSerde {
+            table: crate::serde_table::TableRepr::from_table(self.gear, KNOWN_TABLES),
+            min_size: self.min_size,
+            normal_size: self.normal_size,
+            max_size: self.max_size,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Only [`super::gear_table::GEAR_64`] can be deserialized, as [`FastCdc`] borrows its table.
+#[cfg(feature = "serde")]
+impl<'de, 'a> serde::Deserialize<'de> for FastCdc<'a> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let s = FastCdcSerde::deserialize(deserializer)?;
+        Ok(FastCdc::new(
+            s.table.to_static(KNOWN_TABLES).map_err(D::Error::custom)?,
+            s.min_size,
+            s.normal_size,
+            s.max_size,
+        ))
+    }
+}
+
 impl<'a> Chunk for FastCdc<'a> {
     type SearchState = FastCdcState;
 
@@ -76,10 +187,29 @@ impl<'a> Chunk for FastCdc<'a> {
         data: &[u8],
     ) -> (Option<usize>, usize) {
         match state.push(self, data) {
-            Some(i) => (Some(i + 1), i + 1),
+            Some(i) => (Some(i), i),
             None => (None, data.len()),
         }
     }
+
+    fn find_chunk_edges(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        let mut pos = 0;
+        while pos < data.len() {
+            match state.push(self, &data[pos..]) {
+                Some(i) => {
+                    pos += i;
+                    out.push(pos);
+                }
+                None => break,
+            }
+        }
+        data.len()
+    }
 }
 
 impl<'a> FastCdc<'a> {
@@ -90,6 +220,36 @@ impl<'a> FastCdc<'a> {
             min_size,
             max_size,
             normal_size,
+            cut_before_match: false,
+        }
+    }
+
+    /// Place each edge before the byte whose hash matches (so that byte starts the next chunk),
+    /// like the reference algorithm, instead of after it
+    ///
+    /// This changes the edges found. Chunks are then between `min_size` (or 1, if `min_size` is 0)
+    /// and `max_size` bytes long.
+    pub fn with_cut_before_match(self) -> Self {
+        FastCdc {
+            cut_before_match: true,
+            ..self
+        }
+    }
+}
+
+impl<'a> CutReasons for FastCdc<'a> {
+    /// [`CutReason::HashStrict`] for chunks smaller than the normal size,
+    /// [`CutReason::HashLoose`] for larger ones, and [`CutReason::MaxSize`] for chunks of the
+    /// maximum size
+    fn cut_reason(&self, len: u64) -> CutReason {
+        // the position of the byte that was the last examined
+        let pos = if self.cut_before_match { len } else { len - 1 };
+        if pos >= self.max_size {
+            CutReason::MaxSize
+        } else if pos < self.normal_size {
+            CutReason::HashStrict
+        } else {
+            CutReason::HashLoose
         }
     }
 }
@@ -115,6 +275,9 @@ impl<'a> From<&FastCdc<'a>> for FastCdcIncr<'a> {
 ///
 /// This impl does not buffer data passing through it (the FastCDC algorithm does not require
 /// look-back) making it very efficient.
+///
+/// It finds the same edges as [`FastCdc`]'s [`Chunk`] impl. Previously, it instead split before
+/// the byte whose hash matched: use [`FastCdc::with_cut_before_match()`] to get those edges.
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub struct FastCdcIncr<'a> {
     params: FastCdc<'a>,
@@ -140,13 +303,34 @@ impl FastCdcState {
         self.fp = Wrapping(0);
     }
 
+    /// Push `data`, returning the index in `data` where the next chunk starts (if any)
     fn push(&mut self, params: &FastCdc<'_>, data: &[u8]) -> Option<usize> {
+        let cut = self.find(params, data)?;
+        if params.cut_before_match {
+            Some(cut)
+        } else {
+            Some(cut + 1)
+        }
+    }
+
+    /// Push `data`, returning the index in `data` of the byte whose hash matched or which follows
+    /// the first `max_size` bytes (if any)
+    ///
+    /// Unless cutting before the match, the returned byte ends the chunk, so it is always in
+    /// `data`.
+    fn find(&mut self, params: &FastCdc<'_>, data: &[u8]) -> Option<usize> {
+        // a chunk always contains at least one byte
+        let min_size = if params.cut_before_match {
+            std::cmp::max(params.min_size, 1)
+        } else {
+            params.min_size
+        };
         // global start/index
         let mut gi = self.l;
         // global end
         let ge = data.len() as u64 + gi;
 
-        if ge <= params.min_size {
+        if ge <= min_size {
             // No split, no processing of data, but we've "consumed" the bytes.
             self.l = ge;
             return None;
@@ -154,8 +338,8 @@ impl FastCdcState {
 
         // skip elements prior to MIN_SIZE and track offset of new `data` in argument `data` for
         // return value
-        let mut i = if gi <= params.min_size {
-            let skip = params.min_size - gi;
+        let mut i = if gi <= min_size {
+            let skip = min_size - gi;
             gi += skip;
             skip
         } else {
@@ -163,46 +347,55 @@ impl FastCdcState {
         } as usize;
 
         let mut fp = self.fp;
-
-        loop {
-            if i >= data.len() {
-                break;
-            }
-            if gi >= params.normal_size {
-                // go to next set of matches
-                break;
-            }
-
-            let v = data[i];
-            fp = (fp << 1) + Wrapping(params.gear[v as usize]);
-            if (fp.0 & MASK_S) == 0 {
+        // bytes before this index are either not in `data` or not hashed
+        let hashed_from = i;
+
+        if gi < params.normal_size {
+            let end = std::cmp::min(data.len() as u64, i as u64 + (params.normal_size - gi));
+            let prev = i;
+            if let Some(m) = scan(
+                params.gear,
+                MASK_S,
+                data,
+                &mut i,
+                end as usize,
+                &mut fp,
+                hashed_from,
+            ) {
                 self.reset();
-                return Some(i);
+                return Some(m);
             }
-
-            gi += 1;
-            i += 1;
+            gi += (i - prev) as u64;
         }
 
-        loop {
-            if gi >= params.max_size {
-                // no match found, emit fixed match at MAX_SIZE
+        if gi < params.max_size {
+            let end = std::cmp::min(data.len() as u64, i as u64 + (params.max_size - gi));
+            let prev = i;
+            if let Some(m) = scan(
+                params.gear,
+                MASK_L,
+                data,
+                &mut i,
+                end as usize,
+                &mut fp,
+                hashed_from,
+            ) {
                 self.reset();
-                return Some(i);
-            }
-            if i >= data.len() {
-                break;
+                return Some(m);
             }
+            gi += (i - prev) as u64;
+        }
 
-            let v = data[i];
-            fp = (fp << 1) + Wrapping(params.gear[v as usize]);
-            if (fp.0 & MASK_L) == 0 {
-                self.reset();
-                return Some(i);
+        if gi >= params.max_size {
+            if i == data.len() && !params.cut_before_match {
+                // the chunk ends after the next byte
+                self.l = ge;
+                return None;
             }
 
-            gi += 1;
-            i += 1;
+            // no match found, emit fixed match at MAX_SIZE
+            self.reset();
+            return Some(i);
         }
 
         // no match, but not at MAX_SIZE yet, so store context for next time.
@@ -213,8 +406,56 @@ impl FastCdcState {
     }
 }
 
+/// Hash `data[*i..end]`, returning the index of the first byte where `fp & mask == 0`
+///
+/// If there is no match, `*i` is set to `end` and `fp` to the hash of the data before `end`.
+///
+/// Bytes before `hashed_from` were never hashed. Once 64 bytes following it have been hashed, the
+/// hash no longer depends on the initial `fp`, and the remaining bytes are searched using
+/// [`crate::gear_simd`].
+fn scan(
+    gear: &[u64; 256],
+    mask: u64,
+    data: &[u8],
+    i: &mut usize,
+    end: usize,
+    fp: &mut Wrapping<u64>,
+    hashed_from: usize,
+) -> Option<usize> {
+    let head = std::cmp::min(end, std::cmp::max(*i, hashed_from + 63));
+    while *i < head {
+        *fp = (*fp << 1) + Wrapping(gear[data[*i] as usize]);
+        if (fp.0 & mask) == 0 {
+            return Some(*i);
+        }
+        *i += 1;
+    }
+
+    if *i < end {
+        if let Some(m) = crate::gear_simd::find_64(gear, mask, &data[..end], *i) {
+            return Some(m);
+        }
+        *i = end;
+        *fp = crate::gear_simd::window_64(gear, data, end - 1);
+    }
+
+    None
+}
+
 impl<'a> ChunkIncr for FastCdcIncr<'a> {
     fn push(&mut self, src: &[u8]) -> Option<usize> {
         self.state.push(&self.params, src)
     }
+
+    fn push_all(&mut self, src: &[u8], out: &mut Vec<usize>) {
+        let mut pos = 0;
+        while let Some(i) = self.state.push(&self.params, &src[pos..]) {
+            pos += i;
+            out.push(pos);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.state.reset();
+    }
 }
@@ -1,8 +1,13 @@
 #![cfg(feature = "gear")]
 
+use crate::config::ConfigError;
+use crate::multi::MultiIncr;
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SpecError};
 use crate::{Chunk, ChunkIncr, ToChunkIncr};
 use std::fmt;
 use std::num::Wrapping;
+use std::str::FromStr;
 
 /// Gear Content Defined Chunking using 32bit expansion.
 ///
@@ -57,14 +62,35 @@ impl<'a> Chunk for Gear32<'a> {
         state: &mut Self::SearchState,
         data: &[u8],
     ) -> (Option<usize>, usize) {
-        for (i, v) in data.iter().enumerate() {
-            if state.push(self, *v) {
+        match state.push_slice(self, data) {
+            Some(i) => {
                 *state = self.to_search_state();
-                return (Some(i + 1), i + 1);
+                (Some(i + 1), i + 1)
             }
+            None => (None, data.len()),
         }
+    }
+
+    fn find_chunk_edges(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        let mut pos = 0;
+        while let Some(i) = state.push_slice(self, &data[pos..]) {
+            state.reset();
+            pos += i + 1;
+            out.push(pos);
+        }
+        data.len()
+    }
+}
 
-        (None, data.len())
+impl<'a> CutReasons for Gear32<'a> {
+    /// Always [`CutReason::Hash`]
+    fn cut_reason(&self, _len: u64) -> CutReason {
+        CutReason::Hash
     }
 }
 
@@ -95,12 +121,118 @@ impl<'a> fmt::Debug for Gear32<'a> {
     }
 }
 
+/// Formats as `gear:bits=<average_size_log2>`
+///
+/// See [`crate::spec`] for details.
+impl<'a> fmt::Display for Gear32<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gear:bits={}", self.mask.count_ones())?;
+        if self.gear[..] != super::gear_table::GEAR_32[..] {
+            write!(f, ",table=custom")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the format emitted by [`Gear32`]'s `Display` impl
+///
+/// `bits` must be between 1 and 31. `table` may be given as `rsroll` to select
+/// [`super::gear_table::GEAR_32`] (the default).
+impl<'a> FromStr for Gear32<'a> {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("gear", s)?;
+        let bits = match p.take("bits") {
+            None => 13,
+            Some(v) => match v.parse() {
+                Ok(b) if (1..32).contains(&b) => b,
+                _ => return Err(p.invalid("bits", v, "must be between 1 and 31")),
+            },
+        };
+        match p.take("table") {
+            None | Some("rsroll") => {}
+            Some(v) => return Err(p.invalid("table", v, "unknown table")),
+        }
+        p.finish()?;
+
+        Ok(Gear32::with_average_size_log2(bits))
+    }
+}
+
+#[cfg(feature = "serde")]
+const KNOWN_TABLES: &[crate::serde_table::KnownTable<u32>] =
+    &[("rsroll", &super::gear_table::GEAR_32)];
+
+/// Serialized form of [`Gear32`]
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "Gear32")]
+struct Gear32Serde {
+    table: crate::serde_table::TableRepr<u32>,
+    mask: u32,
+    xxx: u32,
+}
+
+/// The gear table is serialized by name (`rsroll`) if it is [`super::gear_table::GEAR_32`], and
+/// by value otherwise.
+#[cfg(feature = "serde")]
+impl<'a> serde::Serialize for Gear32<'a> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Gear32Serde {
+            table: crate::serde_table::TableRepr::from_table(self.gear, KNOWN_TABLES),
+            mask: self.mask,
+            xxx: self.xxx,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Only [`super::gear_table::GEAR_32`] can be deserialized, as [`Gear32`] borrows its table.
+#[cfg(feature = "serde")]
+impl<'de, 'a> serde::Deserialize<'de> for Gear32<'a> {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+        let s = Gear32Serde::deserialize(deserializer)?;
+        Ok(Gear32 {
+            mask: s.mask,
+            xxx: s.xxx,
+            gear: s.table.to_static(KNOWN_TABLES).map_err(D::Error::custom)?,
+        })
+    }
+}
+
 impl GearState32 {
     fn push(&mut self, params: &Gear32<'_>, add: u8) -> bool {
         self.fp = (self.fp << 1) + Wrapping(params.gear[add as usize]);
         self.fp.0 & params.mask == params.xxx
     }
 
+    /// Push bytes from `data` until a match, returning the index of the matching byte
+    ///
+    /// Once 32 bytes of `data` have been hashed, the hash no longer depends on `self.fp`, so the
+    /// remainder is searched using [`crate::gear_simd`].
+    fn push_slice(&mut self, params: &Gear32<'_>, data: &[u8]) -> Option<usize> {
+        let head = std::cmp::min(31, data.len());
+        for (i, v) in data[..head].iter().enumerate() {
+            if self.push(params, *v) {
+                return Some(i);
+            }
+        }
+
+        if head == data.len() {
+            return None;
+        }
+
+        match crate::gear_simd::find_32(params.gear, params.mask, params.xxx, data, head) {
+            Some(i) => Some(i),
+            None => {
+                self.fp = crate::gear_simd::window_32(params.gear, data, data.len() - 1);
+                None
+            }
+        }
+    }
+
     fn reset(&mut self) {
         self.fp.0 = 0;
     }
@@ -108,22 +240,107 @@ impl GearState32 {
 
 impl<'a> ChunkIncr for GearIncr32<'a> {
     fn push(&mut self, data: &[u8]) -> Option<usize> {
-        for (i, v) in data.iter().enumerate() {
-            if self.state.push(&self.params, *v) {
-                self.state.reset();
-                return Some(i);
-            }
+        let r = self.state.push_slice(&self.params, data)?;
+        self.state.reset();
+        Some(r + 1)
+    }
+
+    fn reset(&mut self) {
+        self.state.reset();
+    }
+
+    fn push_all(&mut self, data: &[u8], out: &mut Vec<usize>) {
+        let mut pos = 0;
+        while let Some(i) = self.state.push_slice(&self.params, &data[pos..]) {
+            self.state.reset();
+            pos += i + 1;
+            out.push(pos);
         }
+    }
+}
+
+/// Incremental interface to several [`Gear32`] configurations sharing a table, for use with
+/// [`crate::multi::MultiChunker`]
+///
+/// The fingerprint only depends on the last 32 bytes, so once 32 bytes have passed since a
+/// configuration's last edge its fingerprint is the same as every other configuration's. The
+/// fingerprint is computed once for each byte, and only the first 31 bytes of each chunk are
+/// hashed separately for each configuration. Each configuration finds the same edges as its own
+/// [`GearIncr32`].
+#[derive(Debug, Clone)]
+pub struct GearMultiIncr32<'a> {
+    gear: &'a [u32; 256],
+
+    /// `(mask, xxx)` of each configuration
+    configs: Vec<(u32, u32)>,
+
+    /// fingerprint of the last 32 bytes
+    fp: Wrapping<u32>,
+
+    /// fingerprint of each configuration, only used for the first 31 bytes after an edge
+    own: Vec<Wrapping<u32>>,
 
-        None
+    /// number of bytes since the last edge of each configuration, up to 32
+    since: Vec<u32>,
+}
+
+impl<'a> GearMultiIncr32<'a> {
+    /// Chunk with each of `configs`
+    ///
+    /// # Panics
+    ///
+    /// If `configs` do not all use the same table
+    pub fn new(configs: &[Gear32<'a>]) -> Self {
+        let gear = configs
+            .first()
+            .map_or(&super::gear_table::GEAR_32, |c| c.gear);
+        assert!(
+            configs.iter().all(|c| c.gear == gear),
+            "all configurations must use the same table"
+        );
+        GearMultiIncr32 {
+            gear,
+            configs: configs.iter().map(|c| (c.mask, c.xxx)).collect(),
+            fp: Wrapping(0),
+            own: vec![Wrapping(0); configs.len()],
+            since: vec![0; configs.len()],
+        }
+    }
+}
+
+impl<'a> MultiIncr for GearMultiIncr32<'a> {
+    fn configs(&self) -> usize {
+        self.configs.len()
+    }
+
+    fn push_multi(&mut self, data: &[u8], out: &mut [Vec<usize>]) {
+        for (i, &v) in data.iter().enumerate() {
+            let g = Wrapping(self.gear[v as usize]);
+            self.fp = (self.fp << 1) + g;
+            for (c, &(mask, xxx)) in self.configs.iter().enumerate() {
+                let fp = if self.since[c] < 32 {
+                    self.since[c] += 1;
+                    self.own[c] = (self.own[c] << 1) + g;
+                    self.own[c]
+                } else {
+                    self.fp
+                };
+
+                if fp.0 & mask == xxx {
+                    out[c].push(i + 1);
+                    self.since[c] = 0;
+                    self.own[c] = Wrapping(0);
+                }
+            }
+        }
     }
 }
 
 fn msb_mask(log2: usize) -> u32 {
     // at least 1 bit & not all the bits
     // FIXME: probably could relax those requirements with better math.
-    //debug_assert!(log2 > 0);
-    //debug_assert!(log2 < 32);
+    debug_assert!(log2 > 0);
+    debug_assert!(log2 < 32);
 
     ((1 << log2) - 1) << (32 - log2)
 }
@@ -131,12 +348,31 @@ fn msb_mask(log2: usize) -> u32 {
 impl<'a> Gear32<'a> {
     /// Create a gear chunker which emits blocks with average size `(1<<average_size_log2)`, (or:
     /// `2**average_size_log2`
+    ///
+    /// # Panics
+    ///
+    /// If `average_size_log2` is not between 1 and 31. See
+    /// [`Gear32::try_with_average_size_log2()`].
     pub fn with_average_size_log2(average_size_log2: usize) -> Self {
-        Gear32 {
+        Self::try_with_average_size_log2(average_size_log2).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a gear chunker which emits blocks with average size `(1<<average_size_log2)`, or
+    /// return an error if `average_size_log2` is not between 1 and 31
+    pub fn try_with_average_size_log2(average_size_log2: usize) -> Result<Self, ConfigError> {
+        if !(1..32).contains(&average_size_log2) {
+            return Err(ConfigError::invalid(
+                "gear",
+                "average_size_log2",
+                average_size_log2 as u64,
+                "must be between 1 and 31",
+            ));
+        }
+        Ok(Gear32 {
             mask: msb_mask(average_size_log2),
             xxx: 0,
             gear: &super::gear_table::GEAR_32,
-        }
+        })
     }
 }
 
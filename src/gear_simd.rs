@@ -0,0 +1,624 @@
+#![cfg(any(feature = "gear", feature = "fastcdc"))]
+
+//! Vectorized search for gear hash matches, shared by [`crate::gear`] and [`crate::fastcdc`]
+//!
+//! The gear hash `fp = (fp << 1) + G[b]` shifts out the contribution of each byte after 32 (for
+//! 32-bit hashes) or 64 (for 64-bit hashes) more bytes are added. Once that many bytes have been
+//! hashed, the value at any position only depends on the window of bytes ending there. This lets
+//! us hash several positions at once: each SIMD lane hashes its own stripe of a block, starting
+//! a window's length before the stripe to fill the window.
+//!
+//! Each block is split into stripes, one per lane, so the first match in the block is the first
+//! match in the lowest numbered lane that has one. Blocks are kept small so that little work is
+//! wasted past a match.
+//!
+//! On x86_64, AVX2 or SSE4.1 are used when detected at runtime (detection happens once, on the
+//! first search). Other targets (and inputs too short to fill a block) use the scalar
+//! implementation.
+
+use std::num::Wrapping;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[cfg(feature = "gear")]
+/// Number of positions searched by each lane per block for 32-bit hashes
+#[cfg(target_arch = "x86_64")]
+const STRIPE_32: usize = 256;
+
+#[cfg(feature = "fastcdc")]
+/// Number of positions searched by each lane per block for 64-bit hashes
+#[cfg(target_arch = "x86_64")]
+const STRIPE_64: usize = 512;
+
+/// Instruction set extensions used for the search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Level {
+    Scalar = 0,
+    #[cfg(target_arch = "x86_64")]
+    Sse41 = 1,
+    #[cfg(target_arch = "x86_64")]
+    Avx2 = 2,
+}
+
+/// Value of [`LEVEL`] before the level is first needed
+const LEVEL_UNKNOWN: u8 = u8::MAX;
+
+/// The [`Level`] used for searches, so detection only happens once
+static LEVEL: AtomicU8 = AtomicU8::new(LEVEL_UNKNOWN);
+
+impl Level {
+    /// The best level supported by the current cpu
+    pub(crate) fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return Level::Avx2;
+            }
+            if is_x86_feature_detected!("sse4.1") {
+                return Level::Sse41;
+            }
+        }
+
+        Level::Scalar
+    }
+
+    /// The level used for searches: the one from [`Level::detect()`], unless SIMD was disabled
+    /// with [`set_enabled()`]
+    fn current() -> Self {
+        match LEVEL.load(Ordering::Relaxed) {
+            LEVEL_UNKNOWN => {
+                let level = Level::detect();
+                LEVEL.store(level as u8, Ordering::Relaxed);
+                level
+            }
+            #[cfg(target_arch = "x86_64")]
+            1 => Level::Sse41,
+            #[cfg(target_arch = "x86_64")]
+            2 => Level::Avx2,
+            _ => Level::Scalar,
+        }
+    }
+}
+
+/// Enable or disable the SIMD search for all chunkers
+///
+/// This exists so benchmarks can compare the SIMD and scalar searches on the same input. The
+/// edges found are the same either way.
+pub fn set_enabled(enabled: bool) {
+    let level = if enabled {
+        Level::detect()
+    } else {
+        Level::Scalar
+    };
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+#[cfg(feature = "gear")]
+/// The 32-bit gear hash of the 32 bytes ending at `data[i]`
+///
+/// `i` must be at least 31
+pub(crate) fn window_32(table: &[u32; 256], data: &[u8], i: usize) -> Wrapping<u32> {
+    data[i - 31..=i].iter().fold(Wrapping(0), |fp, &b| {
+        (fp << 1) + Wrapping(table[b as usize])
+    })
+}
+
+#[cfg(feature = "fastcdc")]
+/// The 64-bit gear hash of the 64 bytes ending at `data[i]`
+///
+/// `i` must be at least 63
+pub(crate) fn window_64(table: &[u64; 256], data: &[u8], i: usize) -> Wrapping<u64> {
+    data[i - 63..=i].iter().fold(Wrapping(0), |fp, &b| {
+        (fp << 1) + Wrapping(table[b as usize])
+    })
+}
+
+#[cfg(feature = "gear")]
+/// Find the first index `i` in `from..data.len()` where `window_32(table, data, i) & mask == xxx`
+///
+/// `from` must be at least 31
+pub(crate) fn find_32(
+    table: &[u32; 256],
+    mask: u32,
+    xxx: u32,
+    data: &[u8],
+    from: usize,
+) -> Option<usize> {
+    find_32_with(Level::current(), table, mask, xxx, data, from)
+}
+
+#[cfg(feature = "fastcdc")]
+/// Find the first index `i` in `from..data.len()` where `window_64(table, data, i) & mask == 0`
+///
+/// `from` must be at least 63
+pub(crate) fn find_64(table: &[u64; 256], mask: u64, data: &[u8], from: usize) -> Option<usize> {
+    find_64_with(Level::current(), table, mask, data, from)
+}
+
+#[cfg(feature = "gear")]
+fn find_32_with(
+    level: Level,
+    table: &[u32; 256],
+    mask: u32,
+    xxx: u32,
+    data: &[u8],
+    from: usize,
+) -> Option<usize> {
+    debug_assert!(from >= 31);
+    match level {
+        Level::Scalar => find_32_scalar(table, mask, xxx, data, from),
+        // SAFETY: the level was detected as supported
+        #[cfg(target_arch = "x86_64")]
+        Level::Sse41 => unsafe { x86::find_32_sse41(table, mask, xxx, data, from) },
+        #[cfg(target_arch = "x86_64")]
+        Level::Avx2 => unsafe { x86::find_32_avx2(table, mask, xxx, data, from) },
+    }
+}
+
+#[cfg(feature = "fastcdc")]
+fn find_64_with(
+    level: Level,
+    table: &[u64; 256],
+    mask: u64,
+    data: &[u8],
+    from: usize,
+) -> Option<usize> {
+    debug_assert!(from >= 63);
+    match level {
+        Level::Scalar => find_64_scalar(table, mask, data, from),
+        // SAFETY: the level was detected as supported
+        #[cfg(target_arch = "x86_64")]
+        Level::Sse41 => unsafe { x86::find_64_sse41(table, mask, data, from) },
+        #[cfg(target_arch = "x86_64")]
+        Level::Avx2 => unsafe { x86::find_64_avx2(table, mask, data, from) },
+    }
+}
+
+#[cfg(feature = "gear")]
+fn find_32_scalar(
+    table: &[u32; 256],
+    mask: u32,
+    xxx: u32,
+    data: &[u8],
+    from: usize,
+) -> Option<usize> {
+    if from >= data.len() {
+        return None;
+    }
+
+    // the bytes before `from` that are in the window of `data[from]`
+    let mut fp = data[from - 31..from].iter().fold(Wrapping(0), |fp, &b| {
+        (fp << 1) + Wrapping(table[b as usize])
+    });
+    for (i, &b) in data.iter().enumerate().skip(from) {
+        fp = (fp << 1) + Wrapping(table[b as usize]);
+        if fp.0 & mask == xxx {
+            return Some(i);
+        }
+    }
+
+    None
+}
+
+#[cfg(feature = "fastcdc")]
+fn find_64_scalar(table: &[u64; 256], mask: u64, data: &[u8], from: usize) -> Option<usize> {
+    if from >= data.len() {
+        return None;
+    }
+
+    // the bytes before `from` that are in the window of `data[from]`
+    let mut fp = data[from - 63..from].iter().fold(Wrapping(0), |fp, &b| {
+        (fp << 1) + Wrapping(table[b as usize])
+    });
+    for (i, &b) in data.iter().enumerate().skip(from) {
+        fp = (fp << 1) + Wrapping(table[b as usize]);
+        if fp.0 & mask == 0 {
+            return Some(i);
+        }
+    }
+
+    None
+}
+
+/// Tracks the first match in each lane of a block
+#[cfg(target_arch = "x86_64")]
+struct LaneHits {
+    found: u32,
+    first: [usize; 8],
+}
+
+#[cfg(target_arch = "x86_64")]
+impl LaneHits {
+    fn new() -> Self {
+        LaneHits {
+            found: 0,
+            first: [0; 8],
+        }
+    }
+
+    /// Record matches in the lanes set in `lanes` at step `t`
+    #[inline(always)]
+    fn record(&mut self, lanes: u32, t: usize) {
+        let mut new = lanes & !self.found;
+        while new != 0 {
+            let k = new.trailing_zeros();
+            self.first[k as usize] = t;
+            new &= new - 1;
+        }
+        self.found |= lanes;
+    }
+
+    /// Lane 0 holds the lowest positions, so a match there can't be beaten by another lane
+    fn lane_0_found(&self) -> bool {
+        self.found & 1 != 0
+    }
+
+    /// The step of the first match in the lowest lane that has one
+    fn first(&self) -> Option<(usize, usize)> {
+        if self.found == 0 {
+            None
+        } else {
+            let k = self.found.trailing_zeros() as usize;
+            Some((k, self.first[k]))
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use super::LaneHits;
+    #[cfg(feature = "gear")]
+    use super::{find_32_scalar, STRIPE_32};
+    #[cfg(feature = "fastcdc")]
+    use super::{find_64_scalar, STRIPE_64};
+    use std::arch::x86_64::*;
+
+    /// Each lane starts this many bytes before its stripe to fill the hash window
+    #[cfg(feature = "gear")]
+    const WARM_32: usize = 31;
+    #[cfg(feature = "fastcdc")]
+    const WARM_64: usize = 63;
+
+    #[cfg(feature = "gear")]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn find_32_avx2(
+        table: &[u32; 256],
+        mask: u32,
+        xxx: u32,
+        data: &[u8],
+        from: usize,
+    ) -> Option<usize> {
+        const LANES: usize = 8;
+        let block = LANES * STRIPE_32;
+        let maskv = _mm256_set1_epi32(mask as i32);
+        let xxxv = _mm256_set1_epi32(xxx as i32);
+        let bytev = _mm256_set1_epi32(0xff);
+        let stripes = _mm256_setr_epi32(
+            0,
+            STRIPE_32 as i32,
+            2 * STRIPE_32 as i32,
+            3 * STRIPE_32 as i32,
+            4 * STRIPE_32 as i32,
+            5 * STRIPE_32 as i32,
+            6 * STRIPE_32 as i32,
+            7 * STRIPE_32 as i32,
+        );
+
+        let mut b = from;
+        // the last group of bytes loaded extends up to 3 bytes past the block
+        while b + block + 3 <= data.len() {
+            let base = data.as_ptr().add(b - WARM_32) as *const i32;
+            let mut fp = _mm256_setzero_si256();
+            let mut hits = LaneHits::new();
+            'steps: for t0 in (0..(WARM_32 + STRIPE_32)).step_by(4) {
+                // the next 4 bytes of each lane
+                let idx = _mm256_add_epi32(stripes, _mm256_set1_epi32(t0 as i32));
+                let group = _mm256_i32gather_epi32(base, idx, 1);
+                for j in 0..4 {
+                    let t = t0 + j;
+                    if t >= WARM_32 + STRIPE_32 {
+                        break;
+                    }
+
+                    let shift = _mm_cvtsi32_si128(8 * j as i32);
+                    let bytes = _mm256_and_si256(_mm256_srl_epi32(group, shift), bytev);
+                    let g = _mm256_i32gather_epi32(table.as_ptr() as *const i32, bytes, 4);
+                    fp = _mm256_add_epi32(_mm256_slli_epi32(fp, 1), g);
+
+                    if t >= WARM_32 {
+                        let eq = _mm256_cmpeq_epi32(_mm256_and_si256(fp, maskv), xxxv);
+                        let lanes = _mm256_movemask_ps(_mm256_castsi256_ps(eq)) as u32;
+                        if lanes != 0 {
+                            hits.record(lanes, t - WARM_32);
+                            if hits.lane_0_found() {
+                                break 'steps;
+                            }
+                        }
+                    }
+                }
+            }
+
+            if let Some((k, t)) = hits.first() {
+                return Some(b + k * STRIPE_32 + t);
+            }
+            b += block;
+        }
+
+        find_32_scalar(table, mask, xxx, data, b)
+    }
+
+    #[cfg(feature = "gear")]
+    #[target_feature(enable = "sse4.1")]
+    pub(super) unsafe fn find_32_sse41(
+        table: &[u32; 256],
+        mask: u32,
+        xxx: u32,
+        data: &[u8],
+        from: usize,
+    ) -> Option<usize> {
+        const LANES: usize = 4;
+        let block = LANES * STRIPE_32;
+        let maskv = _mm_set1_epi32(mask as i32);
+        let xxxv = _mm_set1_epi32(xxx as i32);
+
+        let mut b = from;
+        while b + block <= data.len() {
+            let s = &data[b - WARM_32..b + block];
+            let mut fp = _mm_setzero_si128();
+            let mut hits = LaneHits::new();
+            for t in 0..(WARM_32 + STRIPE_32) {
+                let g = _mm_setr_epi32(
+                    table[s[t] as usize] as i32,
+                    table[s[STRIPE_32 + t] as usize] as i32,
+                    table[s[2 * STRIPE_32 + t] as usize] as i32,
+                    table[s[3 * STRIPE_32 + t] as usize] as i32,
+                );
+                fp = _mm_add_epi32(_mm_slli_epi32(fp, 1), g);
+
+                if t >= WARM_32 {
+                    let eq = _mm_cmpeq_epi32(_mm_and_si128(fp, maskv), xxxv);
+                    let lanes = _mm_movemask_ps(_mm_castsi128_ps(eq)) as u32;
+                    if lanes != 0 {
+                        hits.record(lanes, t - WARM_32);
+                        if hits.lane_0_found() {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            if let Some((k, t)) = hits.first() {
+                return Some(b + k * STRIPE_32 + t);
+            }
+            b += block;
+        }
+
+        find_32_scalar(table, mask, xxx, data, b)
+    }
+
+    #[cfg(feature = "fastcdc")]
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn find_64_avx2(
+        table: &[u64; 256],
+        mask: u64,
+        data: &[u8],
+        from: usize,
+    ) -> Option<usize> {
+        const LANES: usize = 4;
+        let block = LANES * STRIPE_64;
+        let maskv = _mm256_set1_epi64x(mask as i64);
+        let zero = _mm256_setzero_si256();
+
+        let mut b = from;
+        while b + block <= data.len() {
+            let s = &data[b - WARM_64..b + block];
+            let mut fp = _mm256_setzero_si256();
+            let mut hits = LaneHits::new();
+            for t in 0..(WARM_64 + STRIPE_64) {
+                // looking up each lane's entry is faster than `_mm256_i64gather_epi64()`
+                let g = _mm256_set_epi64x(
+                    table[s[3 * STRIPE_64 + t] as usize] as i64,
+                    table[s[2 * STRIPE_64 + t] as usize] as i64,
+                    table[s[STRIPE_64 + t] as usize] as i64,
+                    table[s[t] as usize] as i64,
+                );
+                fp = _mm256_add_epi64(_mm256_slli_epi64(fp, 1), g);
+
+                if t >= WARM_64 {
+                    let eq = _mm256_cmpeq_epi64(_mm256_and_si256(fp, maskv), zero);
+                    let lanes = _mm256_movemask_pd(_mm256_castsi256_pd(eq)) as u32;
+                    if lanes != 0 {
+                        hits.record(lanes, t - WARM_64);
+                        if hits.lane_0_found() {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            if let Some((k, t)) = hits.first() {
+                return Some(b + k * STRIPE_64 + t);
+            }
+            b += block;
+        }
+
+        find_64_scalar(table, mask, data, b)
+    }
+
+    #[cfg(feature = "fastcdc")]
+    #[target_feature(enable = "sse4.1")]
+    pub(super) unsafe fn find_64_sse41(
+        table: &[u64; 256],
+        mask: u64,
+        data: &[u8],
+        from: usize,
+    ) -> Option<usize> {
+        const LANES: usize = 2;
+        let block = LANES * STRIPE_64;
+        let maskv = _mm_set1_epi64x(mask as i64);
+        let zero = _mm_setzero_si128();
+
+        let mut b = from;
+        while b + block <= data.len() {
+            let s = &data[b - WARM_64..b + block];
+            let mut fp = _mm_setzero_si128();
+            let mut hits = LaneHits::new();
+            for t in 0..(WARM_64 + STRIPE_64) {
+                let g = _mm_set_epi64x(
+                    table[s[STRIPE_64 + t] as usize] as i64,
+                    table[s[t] as usize] as i64,
+                );
+                fp = _mm_add_epi64(_mm_slli_epi64(fp, 1), g);
+
+                if t >= WARM_64 {
+                    let eq = _mm_cmpeq_epi64(_mm_and_si128(fp, maskv), zero);
+                    let lanes = _mm_movemask_pd(_mm_castsi128_pd(eq)) as u32;
+                    if lanes != 0 {
+                        hits.record(lanes, t - WARM_64);
+                        if hits.lane_0_found() {
+                            break;
+                        }
+                    }
+                }
+            }
+
+            if let Some((k, t)) = hits.first() {
+                return Some(b + k * STRIPE_64 + t);
+            }
+            b += block;
+        }
+
+        find_64_scalar(table, mask, data, b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::{Rng, RngCore, SeedableRng};
+
+    fn levels() -> Vec<Level> {
+        let mut l = vec![Level::Scalar];
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("sse4.1") {
+                l.push(Level::Sse41);
+            }
+            if is_x86_feature_detected!("avx2") {
+                l.push(Level::Avx2);
+            }
+        }
+        l
+    }
+
+    #[test]
+    fn level_cached() {
+        assert_eq!(Level::current(), Level::detect());
+        set_enabled(false);
+        assert_eq!(Level::current(), Level::Scalar);
+        set_enabled(true);
+        assert_eq!(Level::current(), Level::detect());
+    }
+
+    #[cfg(feature = "gear")]
+    /// Reference: recompute the window at every position
+    fn naive_32(
+        table: &[u32; 256],
+        mask: u32,
+        xxx: u32,
+        data: &[u8],
+        from: usize,
+    ) -> Option<usize> {
+        (from..data.len()).find(|&i| window_32(table, data, i).0 & mask == xxx)
+    }
+
+    #[cfg(feature = "fastcdc")]
+    fn naive_64(table: &[u64; 256], mask: u64, data: &[u8], from: usize) -> Option<usize> {
+        (from..data.len()).find(|&i| window_64(table, data, i).0 & mask == 0)
+    }
+
+    #[cfg(feature = "gear")]
+    #[test]
+    fn find_32_matches_scalar() {
+        let table = &crate::gear_table::GEAR_32;
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(32);
+        for _ in 0..200 {
+            let mut data = vec![0u8; rng.gen_range(31, 20000)];
+            rng.fill_bytes(&mut data);
+            let bits = rng.gen_range(1, 14);
+            let mask = ((1u32 << bits) - 1) << (32 - bits);
+            let xxx = if rng.gen() {
+                0
+            } else {
+                mask & rng.gen::<u32>()
+            };
+            let from = rng.gen_range(31, data.len() + 1);
+
+            let expected = find_32_scalar(table, mask, xxx, &data, from);
+            if data.len() < 4000 {
+                assert_eq!(expected, naive_32(table, mask, xxx, &data, from));
+            }
+            for &level in &levels() {
+                assert_eq!(
+                    find_32_with(level, table, mask, xxx, &data, from),
+                    expected,
+                    "level: {:?}, len: {}, from: {}, mask: {:#x}",
+                    level,
+                    data.len(),
+                    from,
+                    mask
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "fastcdc")]
+    #[test]
+    fn find_64_matches_scalar() {
+        let table = &crate::gear_table::GEAR_64;
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(64);
+        for _ in 0..200 {
+            let mut data = vec![0u8; rng.gen_range(63, 40000)];
+            rng.fill_bytes(&mut data);
+            // a random subset of the bits of MASK_L-like masks, for varying match rates
+            let mask = rng.gen::<u64>() & rng.gen::<u64>() & rng.gen::<u64>() & 0xffff_ffff_0000;
+            let from = rng.gen_range(63, data.len() + 1);
+
+            let expected = find_64_scalar(table, mask, &data, from);
+            if data.len() < 4000 {
+                assert_eq!(expected, naive_64(table, mask, &data, from));
+            }
+            for &level in &levels() {
+                assert_eq!(
+                    find_64_with(level, table, mask, &data, from),
+                    expected,
+                    "level: {:?}, len: {}, from: {}, mask: {:#x}",
+                    level,
+                    data.len(),
+                    from,
+                    mask
+                );
+            }
+        }
+    }
+
+    #[cfg(all(feature = "gear", feature = "fastcdc"))]
+    #[test]
+    fn find_low_entropy() {
+        // matches in every lane of a block, or in none
+        let data = vec![0u8; 50000];
+        for &level in &levels() {
+            assert_eq!(
+                find_32_with(level, &crate::gear_table::GEAR_32, 0, 0, &data, 31),
+                Some(31)
+            );
+            assert_eq!(
+                find_32_with(level, &crate::gear_table::GEAR_32, 1, 1, &data, 31),
+                find_32_scalar(&crate::gear_table::GEAR_32, 1, 1, &data, 31),
+            );
+            assert_eq!(
+                find_64_with(level, &crate::gear_table::GEAR_64, u64::MAX, &data, 100),
+                find_64_scalar(&crate::gear_table::GEAR_64, u64::MAX, &data, 100),
+            );
+        }
+    }
+}
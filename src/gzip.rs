@@ -46,12 +46,22 @@
 //!
 //! Trigger splits when H(n) == 0
 
+use crate::config::ConfigError;
+use crate::lookback::Lookback;
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SizeDisplay, SpecError};
 use crate::{Chunk, ChunkIncr, ToChunkIncr};
-use std::collections::VecDeque;
+use std::fmt;
 use std::num::Wrapping;
+use std::str::FromStr;
 
 /// Parameters for defining the gzip rsyncable algorithm
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "GzipRsyncableSerde")
+)]
 pub struct GzipRsyncable {
     /*
      * TODO: if we can avoid loading entire files into memory, this could be u64
@@ -60,12 +70,54 @@ pub struct GzipRsyncable {
     modulus: u64,
 }
 
+/// Serialized form of [`GzipRsyncable`], checked by
+/// [`GzipRsyncable::try_with_window_and_modulus()`] when deserialized
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "GzipRsyncable")]
+struct GzipRsyncableSerde {
+    window_len: usize,
+    modulus: u64,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<GzipRsyncableSerde> for GzipRsyncable {
+    type Error = ConfigError;
+
+    fn try_from(s: GzipRsyncableSerde) -> Result<Self, Self::Error> {
+        GzipRsyncable::try_with_window_and_modulus(s.window_len, s.modulus)
+    }
+}
+
 impl GzipRsyncable {
+    /// Create an instance summing the last `window` bytes, and splitting when the sum is a
+    /// multiple of `modulus`
+    ///
+    /// # Panics
+    ///
+    /// If `modulus` is 0. See [`GzipRsyncable::try_with_window_and_modulus()`].
     pub fn with_window_and_modulus(window: usize, modulus: u64) -> GzipRsyncable {
-        Self {
+        Self::try_with_window_and_modulus(window, modulus).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create an instance summing the last `window` bytes, and splitting when the sum is a
+    /// multiple of `modulus`, or return an error if `modulus` is 0
+    pub fn try_with_window_and_modulus(
+        window: usize,
+        modulus: u64,
+    ) -> Result<GzipRsyncable, ConfigError> {
+        if modulus == 0 {
+            return Err(ConfigError::invalid(
+                "gzip",
+                "modulus",
+                0,
+                "must not be zero",
+            ));
+        }
+        Ok(Self {
             window_len: window,
             modulus,
-        }
+        })
     }
 }
 
@@ -75,6 +127,46 @@ impl Default for GzipRsyncable {
     }
 }
 
+/// Formats as `gzip:window=<size>,modulus=<modulus>`
+///
+/// See [`crate::spec`] for details.
+impl fmt::Display for GzipRsyncable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "gzip:window={},modulus={}",
+            SizeDisplay(self.window_len as u64),
+            self.modulus
+        )
+    }
+}
+
+/// Parses the format emitted by [`GzipRsyncable`]'s `Display` impl
+///
+/// `modulus` must not be zero, and `window` must be no larger than [`crate::spec::MAX_WINDOW`].
+impl FromStr for GzipRsyncable {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("gzip", s)?;
+        let d = GzipRsyncable::default();
+        let window_len = match p.take("window") {
+            None => d.window_len,
+            Some(v) => match crate::spec::parse_size(v) {
+                Some(w) if w <= crate::spec::MAX_WINDOW => w as usize,
+                Some(_) => return Err(p.invalid("window", v, "too large")),
+                None => return Err(p.invalid("window", v, "expected a byte size")),
+            },
+        };
+        let modulus = p.take_int("modulus", u64::MAX)?.unwrap_or(d.modulus);
+        if modulus == 0 {
+            return Err(p.invalid("modulus", "0", "must not be zero"));
+        }
+        p.finish()?;
+        Ok(GzipRsyncable::with_window_and_modulus(window_len, modulus))
+    }
+}
+
 impl Chunk for GzipRsyncable {
     type SearchState = GzipRsyncableSearchState;
 
@@ -109,6 +201,13 @@ impl From<&GzipRsyncable> for GzipRsyncableIncr {
     }
 }
 
+impl CutReasons for GzipRsyncable {
+    /// Always [`CutReason::Hash`]
+    fn cut_reason(&self, _len: u64) -> CutReason {
+        CutReason::Hash
+    }
+}
+
 impl ToChunkIncr for GzipRsyncable {
     type Incr = GzipRsyncableIncr;
     fn to_chunk_incr(&self) -> Self::Incr {
@@ -145,9 +244,9 @@ impl GzipRsyncableSearchState {
 
 /// Provides an incremental interface to [`GzipRsyncable`]
 ///
-/// Performance Note: [`GzipRsyncable`] requires look-back. As a result, [`GzipRsyncableIncr`] internally
-/// buffers data up to the window size. This additional copying may affect performance. If
-/// possible for your use case, use the non-incremental interface.
+/// [`GzipRsyncable`] requires look-back. [`GzipRsyncableIncr`] reads bytes leaving the window from
+/// the data passed to `push()` where possible, and only keeps the last `window_len` bytes of
+/// each slice.
 ///
 /// See [`GzipRsyncable`] for details on the underlying algorithm
 #[derive(Debug, Clone)]
@@ -155,24 +254,16 @@ pub struct GzipRsyncableIncr {
     params: GzipRsyncable,
 
     accum: Wrapping<u64>,
-    // really poor efficiency
-    window: VecDeque<u8>,
-}
-
-impl GzipRsyncableIncr {
-    fn reset(&mut self) {
-        self.window.clear();
-        self.accum = Wrapping(0);
-    }
+    lookback: Lookback,
 }
 
 impl From<GzipRsyncable> for GzipRsyncableIncr {
     fn from(params: GzipRsyncable) -> Self {
-        let window = VecDeque::with_capacity(params.window_len);
+        let lookback = Lookback::new(params.window_len);
         GzipRsyncableIncr {
             params,
             accum: Wrapping(0),
-            window,
+            lookback,
         }
     }
 }
@@ -190,12 +281,11 @@ impl GzipRsyncableState {
 impl ChunkIncr for GzipRsyncableIncr {
     fn push(&mut self, data: &[u8]) -> Option<usize> {
         for (i, &v) in data.iter().enumerate() {
-            if self.window.len() >= self.params.window_len {
-                self.accum -= Wrapping(self.window.pop_front().unwrap() as u64);
+            if let Some(o) = self.lookback.dropped(data, i) {
+                self.accum -= Wrapping(o as u64);
             }
 
             self.accum += Wrapping(v as u64);
-            self.window.push_back(v);
 
             if (self.accum % Wrapping(self.params.modulus)).0 == 0 {
                 self.reset();
@@ -203,6 +293,12 @@ impl ChunkIncr for GzipRsyncableIncr {
             }
         }
 
+        self.lookback.extend(data);
         None
     }
+
+    fn reset(&mut self) {
+        self.lookback.clear();
+        self.accum = Wrapping(0);
+    }
 }
@@ -16,6 +16,8 @@
 //!  (like [`Bup`]). These can be thought of as "parameters" for an algorithm.
 //! - Incrimental (impliments [`ChunkIncr`]). Normally named with `Incr` suffix. These are created
 //!   using [`ToChunkIncr`] for a configured algorithm instance.
+//! - Runtime selected algorithms. [`dyn_chunker::DynChunker`] is an enum over the configured
+//!   algorithm instances, and [`DynChunk`] allows using `Box<dyn DynChunk>` for any algorithm.
 //!
 //! Because of the various ways one might use a CDC, and the different CDC algorithm
 //! characteristics, hash-roll provides a few ways to use them.
@@ -27,11 +29,22 @@
 //! methods to obtain other kinds of APIs, like incrimental style apis.
 //!
 //! ```rust
+//! # #[cfg(feature = "mii")] {
 //! use hash_roll::ToChunkIncr;
 //! let algorithm_instance = hash_roll::mii::Mii::default();
 //! let _incrimental_comp = algorithm_instance.to_chunk_incr();
+//! # }
 //! ```
 //!
+//! ## Optional Features
+//!
+//! - `serde`: impliments `Serialize` and `Deserialize` for the configured algorithm instances,
+//!   allowing the parameters used to chunk some data to be stored alongside it.
+//! - `blake3`, `sha256`, `xxh3`: digest backends for [`digest::HashingChunker`]
+//! - `rayon`: multi-threaded chunking of in-memory buffers with `par::par_chunk_ranges()`
+//! - `testing`: `conformance`, which checks that an algorithm finds the same chunk edges through
+//!   each of its interfaces
+//!
 //! ## CDC Algorithms and Window Buffering
 //!
 //! Different CDC algorithms have different constraints about how they process data. Notably, some
@@ -42,11 +55,11 @@
 //! For the window-buffering algorithms, their is an extra cost to certain types of API
 //! implimentations. The documentation will note when these occur and suggest alternatives.
 //!
-//! Generally, CDC interfaces that are incrimental will be slower for window-buffering algorithms.
-//! Using an explicitly allocating interface (which emits `Vec<u8>` or `Vec<Vec<u8>>`) will have no
-//! worse performance that the incrimental API, but might be more convenient. Using an all-at-once
-//! API will provide the best performance due to not requiring any buffering (the input data can be
-//! used directly).
+//! The incremental (`ChunkIncr`) implementations of the window-buffering algorithms read the bytes
+//! leaving the window directly from the slice passed to `push()`, and only copy the last window's
+//! worth of each slice so the next `push()` can continue. Their cost is close to that of the
+//! all-at-once API, though pushing many very small slices adds per-call overhead. Using an
+//! all-at-once API still provides the best performance, as the input data is used directly.
 //!
 //! ## Use Cases that drive API choices
 //!
@@ -98,22 +111,48 @@
  * rollsum of librsync
  */
 
+use std::any::Any;
 use std::mem;
+use std::ops::Range;
 
+pub mod bounded;
+pub mod buffer;
 pub mod bup;
 pub mod buzhash;
 pub mod buzhash_table;
+pub mod config;
+pub mod conformance;
+pub mod digest;
+pub mod dyn_chunker;
 pub mod fastcdc;
 pub mod gear;
+mod gear_simd;
 pub mod gear_table;
 pub mod gzip;
+mod lookback;
 pub mod mii;
+pub mod multi;
+pub mod offset;
+#[cfg(feature = "rayon")]
+pub mod par;
 pub mod pigz;
 pub mod ram;
 pub mod range;
+pub mod reason;
+pub mod runs;
+mod serde_table;
+#[cfg(target_os = "linux")]
+pub mod sparse;
+pub mod spec;
 pub mod zpaq;
 pub mod zstd;
 
+/// See `gear_simd::set_enabled()`. Only for benchmarks, not part of the public API.
+#[doc(hidden)]
+#[cfg(any(feature = "gear", feature = "fastcdc"))]
+pub use gear_simd::set_enabled as __set_gear_simd_enabled;
+
+#[cfg(feature = "zpaq")]
 pub(crate) use range::RangeExt;
 
 /// Accept incrimental input and provide indexes of split points
@@ -124,14 +163,16 @@ pub(crate) use range::RangeExt;
 ///
 /// Data fed into a given [`ChunkIncr`] instance is considered to be part of the same
 /// data "source". This affects chunking algorithms that maintain some state between chunks
-/// (like `ZstdRsyncable` does). If you have multiple "sources", one should obtain new instances of
-/// [`ChunkIncr`] for each of them (typically via [`ToChunkIncr`]).
+/// (like [`runs::Runs`] does). If you have multiple "sources", one should obtain new instances of
+/// [`ChunkIncr`] for each of them (typically via [`ToChunkIncr`]), call [`ChunkIncr::reset()`]
+/// between them, or use [`ChunkIncr::force_boundary()`] to chunk them as a single stream with an
+/// edge at the start of each source.
 ///
 /// Note that for some splitting/chunking algorithms, the incrimental api will be less efficient
 /// compared to the non-incrimental API. In particular, algorithms like [`Rsyncable`] that require
-/// the use of previously examined data to shift their "window" (resulting in needing a circular
-/// buffer which all inputed data passes through) will perform more poorly using [`ChunkIncr`]
-/// compared with non-incrimental interfaces
+/// the use of previously examined data to shift their "window" read it from the slice passed to
+/// [`ChunkIncr::push()`] where they can, but must also keep a copy of the last window's worth of
+/// each slice for use at the start of the next one.
 pub trait ChunkIncr {
     /// The data "contained" within a implimentor of this trait is the history of all data slices
     /// passed to feed.
@@ -142,10 +183,168 @@ pub trait ChunkIncr {
     /// Returns None if the data has no split point.
     /// Otherwise, returns an index in the most recently passed `data`.
     ///
+    /// The index is where the next chunk starts, and is the same edge [`Chunk::find_chunk_edge()`]
+    /// finds given the same input. The state is reset at the edge, so the data following it
+    /// should be passed to the next call.
+    ///
     /// Note that returning the index in the current slice makes most "look-ahead" splitting
     /// impossible (as it is permissible to pass 1 byte at a time).
+    ///
+    /// [`offset::OffsetTracking`] reports chunks by their offsets in the entire stream instead.
     fn push(&mut self, data: &[u8]) -> Option<usize>;
 
+    /// Return to the state of a newly created instance, so it can be used for a new input
+    ///
+    /// Allocations (like the window kept by window-buffering algorithms) are reused, so resetting
+    /// an instance is cheaper than obtaining a new one via [`ToChunkIncr`] for each input.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "bup")] {
+    /// use hash_roll::{ChunkIncr, ToChunkIncr};
+    ///
+    /// let mut incr = hash_roll::bup::RollSum::default().to_chunk_incr();
+    /// for file in [&b"some data"[..], &b"more data"[..]].iter() {
+    ///     incr.reset();
+    ///     for chunk in (&mut incr).iter_slices(file) {
+    ///         println!("chunk: {:?}", chunk);
+    ///     }
+    /// }
+    /// # }
+    /// ```
+    fn reset(&mut self);
+
+    /// Place a chunk edge at the current position (the end of the data pushed so far), restarting
+    /// the algorithm as if a new source started there
+    ///
+    /// This is useful when several sources are concatenated into one stream (like files in an
+    /// archive), but each source should start a new chunk. The caller treats the current position
+    /// as an edge exactly as if [`ChunkIncr::push()`] had returned it (if no data was pushed since
+    /// the last edge, there is no chunk to emit). [`offset::OffsetTracking::force_boundary()`]
+    /// does this bookkeeping.
+    ///
+    /// Any state an algorithm keeps between chunks is defined by that algorithm. For the
+    /// algorithms in this crate:
+    ///
+    ///  - [`bup`], [`buzhash`], [`fastcdc`], [`gear`], [`gzip`], [`mii`], [`pigz`], [`ram`] and
+    ///    [`zpaq`] already clear their hash and window at every edge, so no state survives.
+    ///  - [`zstd`] continues its rolling hash (and the window it is computed over) across edges,
+    ///    as zstd does. A forced boundary clears them.
+    ///  - [`bounded::BoundedIncr`] restarts counting the size of the chunk, and forces a boundary
+    ///    in the algorithm it wraps.
+    ///  - [`runs::RunsIncr`] normally tracks runs across edges. A forced boundary ends any run,
+    ///    so a run at the end of one source does not continue into the next. It also forces a
+    ///    boundary in the algorithm it wraps.
+    ///
+    /// As a result, the edges found in the data following a forced boundary are the same as the
+    /// edges found by a new instance.
+    ///
+    /// The default calls [`ChunkIncr::reset()`].
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "mii")] {
+    /// use hash_roll::{ChunkIncr, ToChunkIncr};
+    ///
+    /// let mut incr = hash_roll::mii::Mii::default().to_chunk_incr();
+    /// let mut chunks = Vec::new();
+    /// for file in [&b"some data"[..], &b"more data"[..]].iter() {
+    ///     let mut rem = *file;
+    ///     while let Some(i) = incr.push(rem) {
+    ///         chunks.push(&rem[..i]);
+    ///         rem = &rem[i..];
+    ///     }
+    ///     // the rest of the file is the last chunk it contains
+    ///     incr.force_boundary();
+    ///     if !rem.is_empty() {
+    ///         chunks.push(rem);
+    ///     }
+    /// }
+    /// assert_eq!(chunks.concat(), b"some datamore data");
+    /// # }
+    /// ```
+    fn force_boundary(&mut self) {
+        self.reset()
+    }
+
+    /// Push all of `data`, appending the index in `data` of each split point to `out`
+    ///
+    /// This is equivalent to calling [`ChunkIncr::push()`] again on the remainder of `data` after
+    /// each split point, but without re-entering the chunker once per chunk. Several algorithms
+    /// provide a specialized implementation that scans the entire slice in a single loop.
+    ///
+    /// Indexes are appended in increasing order. Existing elements of `out` are left as-is. Data
+    /// after the last split point is part of the next chunk, exactly as with `push()`.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "zpaq")] {
+    /// use hash_roll::{ChunkIncr, ToChunkIncr};
+    ///
+    /// let data = vec![7u8; 100_000];
+    /// let mut incr = hash_roll::zpaq::Zpaq::default().to_chunk_incr();
+    /// let mut edges = Vec::new();
+    /// incr.push_all(&data, &mut edges);
+    /// assert!(edges.windows(2).all(|w| w[0] < w[1]));
+    /// # }
+    /// ```
+    fn push_all(&mut self, data: &[u8], out: &mut Vec<usize>) {
+        let mut pos = 0;
+        while let Some(i) = self.push(&data[pos..]) {
+            pos += i;
+            out.push(pos);
+        }
+    }
+
+    /// Push all of `data`, passing each piece of a chunk it contains to `f` as a [`Fragment`]
+    ///
+    /// Chunks may span many calls to `push_with()`. Each call passes the pieces of `data` in
+    /// order, so concatenating the fragments passed since the last one with
+    /// [`Fragment::ends_chunk`] set gives the contents of the chunk. The data is borrowed from
+    /// `data`, so chunks of any size can be processed (hashed, uploaded, ...) without assembling
+    /// them in memory.
+    ///
+    /// The fragments following the last one that ends a chunk are part of the next chunk. At the
+    /// end of the input, they form the final chunk (which does not end on a chunk edge).
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "mii")] {
+    /// use hash_roll::{ChunkIncr, Fragment, ToChunkIncr};
+    ///
+    /// let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+    /// let mut incr = hash_roll::mii::Mii::default().to_chunk_incr();
+    /// let mut chunk_lens = vec![0];
+    /// for piece in data.chunks(1000) {
+    ///     incr.push_with(piece, |Fragment { bytes, ends_chunk }| {
+    ///         *chunk_lens.last_mut().unwrap() += bytes.len();
+    ///         if ends_chunk {
+    ///             chunk_lens.push(0);
+    ///         }
+    ///     });
+    /// }
+    /// assert_eq!(chunk_lens.iter().sum::<usize>(), data.len());
+    /// # }
+    /// ```
+    fn push_with<F>(&mut self, data: &[u8], mut f: F)
+    where
+        Self: std::marker::Sized,
+        F: FnMut(Fragment<'_>),
+    {
+        let mut rem = data;
+        while let Some(i) = self.push(rem) {
+            let (bytes, rn) = rem.split_at(i);
+            f(Fragment {
+                bytes,
+                ends_chunk: true,
+            });
+            rem = rn;
+        }
+
+        if !rem.is_empty() {
+            f(Fragment {
+                bytes: rem,
+                ends_chunk: false,
+            });
+        }
+    }
+
     /// Given a [`ChunkIncr`] and a single slice, return a list of slices chunked by the chunker.
     ///
     /// Will always return enough slices to form the entire content of `data`, even if the trailing
@@ -177,6 +376,42 @@ pub trait ChunkIncr {
     }
 }
 
+impl<C: ChunkIncr + ?Sized> ChunkIncr for &mut C {
+    fn push(&mut self, data: &[u8]) -> Option<usize> {
+        (**self).push(data)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn force_boundary(&mut self) {
+        (**self).force_boundary()
+    }
+
+    fn push_all(&mut self, data: &[u8], out: &mut Vec<usize>) {
+        (**self).push_all(data, out)
+    }
+}
+
+impl<C: ChunkIncr + ?Sized> ChunkIncr for Box<C> {
+    fn push(&mut self, data: &[u8]) -> Option<usize> {
+        (**self).push(data)
+    }
+
+    fn reset(&mut self) {
+        (**self).reset()
+    }
+
+    fn force_boundary(&mut self) {
+        (**self).force_boundary()
+    }
+
+    fn push_all(&mut self, data: &[u8], out: &mut Vec<usize>) {
+        (**self).push_all(data, out)
+    }
+}
+
 /// Returned by [`ChunkIncr::iter_slices_strict()`]
 ///
 /// Always emits _complete_ slices durring iteration.
@@ -258,6 +493,18 @@ impl<'a, C: ChunkIncr> Iterator for IterSlices<'a, C> {
     }
 }
 
+/// A piece of a chunk, passed by [`ChunkIncr::push_with()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fragment<'a> {
+    /// The next bytes of the chunk, borrowed from the data being pushed
+    ///
+    /// This is only empty when a chunk ends exactly where the previous data pushed ended.
+    pub bytes: &'a [u8],
+
+    /// Whether `bytes` are the last bytes of the chunk
+    pub ends_chunk: bool,
+}
+
 /// Impl on algorthms that define methods of chunking data
 ///
 /// This is the lowest level (but somewhat restrictive) trait for chunking algorthms.  It assumes
@@ -282,9 +529,64 @@ pub trait Chunk {
     */
 
     /// Provide an initial [`SearchState`] for use with [`find_chunk_edge()`]. Generally, for each
-    /// input one should generate a new [`SearchState`].
+    /// input one should generate a new [`SearchState`] (or reset an existing one with
+    /// [`Chunk::reset_search_state()`]).
     fn to_search_state(&self) -> Self::SearchState;
 
+    /// Return `state` to the state provided by [`Chunk::to_search_state()`], so it can be used
+    /// for a new input
+    ///
+    /// The default replaces `state` with a new one. Implementations whose [`Chunk::SearchState`]
+    /// allocates override this to reuse the allocation.
+    fn reset_search_state(&self, state: &mut Self::SearchState) {
+        *state = self.to_search_state();
+    }
+
+    /// Place a chunk edge at the end of the data passed to [`Chunk::find_chunk_edge()`] so far,
+    /// restarting the algorithm as if a new source started there
+    ///
+    /// The next `data` passed to [`Chunk::find_chunk_edge()`] must start at the boundary: all
+    /// earlier data is discarded, exactly as if `find_chunk_edge()` had returned the boundary as
+    /// an edge. See [`ChunkIncr::force_boundary()`] for which state each algorithm keeps (the same
+    /// applies to its [`Chunk::SearchState`]). [`buffer::ChunkBuffer::force_boundary()`] does this
+    /// bookkeeping.
+    ///
+    /// The default calls [`Chunk::reset_search_state()`].
+    fn force_boundary(&self, state: &mut Self::SearchState) {
+        self.reset_search_state(state)
+    }
+
+    /// Whether the state is reset after each edge, so the edges following an edge depend only on
+    /// the data after it
+    ///
+    /// When `true`, once [`Chunk::find_chunk_edge()`] returns an edge, `state` must be equivalent
+    /// to a new one from [`Chunk::to_search_state()`], with the returned `discard_ct` covering
+    /// all the data up to the edge. `par::par_chunk_ranges()` (with the `rayon` feature) relies on
+    /// this to chunk parts of its input independently, and chunks sequentially for algorithms
+    /// where it is `false`.
+    ///
+    /// This is `true` for every algorithm in this crate except [`zstd`], whose rolling hash
+    /// continues across edges. Wrappers which carry state from one chunk to the next (like
+    /// [`runs::Runs`]) return `false`, and so must any other implementation that does.
+    fn resets_at_edges(&self) -> bool {
+        true
+    }
+
+    /// For algorithms that don't reset after each edge, the number of bytes that determine
+    /// whether there is an edge, if that is bounded
+    ///
+    /// When `Some(w)`, no edge is placed within the first `w` bytes, and whether there is an edge
+    /// after any later byte depends only on the `w` bytes ending with it. Starting
+    /// [`Chunk::find_chunk_edge()`] with a new state `w` bytes before a position then finds the
+    /// same edges after that position as chunking from the start of the data.
+    /// `par::par_chunk_ranges()` uses this to chunk parts of its input independently for
+    /// algorithms where [`Chunk::resets_at_edges()`] is `false`.
+    ///
+    /// The default is `None`. [`zstd`] returns the length of its rolling hash window.
+    fn edge_window(&self) -> Option<usize> {
+        None
+    }
+
     /// Find the next "chunk" in `data` to emit
     ///
     /// The return value is a pair of a range representing the start and end of the chunk being
@@ -299,6 +601,7 @@ pub trait Chunk {
     /// the next `data` buffer called.
     ///
     /// ```rust
+    /// # #[cfg(feature = "mii")] {
     /// use hash_roll::Chunk;
     ///
     /// fn some_chunk() -> impl Chunk {
@@ -331,8 +634,13 @@ pub trait Chunk {
     ///
     ///    data = &data[discard_ct..];
     /// }
+    /// # }
     /// ```
     ///
+    /// If all of the input is available in a single slice, [`Chunk::chunk_ranges()`] and
+    /// [`Chunk::chunk_slices()`] perform this bookkeeping. Otherwise, [`buffer::ChunkBuffer`]
+    /// performs it while owning the data.
+    ///
     /// Note: call additional times on the same `SearchState` and the required `data` to obtain
     /// subsequent chunks in the same input data. To handle a seperate input, use a new
     /// `SearchState`.
@@ -342,8 +650,8 @@ pub trait Chunk {
     /// underlying algorithm. Avoid relying on consistent cut points to reason about memory safety.
     ///
     // NOTE: the reason that we preserve `state` even when chunks are emitted is that some
-    // algorthims require some state to pass between chunks for a given input. zstd includes an
-    // example of an algorithm that needs this
+    // algorthims require some state to pass between chunks for a given input. `runs::Runs` includes
+    // an example of an algorithm that needs this
     //
     // Potential pitfal: for better performance, keeping the return value small is a very good
     // idea. By returning ~2x64+32, we are might be less performant depending on the ABI selected.
@@ -351,8 +659,6 @@ pub trait Chunk {
     // Consider if result should return `(&[u8], &[u8])` instead of an index (which would then be
     // given to `.split_at()`
     //
-    // Consider if `state` should have a `reset()` method to avoid reallocating
-    //
     // API:
     //  - `fn find_chunk_edge(&self, state: &mut Self::SearchState, data: &[u8]) -> (Option<(usize, uszie)>, usize);
     //     - Problem: unclear what indexes of slices represent: start can't be in the data being
@@ -363,6 +669,381 @@ pub trait Chunk {
     //     being the "slice we need to pass start"
     fn find_chunk_edge(&self, state: &mut Self::SearchState, data: &[u8])
         -> (Option<usize>, usize);
+
+    /// Find all the chunk edges in `data`, appending the index in `data` of each to `out`
+    ///
+    /// Returns the `discard_ct` for the data following the last edge: the next call to
+    /// `find_chunk_edge()` or `find_chunk_edges()` with `state` must be passed `data` starting at
+    /// that index (extended with any additional data).
+    ///
+    /// This is equivalent to calling [`Chunk::find_chunk_edge()`] until it returns no edge, but
+    /// several algorithms provide a specialized implementation that scans `data` in a single
+    /// loop. Indexes are appended in increasing order, and existing elements of `out` are
+    /// left as-is.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "ram")] {
+    /// use hash_roll::Chunk;
+    ///
+    /// let chunk = hash_roll::ram::Ram::with_w(64);
+    /// let data: Vec<u8> = (0..10_000u32).map(|i| (i * 7919 % 251) as u8).collect();
+    /// let mut ss = chunk.to_search_state();
+    /// let mut edges = Vec::new();
+    /// let discard_ct = chunk.find_chunk_edges(&mut ss, &data, &mut edges);
+    ///
+    /// let ends: Vec<usize> = chunk.chunk_ranges(&data).map(|r| r.end).collect();
+    /// assert_eq!(&ends[..edges.len()], &edges[..]);
+    /// assert!(discard_ct <= data.len());
+    /// # }
+    /// ```
+    fn find_chunk_edges(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        let mut discard = 0;
+        loop {
+            let (cut, discard_ct) = self.find_chunk_edge(state, &data[discard..]);
+            match cut {
+                Some(cut_point) => {
+                    out.push(discard + cut_point);
+                    discard += discard_ct;
+                }
+                None => return discard + discard_ct,
+            }
+        }
+    }
+
+    /// Iterate over the ranges of each chunk in `data`, where `data` is the entire input.
+    ///
+    /// The ranges are indexes into `data`. The trailing part of `data` that does not end on a
+    /// chunk edge (if any) is emitted as the last range, so the ranges always cover all of
+    /// `data`.
+    ///
+    /// This uses [`Chunk::find_chunk_edge()`] directly on `data` and does not buffer or copy any
+    /// of the input.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "mii")] {
+    /// use hash_roll::Chunk;
+    ///
+    /// let chunk = hash_roll::mii::Mii::default();
+    /// let data = b"hello";
+    /// for range in chunk.chunk_ranges(&data[..]) {
+    ///     println!("chunk: {:?}", &data[range]);
+    /// }
+    /// # }
+    /// ```
+    fn chunk_ranges<'a>(&'a self, data: &'a [u8]) -> ChunkRanges<'a, Self>
+    where
+        Self: std::marker::Sized,
+    {
+        ChunkRanges {
+            chunker: self,
+            state: self.to_search_state(),
+            data,
+            discard: 0,
+            last_cut: 0,
+        }
+    }
+
+    /// Iterate over the slices of each chunk in `data`, where `data` is the entire input.
+    ///
+    /// Equivalent to [`Chunk::chunk_ranges()`], but emits sub-slices of `data` instead of ranges.
+    fn chunk_slices<'a>(&'a self, data: &'a [u8]) -> ChunkSlices<'a, Self>
+    where
+        Self: std::marker::Sized,
+    {
+        ChunkSlices {
+            ranges: self.chunk_ranges(data),
+        }
+    }
+}
+
+impl<C: Chunk + ?Sized> Chunk for &C {
+    type SearchState = C::SearchState;
+
+    fn to_search_state(&self) -> Self::SearchState {
+        (**self).to_search_state()
+    }
+
+    fn reset_search_state(&self, state: &mut Self::SearchState) {
+        (**self).reset_search_state(state)
+    }
+
+    fn force_boundary(&self, state: &mut Self::SearchState) {
+        (**self).force_boundary(state)
+    }
+
+    fn resets_at_edges(&self) -> bool {
+        (**self).resets_at_edges()
+    }
+
+    fn edge_window(&self) -> Option<usize> {
+        (**self).edge_window()
+    }
+
+    fn find_chunk_edge(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+    ) -> (Option<usize>, usize) {
+        (**self).find_chunk_edge(state, data)
+    }
+
+    fn find_chunk_edges(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        (**self).find_chunk_edges(state, data, out)
+    }
+}
+
+/// Returned by [`Chunk::chunk_ranges()`]
+///
+/// When it runs out of data, it returns the remainder as the last element of the iteration
+pub struct ChunkRanges<'a, C: Chunk> {
+    chunker: &'a C,
+    state: C::SearchState,
+    data: &'a [u8],
+
+    /// index in `data` of the first byte we still need to pass to `find_chunk_edge()`
+    discard: usize,
+
+    /// index in `data` of the end of the previously emitted chunk
+    last_cut: usize,
+}
+
+impl<'a, C: Chunk + std::fmt::Debug> std::fmt::Debug for ChunkRanges<'a, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkRanges")
+            .field("chunker", &self.chunker)
+            .field("data_len", &self.data.len())
+            .field("discard", &self.discard)
+            .field("last_cut", &self.last_cut)
+            .finish()
+    }
+}
+
+impl<'a, C: Chunk> Iterator for ChunkRanges<'a, C> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.last_cut == self.data.len() {
+            return None;
+        }
+
+        let (cut, discard_ct) = self
+            .chunker
+            .find_chunk_edge(&mut self.state, &self.data[self.discard..]);
+        match cut {
+            Some(cut_point) => {
+                // map `cut_point` from the slice we passed back into `data`
+                let cut_point = self.discard + cut_point;
+                let r = self.last_cut..cut_point;
+                self.last_cut = cut_point;
+                self.discard += discard_ct;
+                Some(r)
+            }
+            None => {
+                // no more edges: emit the remainder
+                let r = self.last_cut..self.data.len();
+                self.last_cut = self.data.len();
+                self.discard = self.data.len();
+                Some(r)
+            }
+        }
+    }
+}
+
+/// Returned by [`Chunk::chunk_slices()`]
+///
+/// When it runs out of data, it returns the remainder as the last element of the iteration
+pub struct ChunkSlices<'a, C: Chunk> {
+    ranges: ChunkRanges<'a, C>,
+}
+
+impl<'a, C: Chunk + std::fmt::Debug> std::fmt::Debug for ChunkSlices<'a, C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChunkSlices")
+            .field("ranges", &self.ranges)
+            .finish()
+    }
+}
+
+impl<'a, C: Chunk> Iterator for ChunkSlices<'a, C> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let data = self.ranges.data;
+        self.ranges.next().map(|r| &data[r])
+    }
+}
+
+/// Object safe version of [`Chunk`] and [`ToChunkIncr`]
+///
+/// [`Chunk`] and [`ToChunkIncr`] have associated types, which prevents using them as trait
+/// objects. [`DynChunk`] boxes those associated types, allowing a configured algorithm to be
+/// stored as a `Box<dyn DynChunk>` and selected at runtime.
+///
+/// This is implimented for every type that implements both [`Chunk`] and [`ToChunkIncr`] (with
+/// `'static` states), and `Box<dyn DynChunk>` implements [`Chunk`] and [`ToChunkIncr`] in turn.
+///
+/// If the set of algorithms is known ahead of time, [`dyn_chunker::DynChunker`] avoids the
+/// allocations and dynamic dispatch.
+pub trait DynChunk {
+    /// Boxed version of [`Chunk::to_search_state()`]
+    fn to_search_state_dyn(&self) -> Box<dyn Any>;
+
+    /// Version of [`Chunk::reset_search_state()`] using a state from
+    /// [`DynChunk::to_search_state_dyn()`].
+    ///
+    /// # Panics
+    ///
+    /// If `state` was not created by this instance's [`DynChunk::to_search_state_dyn()`]
+    fn reset_search_state_dyn(&self, state: &mut dyn Any);
+
+    /// Version of [`Chunk::force_boundary()`] using a state from
+    /// [`DynChunk::to_search_state_dyn()`].
+    ///
+    /// # Panics
+    ///
+    /// If `state` was not created by this instance's [`DynChunk::to_search_state_dyn()`]
+    fn force_boundary_dyn(&self, state: &mut dyn Any);
+
+    /// Version of [`Chunk::resets_at_edges()`]
+    fn resets_at_edges_dyn(&self) -> bool;
+
+    /// Version of [`Chunk::edge_window()`]
+    fn edge_window_dyn(&self) -> Option<usize>;
+
+    /// Version of [`Chunk::find_chunk_edge()`] using a state from
+    /// [`DynChunk::to_search_state_dyn()`].
+    ///
+    /// # Panics
+    ///
+    /// If `state` was not created by this instance's [`DynChunk::to_search_state_dyn()`]
+    fn find_chunk_edge_dyn(&self, state: &mut dyn Any, data: &[u8]) -> (Option<usize>, usize);
+
+    /// Version of [`Chunk::find_chunk_edges()`] using a state from
+    /// [`DynChunk::to_search_state_dyn()`].
+    ///
+    /// # Panics
+    ///
+    /// If `state` was not created by this instance's [`DynChunk::to_search_state_dyn()`]
+    fn find_chunk_edges_dyn(&self, state: &mut dyn Any, data: &[u8], out: &mut Vec<usize>)
+        -> usize;
+
+    /// Boxed version of [`ToChunkIncr::to_chunk_incr()`]
+    fn to_chunk_incr_dyn(&self) -> Box<dyn ChunkIncr>;
+}
+
+impl<C> DynChunk for C
+where
+    C: Chunk + ToChunkIncr,
+    C::SearchState: 'static,
+    C::Incr: 'static,
+{
+    fn to_search_state_dyn(&self) -> Box<dyn Any> {
+        Box::new(self.to_search_state())
+    }
+
+    fn reset_search_state_dyn(&self, state: &mut dyn Any) {
+        let state = state
+            .downcast_mut::<C::SearchState>()
+            .expect("search state is from a different chunker");
+        self.reset_search_state(state)
+    }
+
+    fn force_boundary_dyn(&self, state: &mut dyn Any) {
+        let state = state
+            .downcast_mut::<C::SearchState>()
+            .expect("search state is from a different chunker");
+        self.force_boundary(state)
+    }
+
+    fn resets_at_edges_dyn(&self) -> bool {
+        self.resets_at_edges()
+    }
+
+    fn edge_window_dyn(&self) -> Option<usize> {
+        self.edge_window()
+    }
+
+    fn find_chunk_edge_dyn(&self, state: &mut dyn Any, data: &[u8]) -> (Option<usize>, usize) {
+        let state = state
+            .downcast_mut::<C::SearchState>()
+            .expect("search state is from a different chunker");
+        self.find_chunk_edge(state, data)
+    }
+
+    fn find_chunk_edges_dyn(
+        &self,
+        state: &mut dyn Any,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        let state = state
+            .downcast_mut::<C::SearchState>()
+            .expect("search state is from a different chunker");
+        self.find_chunk_edges(state, data, out)
+    }
+
+    fn to_chunk_incr_dyn(&self) -> Box<dyn ChunkIncr> {
+        Box::new(self.to_chunk_incr())
+    }
+}
+
+impl Chunk for Box<dyn DynChunk> {
+    type SearchState = Box<dyn Any>;
+
+    fn to_search_state(&self) -> Self::SearchState {
+        (**self).to_search_state_dyn()
+    }
+
+    fn reset_search_state(&self, state: &mut Self::SearchState) {
+        (**self).reset_search_state_dyn(&mut **state)
+    }
+
+    fn force_boundary(&self, state: &mut Self::SearchState) {
+        (**self).force_boundary_dyn(&mut **state)
+    }
+
+    fn resets_at_edges(&self) -> bool {
+        (**self).resets_at_edges_dyn()
+    }
+
+    fn edge_window(&self) -> Option<usize> {
+        (**self).edge_window_dyn()
+    }
+
+    fn find_chunk_edge(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+    ) -> (Option<usize>, usize) {
+        (**self).find_chunk_edge_dyn(&mut **state, data)
+    }
+
+    fn find_chunk_edges(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        (**self).find_chunk_edges_dyn(&mut **state, data, out)
+    }
+}
+
+impl ToChunkIncr for Box<dyn DynChunk> {
+    type Incr = Box<dyn ChunkIncr>;
+
+    fn to_chunk_incr(&self) -> Self::Incr {
+        (**self).to_chunk_incr_dyn()
+    }
 }
 
 /// Implimented on types which can be converted to/can provide a [`ChunkIncr`] interface.
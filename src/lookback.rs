@@ -0,0 +1,160 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "gzip",
+    feature = "zstd"
+))]
+
+//! Look-back for incremental window-buffering algorithms
+//!
+//! Algorithms that "remove" bytes as they leave their window need to know the byte `k` positions
+//! before the one being added. Within a slice passed to `push()`, that byte is read directly from
+//! the slice. Only for the first `k` bytes of a slice is it from a previous `push()`, so only the
+//! last `k` bytes of each slice need to be kept.
+
+/// The last `k` bytes passed to an incremental chunker since it was last cleared
+///
+/// `B` is the storage for the bytes, which determines `k`: a boxed slice for windows sized at
+/// runtime, or an array for windows sized at compile time.
+#[derive(Clone, PartialEq, Eq)]
+pub(crate) struct Lookback<B = Box<[u8]>> {
+    /// Ring buffer of the most recent bytes. `buf[head]` is the oldest.
+    buf: B,
+
+    /// Index in `buf` where the next byte is stored
+    head: usize,
+
+    /// Number of bytes stored since the last clear, saturating at `buf.len()`
+    seen: usize,
+}
+
+impl Lookback {
+    /// Keep the last `k` bytes
+    pub(crate) fn new(k: usize) -> Self {
+        Lookback::with_buf(vec![0; k].into_boxed_slice())
+    }
+}
+
+impl<B: AsRef<[u8]> + AsMut<[u8]>> Lookback<B> {
+    /// Keep the last `buf.len()` bytes in `buf`
+    pub(crate) fn with_buf(buf: B) -> Self {
+        Lookback {
+            buf,
+            head: 0,
+            seen: 0,
+        }
+    }
+
+    /// Forget all previous bytes, without releasing the buffer
+    pub(crate) fn clear(&mut self) {
+        self.head = 0;
+        self.seen = 0;
+    }
+
+    /// Number of bytes kept (`k`)
+    #[cfg(feature = "bup")]
+    pub(crate) fn window_len(&self) -> usize {
+        self.buf.as_ref().len()
+    }
+
+    /// Number of bytes added since the last clear, saturating at `k`
+    #[cfg(feature = "zstd")]
+    pub(crate) fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// The byte `k` positions before `data[i]`, where `data` follows the bytes previously added
+    ///
+    /// Returns `None` if that position is before the last clear.
+    #[inline]
+    pub(crate) fn dropped(&self, data: &[u8], i: usize) -> Option<u8> {
+        let k = self.buf.as_ref().len();
+        if i >= k {
+            Some(data[i - k])
+        } else if self.seen + i >= k {
+            // `k - i` bytes before the start of `data`
+            let j = self.head + i;
+            Some(self.buf.as_ref()[if j >= k { j - k } else { j }])
+        } else {
+            None
+        }
+    }
+
+    /// Add `data`, retaining only the last `k` bytes
+    pub(crate) fn extend(&mut self, data: &[u8]) {
+        let k = self.buf.as_ref().len();
+        if data.len() >= k {
+            self.buf.as_mut().copy_from_slice(&data[data.len() - k..]);
+            self.head = 0;
+            self.seen = k;
+            return;
+        }
+
+        let first = std::cmp::min(k - self.head, data.len());
+        self.buf.as_mut()[self.head..self.head + first].copy_from_slice(&data[..first]);
+        let rest = data.len() - first;
+        self.buf.as_mut()[..rest].copy_from_slice(&data[first..]);
+        self.head = if rest > 0 { rest } else { self.head + first };
+        if self.head == k {
+            self.head = 0;
+        }
+        self.seen = std::cmp::min(self.seen + data.len(), k);
+    }
+}
+
+impl<B: AsRef<[u8]>> std::fmt::Debug for Lookback<B> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let buf = self.buf.as_ref();
+        let ordered: Vec<u8> = buf[self.head..]
+            .iter()
+            .chain(&buf[..self.head])
+            .copied()
+            .collect();
+        let kept = &ordered[ordered.len() - self.seen..];
+        f.debug_struct("Lookback")
+            .field("window_len", &buf.len())
+            .field("bytes", &::fmt_extra::Hs(kept))
+            .finish()
+    }
+}
+
+#[cfg(all(test, feature = "zstd", any(feature = "buzhash", feature = "gzip")))]
+mod test {
+    use super::Lookback;
+    use rand::{Rng, SeedableRng};
+
+    #[test]
+    fn matches_full_history() {
+        let mut rng = rand_pcg::Pcg64::seed_from_u64(0);
+        for &k in &[1, 2, 7, 64] {
+            let mut lb = Lookback::new(k);
+            // all bytes added since the last clear
+            let mut history: Vec<u8> = Vec::new();
+            for _ in 0..500 {
+                if rng.gen_ratio(1, 20) {
+                    lb.clear();
+                    history.clear();
+                }
+
+                let data: Vec<u8> = (0..rng.gen_range(0, 3 * k)).map(|_| rng.gen()).collect();
+                for i in 0..data.len() {
+                    let pos = history.len() + i;
+                    let expected = if pos >= k {
+                        Some(if i >= k {
+                            data[i - k]
+                        } else {
+                            history[pos - k]
+                        })
+                    } else {
+                        None
+                    };
+                    assert_eq!(lb.dropped(&data, i), expected);
+                }
+
+                lb.extend(&data);
+                history.extend_from_slice(&data);
+                assert_eq!(lb.seen(), std::cmp::min(history.len(), k));
+            }
+        }
+    }
+}
@@ -1,14 +1,81 @@
 #![cfg(feature = "mii")]
+use crate::config::ConfigError;
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SizeDisplay, SpecError};
 use crate::{ChunkIncr, ToChunkIncr};
+use std::fmt;
+use std::ops::RangeBounds;
+use std::str::FromStr;
 
 /// C. Zhang et al., "MII: A Novel Content Defined Chunking Algorithm for Finding Incremental Data
 /// in Data Synchronization," in IEEE Access, vol. 7, pp. 86932-86945, 2019, doi:
 /// 10.1109/ACCESS.2019.2926195.
 ///
 /// https://ieeexplore.ieee.org/abstract/document/8752387
+///
+/// A chunk ends after `w` consecutive "increments" (bytes greater than the byte before them). The
+/// first byte of a chunk is never an increment.
+///
+/// In random data a run of `w` increments is `w + 1` strictly increasing bytes, which has
+/// probability `C(256, w + 1) / 256^(w + 1)` at any position, so chunks are small for the small
+/// `w` used in the paper (see [`Mii::expected_chunk_size()`]). Data without long increasing runs
+/// (like runs of a repeated byte, or decreasing data) is never split. [`Mii::with_w_and_range()`]
+/// limits chunk sizes in both directions.
 #[derive(Debug, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "MiiSerde")
+)]
 pub struct Mii {
     w: u64,
+
+    /// bytes at the start of each chunk that are not examined
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "is_zero"))]
+    min: u64,
+
+    /// largest chunk size
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "unbounded", skip_serializing_if = "is_unbounded")
+    )]
+    max: u64,
+}
+
+/// Serialized form of [`Mii`], checked by [`Mii::try_with_w_and_range()`] when deserialized
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Mii")]
+struct MiiSerde {
+    w: u64,
+    #[serde(default)]
+    min: u64,
+    #[serde(default = "unbounded")]
+    max: u64,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<MiiSerde> for Mii {
+    type Error = ConfigError;
+
+    fn try_from(s: MiiSerde) -> Result<Self, Self::Error> {
+        Mii::try_with_w_and_range(s.w, s.min..=s.max)
+    }
+}
+
+#[cfg(feature = "serde")]
+fn is_zero(v: &u64) -> bool {
+    *v == 0
+}
+
+#[cfg(feature = "serde")]
+fn unbounded() -> u64 {
+    u64::MAX
+}
+
+#[cfg(feature = "serde")]
+fn is_unbounded(v: &u64) -> bool {
+    *v == u64::MAX
 }
 
 impl Mii {
@@ -16,18 +83,148 @@ impl Mii {
     ///
     /// `w` is the number of "increments" (positive changes in byte value) after which we split the
     /// input
-    ///
-    // TODO: determine distribution and expected size of chunks
-    //
-    // 1: P(curr > prev) = 0    (prev set to 0xff)
-    // 2: P(curr > prev) = 0.5  (prev and curr assumed to be randomly distributed)
-    // 3: P(curr > prev) |  t2 = ???
-    //    P(curr > prev) | !t2 = ???
     pub fn with_w(w: u64) -> Self {
-        Self { w }
+        Self {
+            w,
+            min: 0,
+            max: u64::MAX,
+        }
+    }
+
+    /// Create a new splitter with parameter `w`, and chunk sizes limited to `range`
+    ///
+    /// The first `min` bytes of each chunk are not examined (so a chunk never ends within them,
+    /// and increments are counted starting from the byte after them), and a chunk is split when
+    /// it reaches the maximum size.
+    ///
+    /// # Panics
+    ///
+    /// If `range` is empty or only contains 0. See [`Mii::try_with_w_and_range()`].
+    pub fn with_w_and_range(w: u64, range: impl RangeBounds<u64>) -> Self {
+        Self::try_with_w_and_range(w, range).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create a new splitter with parameter `w`, and chunk sizes limited to `range`, or return an
+    /// error if `range` is empty or only contains 0
+    ///
+    /// See [`Mii::with_w_and_range()`].
+    pub fn try_with_w_and_range(w: u64, range: impl RangeBounds<u64>) -> Result<Self, ConfigError> {
+        let (min, max) = crate::config::size_range("mii", "range", range)?;
+        Ok(Self { w, min, max })
+    }
+
+    /// The expected size of chunks of random data (where each byte is independent and uniformly
+    /// distributed), ignoring the maximum size
+    ///
+    /// This is infinite when `w` is 0 or larger than 255, as such chunks are never split.
+    ///
+    /// Without a minimum size:
+    ///
+    /// | `w` | expected size |
+    /// |---|---|
+    /// | 1 | 2.7 |
+    /// | 2 | 8.0 |
+    /// | 3 | 30.6 |
+    /// | 4 | 149.2 |
+    /// | 5 | 887.6 |
+    /// | 6 | 6232.5 |
+    /// | 7 | 50460.5 |
+    /// | 8 | 463006.8 |
+    ///
+    /// With a minimum size, the expected size is increased by `min - 1` (if `min` is not 0).
+    pub fn expected_chunk_size(&self) -> f64 {
+        // the (possibly skipped) first byte of a chunk is never an increment, and the remainder
+        // only depends on `w`
+        std::cmp::max(self.min, 1) as f64 + expected_size(self.w) - 1.0
     }
 }
 
+/// Expected size of chunks of random data with at least `w` increments and no size limits
+///
+/// Between chunk edges, the chunker returns to having no increments each time a byte is not an
+/// increment. This forms a Markov chain over the value of that byte, with chunk edges (followed by
+/// the first byte of the next chunk) as additional transitions. The expected chunk size is the
+/// ratio of bytes to chunk edges in its stationary distribution.
+fn expected_size(w: u64) -> f64 {
+    if w == 0 || w > 255 {
+        return f64::INFINITY;
+    }
+
+    const N: usize = 256;
+    const P: f64 = 1.0 / N as f64;
+    // transitions between values of the byte that ended a run of increments
+    let mut trans = vec![[0f64; N]; N];
+    // probability of a run reaching `w` increments
+    let mut split = [0f64; N];
+    // expected bytes consumed by a transition
+    let mut bytes = [0f64; N];
+
+    for v in 0..N {
+        // distribution of the last byte in runs of increments that are still continuing
+        let mut run = [0f64; N];
+        run[v] = 1.0;
+        for _ in 0..w {
+            bytes[v] += run.iter().sum::<f64>();
+
+            // the next byte is either larger than the last (continuing the run) or not
+            let mut next = [0f64; N];
+            let mut below = 0.0;
+            for u in 0..N {
+                next[u] = below * P;
+                below += run[u];
+            }
+            let mut above = 0.0;
+            for u in (0..N).rev() {
+                above += run[u];
+                trans[v][u] += above * P;
+            }
+            run = next;
+        }
+
+        split[v] = run.iter().sum();
+        // after a split, the first byte of the next chunk is never an increment
+        bytes[v] += split[v];
+        for t in trans[v].iter_mut() {
+            *t += split[v] * P;
+        }
+    }
+
+    let pi = stationary(trans);
+    let total_bytes: f64 = pi.iter().zip(bytes.iter()).map(|(p, b)| p * b).sum();
+    let total_splits: f64 = pi.iter().zip(split.iter()).map(|(p, s)| p * s).sum();
+    total_bytes / total_splits
+}
+
+/// Stationary distribution of the Markov chain with transition probabilities `p`, using the
+/// Grassmann-Taksar-Heyman algorithm (which avoids subtraction, so remains accurate when some
+/// transitions are very unlikely)
+fn stationary(mut p: Vec<[f64; 256]>) -> [f64; 256] {
+    let n = p.len();
+    for k in (1..n).rev() {
+        let (rows, rest) = p.split_at_mut(k);
+        let pk = &rest[0];
+        let s: f64 = pk[..k].iter().sum();
+        for row in rows {
+            row[k] /= s;
+            let pik = row[k];
+            for (x, y) in row[..k].iter_mut().zip(pk[..k].iter()) {
+                *x += pik * y;
+            }
+        }
+    }
+
+    let mut pi = [0f64; 256];
+    pi[0] = 1.0;
+    for j in 1..n {
+        pi[j] = (0..j).map(|i| pi[i] * p[i][j]).sum();
+    }
+    let total: f64 = pi.iter().sum();
+    for v in pi.iter_mut() {
+        *v /= total;
+    }
+    pi
+}
+
 impl Default for Mii {
     /// The window of 5 is used in the paper for the generated graphs
     ///
@@ -37,6 +234,53 @@ impl Default for Mii {
     }
 }
 
+/// Formats as `mii:w=<increments>,min=<size>,max=<size>`
+///
+/// `min` and `max` are omitted when the chunk size is not limited. `max` is the largest chunk
+/// size.
+///
+/// See [`crate::spec`] for details.
+impl fmt::Display for Mii {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "mii:w={}", self.w)?;
+        if self.min != 0 {
+            write!(f, ",min={}", SizeDisplay(self.min))?;
+        }
+        if self.max != u64::MAX {
+            write!(f, ",max={}", SizeDisplay(self.max))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the format emitted by [`Mii`]'s `Display` impl
+///
+/// `max` must not be 0 or less than `min`.
+impl FromStr for Mii {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("mii", s)?;
+        let w = p.take_int("w", u64::MAX)?.unwrap_or(Mii::default().w);
+        let min = p.take_size("min")?.unwrap_or(0);
+        let max = match p.take("max") {
+            None => u64::MAX,
+            Some(v) => match crate::spec::parse_size(v) {
+                Some(m) if m != 0 && m >= min => m,
+                _ => {
+                    return Err(p.invalid(
+                        "max",
+                        v,
+                        "expected a non-zero byte size, no smaller than `min`",
+                    ))
+                }
+            },
+        };
+        p.finish()?;
+        Ok(Mii::with_w_and_range(w, min..=max))
+    }
+}
+
 impl crate::Chunk for Mii {
     type SearchState = MiiSearchState;
 
@@ -65,6 +309,16 @@ impl From<&Mii> for MiiIncr {
     }
 }
 
+impl CutReasons for Mii {
+    fn cut_reason(&self, len: u64) -> CutReason {
+        if len >= self.max {
+            CutReason::MaxSize
+        } else {
+            CutReason::Hash
+        }
+    }
+}
+
 impl ToChunkIncr for Mii {
     type Incr = MiiIncr;
 
@@ -96,38 +350,69 @@ impl MiiSearchState {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct MiiIncr {
     /// After this many increments, split the file
     w: u64,
 
+    /// bytes at the start of each chunk that are not examined
+    min: u64,
+
+    /// largest chunk size
+    max: u64,
+
     /// previous examined byte, if any
     prev: u8,
 
     /// number of times a byte was greater than the previous value
     increment: u64,
+
+    /// bytes in the current chunk
+    len: u64,
 }
 
 impl From<Mii> for MiiIncr {
     fn from(p: Mii) -> Self {
+        // ensured by `Mii`'s constructors
+        debug_assert!(p.min <= p.max && p.max > 0);
         MiiIncr {
             w: p.w,
+            min: p.min,
+            max: p.max,
             // we use 0xff to ensure that the first examined byte does not trigger an increment
             prev: 0xff,
             increment: 0,
+            len: 0,
         }
     }
 }
 
 impl ChunkIncr for MiiIncr {
     fn push(&mut self, input: &[u8]) -> Option<usize> {
-        for (i, b) in input.iter().cloned().enumerate() {
+        let mut start = 0;
+        if self.len < self.min {
+            // skip the minimum, only keeping the last byte to compare against
+            let n = std::cmp::min(self.min - self.len, input.len() as u64) as usize;
+            if n > 0 {
+                self.prev = input[n - 1];
+            }
+            self.len += n as u64;
+            start = n;
+        }
+
+        // `min <= max` and `max > 0` are ensured by `Mii`'s constructors, but a chunk must end
+        // after at least one byte regardless
+        let max = std::cmp::max(self.max, 1);
+        let end = std::cmp::min(
+            input.len() as u64,
+            start as u64 + max.saturating_sub(self.len),
+        ) as usize;
+        for (i, b) in input[..end].iter().cloned().enumerate().skip(start) {
             if b > self.prev {
                 self.increment += 1;
                 if self.increment == self.w {
                     // this is a split
-                    self.increment = 0;
-                    self.prev = 0;
+                    self.reset();
                     return Some(i + 1);
                 }
             } else {
@@ -136,13 +421,18 @@ impl ChunkIncr for MiiIncr {
             self.prev = b;
         }
 
+        self.len += (end - start) as u64;
+        if self.len >= max && end > 0 {
+            self.reset();
+            return Some(end);
+        }
+
         None
     }
-}
 
-impl MiiIncr {
     fn reset(&mut self) {
         self.prev = 0xff;
         self.increment = 0;
+        self.len = 0;
     }
 }
@@ -0,0 +1,201 @@
+//! Chunk the same input with several configurations in a single pass
+//!
+//! Comparing configurations (for example, to choose parameters for a corpus) would otherwise need
+//! the input to be read once for each of them. [`MultiChunker`] feeds each buffer read to all of
+//! the configurations, and reports the chunks each one finds as [`ChunkBoundary`]s.
+//!
+//! Configurations are provided by a [`MultiIncr`], which is implemented for:
+//!
+//!  - any [`ChunkIncr`] (a single configuration), including `Box<dyn ChunkIncr>`,
+//!  - `Vec<M>` and tuples of up to 6 [`MultiIncr`]s, with the configurations of each in turn,
+//!  - algorithms which share work between several configurations of the same family, like
+//!    [`gear::GearMultiIncr32`](crate::gear::GearMultiIncr32), which computes one fingerprint
+//!    for several masks.
+//!
+//! ```rust
+//! # #[cfg(all(feature = "mii", feature = "zpaq"))] {
+//! use hash_roll::multi::MultiChunker;
+//! use hash_roll::{ChunkIncr, ToChunkIncr};
+//!
+//! let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+//! let configs: Vec<Box<dyn ChunkIncr>> = vec![
+//!     Box::new(hash_roll::mii::Mii::default().to_chunk_incr()),
+//!     Box::new(hash_roll::zpaq::Zpaq::with_average_size_pow_2(12).to_chunk_incr()),
+//! ];
+//! let mut mc = MultiChunker::new(configs);
+//! let mut chunks = vec![Vec::new(); mc.configs()];
+//! for piece in data.chunks(1000) {
+//!     mc.push(piece, &mut chunks);
+//! }
+//! mc.finish(&mut chunks);
+//!
+//! for c in &chunks {
+//!     assert_eq!(c.last().unwrap().end, data.len() as u64);
+//! }
+//! # }
+//! ```
+use crate::offset::ChunkBoundary;
+use crate::ChunkIncr;
+
+/// Number of bytes passed to every configuration before moving on to the next block of a buffer,
+/// so that the block stays in cache
+const BLOCK: usize = 32 * 1024;
+
+/// One or more chunking configurations fed the same input
+pub trait MultiIncr {
+    /// Number of configurations
+    fn configs(&self) -> usize;
+
+    /// Push all of `data` into every configuration, appending the index in `data` of each split
+    /// point found by configuration `n` to `out[n]`
+    ///
+    /// This is [`ChunkIncr::push_all()`] for each configuration. `out` must contain
+    /// [`MultiIncr::configs()`] elements.
+    fn push_multi(&mut self, data: &[u8], out: &mut [Vec<usize>]);
+}
+
+impl<I: ChunkIncr> MultiIncr for I {
+    fn configs(&self) -> usize {
+        1
+    }
+
+    fn push_multi(&mut self, data: &[u8], out: &mut [Vec<usize>]) {
+        self.push_all(data, &mut out[0]);
+    }
+}
+
+impl<M: MultiIncr> MultiIncr for Vec<M> {
+    fn configs(&self) -> usize {
+        self.iter().map(|m| m.configs()).sum()
+    }
+
+    fn push_multi(&mut self, data: &[u8], mut out: &mut [Vec<usize>]) {
+        for m in self {
+            let (head, rest) = out.split_at_mut(m.configs());
+            m.push_multi(data, head);
+            out = rest;
+        }
+    }
+}
+
+macro_rules! multi_tuple {
+    ($($t:ident $v:ident),+) => {
+        impl<$($t: MultiIncr),+> MultiIncr for ($($t,)+) {
+            fn configs(&self) -> usize {
+                let ($($v,)+) = self;
+                0 $(+ $v.configs())+
+            }
+
+            fn push_multi(&mut self, data: &[u8], out: &mut [Vec<usize>]) {
+                let ($($v,)+) = self;
+                let rest = out;
+                $(
+                    let (head, rest) = rest.split_at_mut($v.configs());
+                    $v.push_multi(data, head);
+                )+
+                let _ = rest;
+            }
+        }
+    };
+}
+
+multi_tuple!(A a);
+multi_tuple!(A a, B b);
+multi_tuple!(A a, B b, C c);
+multi_tuple!(A a, B b, C c, D d);
+multi_tuple!(A a, B b, C c, D d, E e);
+multi_tuple!(A a, B b, C c, D d, E e, F f);
+
+/// Chunks a stream with every configuration of a [`MultiIncr`], reporting the position of each
+/// chunk in the stream
+///
+/// This is [`offset::OffsetTracking`](crate::offset::OffsetTracking) for several configurations.
+/// Each buffer pushed is split into blocks which are passed to every configuration in turn, so
+/// the input is read from memory once.
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone)]
+pub struct MultiChunker<M> {
+    multi: M,
+
+    /// offset of the start of the current chunk of each configuration
+    starts: Vec<u64>,
+
+    /// number of bytes pushed
+    offset: u64,
+
+    /// edges found in the current block by each configuration, kept to reuse the allocations
+    edges: Vec<Vec<usize>>,
+}
+
+impl<M: MultiIncr> MultiChunker<M> {
+    /// Create a [`MultiChunker`] from fresh configurations
+    pub fn new(multi: M) -> Self {
+        let n = multi.configs();
+        MultiChunker {
+            multi,
+            starts: vec![0; n],
+            offset: 0,
+            edges: vec![Vec::new(); n],
+        }
+    }
+
+    /// Number of configurations
+    pub fn configs(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Feed all of `data` into every configuration, appending each chunk completed by
+    /// configuration `n` to `out[n]`
+    ///
+    /// # Panics
+    ///
+    /// If `out` does not contain [`MultiChunker::configs()`] elements
+    pub fn push(&mut self, data: &[u8], out: &mut [Vec<ChunkBoundary>]) {
+        assert_eq!(out.len(), self.configs(), "one output per configuration");
+        for block in data.chunks(BLOCK) {
+            for e in &mut self.edges {
+                e.clear();
+            }
+            self.multi.push_multi(block, &mut self.edges);
+
+            let base = self.offset;
+            for ((edges, start), out) in self.edges.iter().zip(&mut self.starts).zip(&mut *out) {
+                for &e in edges {
+                    let end = base + e as u64;
+                    out.push(ChunkBoundary { start: *start, end });
+                    *start = end;
+                }
+            }
+            self.offset = base + block.len() as u64;
+        }
+    }
+
+    /// Number of bytes pushed so far, which is the offset of the next byte to be pushed
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Emit the data passed since the last chunk (if any) of each configuration as its final
+    /// chunk
+    ///
+    /// # Panics
+    ///
+    /// If `out` does not contain [`MultiChunker::configs()`] elements
+    pub fn finish(self, out: &mut [Vec<ChunkBoundary>]) {
+        assert_eq!(out.len(), self.configs(), "one output per configuration");
+        for (&start, out) in self.starts.iter().zip(out) {
+            if start != self.offset {
+                out.push(ChunkBoundary {
+                    start,
+                    end: self.offset,
+                });
+            }
+        }
+    }
+
+    /// Obtain the configurations
+    pub fn into_inner(self) -> M {
+        self.multi
+    }
+}
@@ -0,0 +1,183 @@
+//! Chunk positions in a stream of data passed to a [`ChunkIncr`]
+//!
+//! [`ChunkIncr::push()`] returns indexes relative to the slice passed to it. [`OffsetTracking`]
+//! wraps a [`ChunkIncr`] and reports each chunk as a [`ChunkBoundary`]: the offsets of its first
+//! byte and the byte following it, counting from the first byte pushed.
+//!
+//! ```rust
+//! # #[cfg(feature = "mii")] {
+//! use hash_roll::offset::{ChunkBoundary, OffsetTracking};
+//! use hash_roll::ToChunkIncr;
+//!
+//! let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+//! let mut ot = OffsetTracking::new(hash_roll::mii::Mii::default().to_chunk_incr());
+//! let mut chunks = Vec::new();
+//! for piece in data.chunks(1000) {
+//!     ot.push_all(piece, &mut chunks);
+//! }
+//! chunks.extend(ot.finish());
+//!
+//! assert_eq!(chunks[0].start, 0);
+//! for w in chunks.windows(2) {
+//!     assert_eq!(w[0].end, w[1].start);
+//! }
+//! assert_eq!(chunks.last().unwrap().end, data.len() as u64);
+//! # }
+//! ```
+use crate::ChunkIncr;
+use std::ops::Range;
+
+/// The position of a chunk in a stream
+///
+/// `start` is the offset of the first byte of the chunk, and `end` is the offset of the byte
+/// following it (so `end - start` is the length of the chunk, and the next chunk starts at `end`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkBoundary {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ChunkBoundary {
+    /// Number of bytes in the chunk
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Whether the chunk contains no bytes (which chunkers never emit)
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// The offsets of the bytes in the chunk
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.end
+    }
+}
+
+/// Chunks data using a [`ChunkIncr`], reporting the position of each chunk in the stream
+///
+/// See the [module documentation](self) for an example.
+#[derive(Debug, Clone)]
+pub struct OffsetTracking<I> {
+    incr: I,
+
+    /// offset of the start of the current chunk
+    start: u64,
+
+    /// number of bytes pushed
+    offset: u64,
+
+    /// edges found by `push_all()`, kept to reuse the allocation
+    edges: Vec<usize>,
+}
+
+impl<I: ChunkIncr> OffsetTracking<I> {
+    /// Create an [`OffsetTracking`] from a fresh [`ChunkIncr`] (typically obtained via
+    /// [`ToChunkIncr`](crate::ToChunkIncr))
+    pub fn new(incr: I) -> Self {
+        OffsetTracking {
+            incr,
+            start: 0,
+            offset: 0,
+            edges: Vec::new(),
+        }
+    }
+
+    /// Feed `data` into the chunker
+    ///
+    /// If a chunk ends in `data`, returns the index in `data` immediately following the chunk and
+    /// the completed chunk. Only the data prior to the returned index has been consumed: call
+    /// `push()` again with the remainder of `data` to continue.
+    ///
+    /// If no chunk ends in `data`, all of `data` is consumed and `None` is returned.
+    pub fn push(&mut self, data: &[u8]) -> Option<(usize, ChunkBoundary)> {
+        match self.incr.push(data) {
+            Some(i) => {
+                self.offset += i as u64;
+                Some((i, self.emit()))
+            }
+            None => {
+                self.offset += data.len() as u64;
+                None
+            }
+        }
+    }
+
+    /// Feed all of `data` into the chunker, appending each completed chunk to `out`
+    ///
+    /// This uses [`ChunkIncr::push_all()`], so benefits from the specialized implementations some
+    /// algorithms provide.
+    pub fn push_all(&mut self, data: &[u8], out: &mut Vec<ChunkBoundary>) {
+        self.edges.clear();
+        self.incr.push_all(data, &mut self.edges);
+        let base = self.offset;
+        for &e in &self.edges {
+            let end = base + e as u64;
+            out.push(ChunkBoundary {
+                start: self.start,
+                end,
+            });
+            self.start = end;
+        }
+        self.offset = base + data.len() as u64;
+    }
+
+    /// Number of bytes pushed so far, which is the offset of the next byte to be pushed
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// End the current chunk at the current offset, restarting the chunker as if a new source
+    /// started there
+    ///
+    /// Returns the data passed since the last chunk (if any) as a chunk. Offsets continue to count
+    /// from the first byte pushed. See [`ChunkIncr::force_boundary()`].
+    pub fn force_boundary(&mut self) -> Option<ChunkBoundary> {
+        self.incr.force_boundary();
+        self.take_pending()
+    }
+
+    /// Emit the data passed since the last chunk (if any) as the final chunk
+    ///
+    /// This is used at the end of the input, where the trailing data does not end on a chunk
+    /// edge.
+    pub fn finish(mut self) -> Option<ChunkBoundary> {
+        self.take_pending()
+    }
+
+    /// Obtain the internal [`ChunkIncr`]
+    pub fn into_inner(self) -> I {
+        self.incr
+    }
+
+    fn take_pending(&mut self) -> Option<ChunkBoundary> {
+        if self.start == self.offset {
+            None
+        } else {
+            Some(self.emit())
+        }
+    }
+
+    fn emit(&mut self) -> ChunkBoundary {
+        let c = ChunkBoundary {
+            start: self.start,
+            end: self.offset,
+        };
+        self.start = self.offset;
+        c
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn chunk_boundary() {
+        let b = ChunkBoundary { start: 10, end: 25 };
+        assert_eq!(b.len(), 15);
+        assert!(!b.is_empty());
+        assert_eq!(b.range(), 10..25);
+    }
+}
@@ -0,0 +1,245 @@
+//! Multi-threaded chunking of large in-memory buffers (requires the `rayon` feature)
+//!
+//! [`par_chunk_ranges()`] splits the input into segments and chunks each segment on the rayon
+//! thread pool as if a chunk started at the beginning of the segment. Those speculative edges are
+//! then reconciled with the real ones: starting from the last real edge before a segment, chunking
+//! is re-run until it finds an edge that the segment's speculative run also found. From there on,
+//! both runs are identical, so the rest of the segment's edges are used as-is.
+//!
+//! For content defined chunking the runs normally agree after one or two chunks, so only a small
+//! amount of data near each segment seam is chunked twice.
+//!
+//! This relies on the state used by [`Chunk::find_chunk_edge()`] being reset after each edge (see
+//! [`Chunk::resets_at_edges()`]). Chunkers which carry state from one chunk to the next can't be
+//! split this way. If that state only depends on a bounded window of preceding bytes (see
+//! [`Chunk::edge_window()`], like [`crate::zstd`]), each segment is instead chunked starting that
+//! many bytes before it, which finds exactly the real edges in the segment. Others (like
+//! [`crate::runs::Runs`]) are chunked sequentially.
+use crate::Chunk;
+use rayon::prelude::*;
+use std::ops::Range;
+
+/// Segment length used by [`par_chunk_ranges()`] when the input is large
+const MIN_SEGMENT_LEN: usize = 1 << 20;
+
+/// Number of segments per thread used by [`par_chunk_ranges()`], to balance load when some
+/// segments take longer than others
+const SEGMENTS_PER_THREAD: usize = 4;
+
+/// Chunk `data` using multiple threads, returning the same ranges as [`Chunk::chunk_ranges()`]
+///
+/// Segments are sized based on the number of threads in the current rayon thread pool, with a
+/// minimum of 1 MiB. Use [`par_chunk_ranges_with_segment_len()`] to choose the segment length.
+///
+/// If [`Chunk::resets_at_edges()`] is `false` and [`Chunk::edge_window()`] is `None` (as for
+/// [`crate::runs::Runs`]), `data` is chunked sequentially on the current thread.
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "fastcdc")] {
+/// use hash_roll::Chunk;
+/// use hash_roll::fastcdc::FastCdc;
+///
+/// let data = vec![7u8; 100_000];
+/// let chunker = FastCdc::default();
+/// let ranges = hash_roll::par::par_chunk_ranges(&chunker, &data[..]);
+/// assert_eq!(ranges, chunker.chunk_ranges(&data[..]).collect::<Vec<_>>());
+/// # }
+/// ```
+pub fn par_chunk_ranges<C: Chunk + Sync>(chunker: &C, data: &[u8]) -> Vec<Range<usize>> {
+    let segments = rayon::current_num_threads() * SEGMENTS_PER_THREAD;
+    let segment_len = std::cmp::max(data.len() / segments, MIN_SEGMENT_LEN);
+    par_chunk_ranges_with_segment_len(chunker, data, segment_len)
+}
+
+/// Chunk `data` using multiple threads, processing `segment_len` bytes per task
+///
+/// Returns the same ranges as [`Chunk::chunk_ranges()`]. If [`Chunk::resets_at_edges()`] is
+/// `false` and [`Chunk::edge_window()`] is `None`, `data` is chunked sequentially on the current
+/// thread.
+///
+/// Segments much shorter than the typical chunk size give little benefit, as most of the
+/// speculative work is discarded.
+///
+/// # Panics
+///
+/// If `segment_len` is 0
+pub fn par_chunk_ranges_with_segment_len<C: Chunk + Sync>(
+    chunker: &C,
+    data: &[u8],
+    segment_len: usize,
+) -> Vec<Range<usize>> {
+    assert!(segment_len > 0, "segment_len must not be zero");
+    if !chunker.resets_at_edges() {
+        return match chunker.edge_window() {
+            Some(window) => windowed(chunker, data, segment_len, window),
+            None => chunker.chunk_ranges(data).collect(),
+        };
+    }
+
+    let speculative: Vec<Speculative> = (0..data.len())
+        .into_par_iter()
+        .step_by(segment_len)
+        .map(|start| {
+            let end = std::cmp::min(start + segment_len, data.len());
+            Speculative::find(chunker, data, start, end)
+        })
+        .collect();
+
+    let mut edges = Vec::new();
+    // the last real edge (or the start of the data)
+    let mut last = 0;
+    for (seg, spec) in speculative.iter().enumerate() {
+        let end = std::cmp::min((seg + 1) * segment_len, data.len());
+        if last >= end {
+            // a single chunk covered this entire segment
+            continue;
+        }
+
+        // re-run from the last real edge until we agree with the speculative run
+        let agreed = loop {
+            if let Some(i) = spec.agrees_at(last) {
+                break Some(i);
+            }
+
+            if last >= end {
+                break None;
+            }
+
+            match next_edge(chunker, data, last) {
+                Some(e) => {
+                    edges.push(e);
+                    last = e;
+                }
+                None => return to_ranges(edges, data.len()),
+            }
+        };
+
+        if let Some(i) = agreed {
+            edges.extend_from_slice(&spec.edges[i..]);
+            if let Some(&e) = edges.last() {
+                last = e;
+            }
+            if spec.exhausted {
+                break;
+            }
+        }
+    }
+
+    to_ranges(edges, data.len())
+}
+
+/// Chunk each segment starting `window` bytes before it, for chunkers with an
+/// [`Chunk::edge_window()`]
+///
+/// The edges found within each segment are the real ones, so no reconciliation is needed.
+fn windowed<C: Chunk + Sync>(
+    chunker: &C,
+    data: &[u8],
+    segment_len: usize,
+    window: usize,
+) -> Vec<Range<usize>> {
+    let edges: Vec<Vec<usize>> = (0..data.len())
+        .into_par_iter()
+        .step_by(segment_len)
+        .map(|start| {
+            let end = std::cmp::min(start + segment_len, data.len());
+            let mut edges = Vec::new();
+            let mut state = chunker.to_search_state();
+            let mut pos = start.saturating_sub(window);
+            loop {
+                let (cut, discard_ct) = chunker.find_chunk_edge(&mut state, &data[pos..end]);
+                match cut {
+                    Some(cut) => {
+                        // edges at the start of the segment belong to the previous one
+                        if pos + cut > start {
+                            edges.push(pos + cut);
+                        }
+                        pos += discard_ct;
+                    }
+                    None => break,
+                }
+            }
+            edges
+        })
+        .collect();
+
+    to_ranges(edges.concat(), data.len())
+}
+
+/// Edges found by chunking from the start of a segment as if a chunk started there
+#[derive(Debug)]
+struct Speculative {
+    /// The start of the segment
+    start: usize,
+
+    /// Edges found after `start`, up to and including the first edge at or past the end of the
+    /// segment
+    edges: Vec<usize>,
+
+    /// No edges exist after the last of `edges`
+    exhausted: bool,
+}
+
+impl Speculative {
+    fn find<C: Chunk>(chunker: &C, data: &[u8], start: usize, end: usize) -> Self {
+        let mut edges = Vec::new();
+        let mut last = start;
+        let exhausted = loop {
+            match next_edge(chunker, data, last) {
+                Some(e) => {
+                    edges.push(e);
+                    last = e;
+                    if e >= end {
+                        break false;
+                    }
+                }
+                None => break true,
+            }
+        };
+
+        Speculative {
+            start,
+            edges,
+            exhausted,
+        }
+    }
+
+    /// If a real edge at `pos` means the speculative edges are real, the index in `edges` of the
+    /// first edge following `pos`
+    fn agrees_at(&self, pos: usize) -> Option<usize> {
+        if pos == self.start {
+            return Some(0);
+        }
+
+        self.edges.binary_search(&pos).ok().map(|i| i + 1)
+    }
+}
+
+/// Find the first edge after `start` when a chunk starts at `start`
+fn next_edge<C: Chunk>(chunker: &C, data: &[u8], start: usize) -> Option<usize> {
+    if start == data.len() {
+        return None;
+    }
+
+    let mut state = chunker.to_search_state();
+    let (cut, _) = chunker.find_chunk_edge(&mut state, &data[start..]);
+    cut.map(|cut| start + cut)
+}
+
+fn to_ranges(edges: Vec<usize>, len: usize) -> Vec<Range<usize>> {
+    let mut last = 0;
+    let mut ranges: Vec<Range<usize>> = edges
+        .into_iter()
+        .map(|e| {
+            let r = last..e;
+            last = e;
+            r
+        })
+        .collect();
+    if last != len {
+        ranges.push(last..len);
+    }
+    ranges
+}
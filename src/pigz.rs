@@ -1,7 +1,17 @@
 #![cfg(feature = "pigz")]
+use crate::config::ConfigError;
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SpecError};
 use crate::{Chunk, ChunkIncr, ToChunkIncr};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "PigzRsyncableSerde", into = "PigzRsyncableSerde")
+)]
 pub struct PigzRsyncable {
     bits: u8,
 
@@ -12,10 +22,56 @@ pub struct PigzRsyncable {
 }
 
 impl PigzRsyncable {
+    /// Create an instance with a `bits` bit hash, which emits chunks with an average size of
+    /// `2**bits` bytes
+    ///
+    /// # Panics
+    ///
+    /// If `bits` is not between 1 and 31. See [`PigzRsyncable::try_with_bits()`].
     pub fn with_bits(bits: u8) -> PigzRsyncable {
+        Self::try_with_bits(bits).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Create an instance with a `bits` bit hash, or return an error if `bits` is not between 1
+    /// and 31
+    pub fn try_with_bits(bits: u8) -> Result<PigzRsyncable, ConfigError> {
+        if !(1..32).contains(&bits) {
+            return Err(ConfigError::invalid(
+                "pigz",
+                "bits",
+                bits as u64,
+                "must be between 1 and 31",
+            ));
+        }
         let mask = (1 << bits) - 1;
         let hit = mask >> 1;
-        PigzRsyncable { bits, mask, hit }
+        Ok(PigzRsyncable { bits, mask, hit })
+    }
+}
+
+/// Serialized form of [`PigzRsyncable`], which omits the fields derived from `bits`
+///
+/// `bits` is checked by [`PigzRsyncable::try_with_bits()`] when deserialized.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "PigzRsyncable")]
+struct PigzRsyncableSerde {
+    bits: u8,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<PigzRsyncableSerde> for PigzRsyncable {
+    type Error = ConfigError;
+
+    fn try_from(s: PigzRsyncableSerde) -> Result<Self, Self::Error> {
+        PigzRsyncable::try_with_bits(s.bits)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<PigzRsyncable> for PigzRsyncableSerde {
+    fn from(p: PigzRsyncable) -> Self {
+        PigzRsyncableSerde { bits: p.bits }
     }
 }
 
@@ -25,6 +81,35 @@ impl Default for PigzRsyncable {
     }
 }
 
+/// Formats as `pigz:bits=<bits>`
+///
+/// See [`crate::spec`] for details.
+impl fmt::Display for PigzRsyncable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "pigz:bits={}", self.bits)
+    }
+}
+
+/// Parses the format emitted by [`PigzRsyncable`]'s `Display` impl
+///
+/// `bits` must be between 1 and 31.
+impl FromStr for PigzRsyncable {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("pigz", s)?;
+        let bits = match p.take("bits") {
+            None => PigzRsyncable::default().bits,
+            Some(v) => match v.parse() {
+                Ok(b) if (1..32).contains(&b) => b,
+                _ => return Err(p.invalid("bits", v, "must be between 1 and 31")),
+            },
+        };
+        p.finish()?;
+        Ok(PigzRsyncable::with_bits(bits))
+    }
+}
+
 impl Chunk for PigzRsyncable {
     type SearchState = PigzRsyncableSearchState;
 
@@ -46,6 +131,16 @@ impl Chunk for PigzRsyncable {
 
         (None, data.len())
     }
+
+    fn find_chunk_edges(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        state.state.add_all(self, data, out);
+        data.len()
+    }
 }
 
 impl From<&PigzRsyncable> for PigzRsyncableIncr {
@@ -54,6 +149,13 @@ impl From<&PigzRsyncable> for PigzRsyncableIncr {
     }
 }
 
+impl CutReasons for PigzRsyncable {
+    /// Always [`CutReason::Hash`]
+    fn cut_reason(&self, _len: u64) -> CutReason {
+        CutReason::Hash
+    }
+}
+
 impl ToChunkIncr for PigzRsyncable {
     type Incr = PigzRsyncableIncr;
     fn to_chunk_incr(&self) -> Self::Incr {
@@ -115,6 +217,25 @@ impl PigzRsyncableState {
         self.hash = ((self.hash << 1) ^ (v as u32)) & parent.mask;
         self.hash == parent.hit
     }
+
+    /// Add all of `data`, appending the index following each hit to `out`
+    ///
+    /// After a hit the hash is reset to `hit`, which is the value it already has, so the hash is
+    /// carried through the entire slice.
+    fn add_all(&mut self, parent: &PigzRsyncable, data: &[u8], out: &mut Vec<usize>) {
+        let (mask, hit) = (parent.mask, parent.hit);
+        let mut hash = self.hash;
+        let mut pos = 0;
+        // searching for the next hit in a separate loop keeps the hash in a register
+        while let Some(i) = data[pos..].iter().position(|&v| {
+            hash = ((hash << 1) ^ (v as u32)) & mask;
+            hash == hit
+        }) {
+            pos += i + 1;
+            out.push(pos);
+        }
+        self.hash = hash;
+    }
 }
 
 impl ChunkIncr for PigzRsyncableIncr {
@@ -128,4 +249,12 @@ impl ChunkIncr for PigzRsyncableIncr {
 
         None
     }
+
+    fn reset(&mut self) {
+        self.state = (&self.params).into();
+    }
+
+    fn push_all(&mut self, data: &[u8], out: &mut Vec<usize>) {
+        self.state.add_all(&self.params, data, out);
+    }
 }
@@ -3,17 +3,23 @@
 //! Rapid Asymmetric Maximum (RAM) is a fast chunking algorithm
 //!
 //! - Has a minimum block size (it's "window" size)
-//! - Does not provide an upper bound on block size (though paper discusses a RAML variant that
-//!   does).
+//! - Does not provide an upper bound on block size. The paper also discusses a RAML variant that
+//!   does, which is provided as [`RamL`].
 //!
 //! doi:10.1016/j.future.2017.02.013
 //!
+use crate::config::ConfigError;
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SizeDisplay, SpecError};
 use crate::{Chunk, ChunkIncr, ToChunkIncr};
+use std::fmt;
+use std::str::FromStr;
 
 /// Parameters for the Rapid Asymmetric Maximum (RAM) chunking algorithm
 ///
 /// Is window free, with very small (
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Ram {
     /// window size
     ///
@@ -30,6 +36,27 @@ impl Ram {
     }
 }
 
+/// Formats as `ram:w=<size>`
+///
+/// See [`crate::spec`] for details.
+impl fmt::Display for Ram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ram:w={}", SizeDisplay(self.w))
+    }
+}
+
+/// Parses the format emitted by [`Ram`]'s `Display` impl. `w` is required.
+impl FromStr for Ram {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("ram", s)?;
+        let w = p.take_size("w")?.ok_or_else(|| p.missing("w"))?;
+        p.finish()?;
+        Ok(Ram::with_w(w))
+    }
+}
+
 impl Chunk for Ram {
     type SearchState = RamState;
 
@@ -42,11 +69,21 @@ impl Chunk for Ram {
         state: &mut Self::SearchState,
         data: &[u8],
     ) -> (Option<usize>, usize) {
-        match state.push(self, data) {
+        match state.push(self.w, u64::MAX, data) {
             Some(i) => (Some(i + 1), i + 1),
             None => (None, data.len()),
         }
     }
+
+    fn find_chunk_edges(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        state.push_all(self.w, u64::MAX, data, out);
+        data.len()
+    }
 }
 
 #[derive(Default, Debug, PartialEq, Eq, Clone)]
@@ -59,14 +96,21 @@ pub struct RamState {
 }
 
 impl RamState {
-    fn push(&mut self, params: &Ram, data: &[u8]) -> Option<usize> {
+    /// Find the index of the byte that ends a chunk, using window size `w` and maximum chunk size
+    /// `max` (`u64::MAX` for [`Ram`])
+    fn push(&mut self, w: u64, max: u64, data: &[u8]) -> Option<usize> {
+        // ensured by `RamL`'s constructors, but a chunk must contain at least one byte regardless
+        debug_assert!(max > 0);
+        let max = std::cmp::max(max, 1);
         let i = self.i;
+        // bytes before the maximum chunk size (`i` is always less than `max`)
+        let lim = std::cmp::min(data.len() as u64, max.saturating_sub(i)) as usize;
 
-        for (l_i, b) in data.iter().cloned().enumerate() {
+        for (l_i, b) in data[..lim].iter().cloned().enumerate() {
             if b >= self.max_val {
                 // minimum block size
                 let ri = l_i as u64 + i;
-                if ri > params.w {
+                if ri > w {
                     self.i = 0;
                     self.max_val = 0;
                     return Some(l_i);
@@ -76,9 +120,77 @@ impl RamState {
             }
         }
 
+        if i + lim as u64 == max {
+            self.i = 0;
+            self.max_val = 0;
+            return Some(lim - 1);
+        }
+
         self.i += data.len() as u64;
         None
     }
+
+    /// Push all of `data`, appending each split point (the index following the byte that
+    /// triggers it) to `out`
+    fn push_all(&mut self, w: u64, max: u64, data: &[u8], out: &mut Vec<usize>) {
+        debug_assert!(max > 0);
+        let max = std::cmp::max(max, 1);
+        // index in `data` of the start of the current chunk, and the number of bytes of the
+        // current chunk before it
+        let mut start = 0;
+        let mut before = self.i;
+        let mut max_val = self.max_val;
+
+        // index in `data` of the byte at the maximum chunk size (or `data.len()`)
+        let limit = |start: usize, before: u64| {
+            std::cmp::min(
+                data.len() as u64,
+                (start as u64).saturating_add(max.saturating_sub(before)),
+            ) as usize
+        };
+        let mut end = limit(start, before);
+
+        let mut l_i = 0;
+        loop {
+            while l_i < end {
+                let b = data[l_i];
+                if b >= max_val {
+                    // minimum block size
+                    if (l_i - start) as u64 + before > w {
+                        break;
+                    }
+
+                    max_val = b;
+                }
+                l_i += 1;
+            }
+
+            if l_i == end {
+                if (end - start) as u64 + before != max {
+                    break;
+                }
+                // maximum block size: the last byte triggers the split
+                l_i -= 1;
+            }
+
+            start = l_i + 1;
+            before = 0;
+            max_val = 0;
+            out.push(start);
+            l_i = start;
+            end = limit(start, before);
+        }
+
+        self.i = before + (data.len() - start) as u64;
+        self.max_val = max_val;
+    }
+}
+
+impl CutReasons for Ram {
+    /// Always [`CutReason::Hash`]
+    fn cut_reason(&self, _len: u64) -> CutReason {
+        CutReason::Hash
+    }
 }
 
 impl ToChunkIncr for Ram {
@@ -106,6 +218,189 @@ pub struct RamIncr {
 
 impl ChunkIncr for RamIncr {
     fn push(&mut self, data: &[u8]) -> Option<usize> {
-        self.state.push(&self.params, data)
+        self.state
+            .push(self.params.w, u64::MAX, data)
+            .map(|i| i + 1)
+    }
+
+    fn push_all(&mut self, data: &[u8], out: &mut Vec<usize>) {
+        self.state.push_all(self.params.w, u64::MAX, data, out);
+    }
+
+    fn reset(&mut self) {
+        self.state = Default::default();
+    }
+}
+
+/// Parameters for RAML, the variant of [`Ram`] with a maximum chunk size
+///
+/// When a chunk reaches the maximum size without finding a byte at least as large as the running
+/// maximum (after the window), it is split there. The next chunk starts a new window: its running
+/// maximum is cleared and recomputed from its first `w` bytes, exactly as after any other split.
+/// Chunks that end before the maximum size are the same as those found by [`Ram`].
+///
+/// In data where every byte after the window is smaller than the running maximum (for example, a
+/// run of zeros following other data), [`Ram`] never splits. `RamL` splits it at the maximum size,
+/// and then into chunks of `w + 2` bytes, as the cleared maximum is reached by any byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "RamLSerde")
+)]
+pub struct RamL {
+    /// window size
+    w: u64,
+
+    /// maximum chunk size
+    max: u64,
+}
+
+/// Serialized form of [`RamL`], checked by [`RamL::try_with_w_and_max()`] when deserialized
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "RamL")]
+struct RamLSerde {
+    w: u64,
+    max: u64,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<RamLSerde> for RamL {
+    type Error = ConfigError;
+
+    fn try_from(s: RamLSerde) -> Result<Self, Self::Error> {
+        RamL::try_with_w_and_max(s.w, s.max)
+    }
+}
+
+impl RamL {
+    /// Construct a RAML instance with window size `w` and maximum chunk size `max`
+    ///
+    /// # Panics
+    ///
+    /// If `max` is 0. See [`RamL::try_with_w_and_max()`].
+    pub fn with_w_and_max(w: u64, max: u64) -> Self {
+        Self::try_with_w_and_max(w, max).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Construct a RAML instance with window size `w` and maximum chunk size `max`, or return an
+    /// error if `max` is 0
+    pub fn try_with_w_and_max(w: u64, max: u64) -> Result<Self, ConfigError> {
+        if max == 0 {
+            return Err(ConfigError::invalid("raml", "max", 0, "must not be zero"));
+        }
+        Ok(Self { w, max })
+    }
+}
+
+/// Formats as `raml:w=<size>,max=<size>`
+///
+/// See [`crate::spec`] for details.
+impl fmt::Display for RamL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "raml:w={},max={}",
+            SizeDisplay(self.w),
+            SizeDisplay(self.max)
+        )
+    }
+}
+
+/// Parses the format emitted by [`RamL`]'s `Display` impl. `w` and `max` are required, and `max`
+/// must not be 0.
+impl FromStr for RamL {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("raml", s)?;
+        let w = p.take_size("w")?.ok_or_else(|| p.missing("w"))?;
+        let max = p.take_size("max")?.ok_or_else(|| p.missing("max"))?;
+        if max == 0 {
+            return Err(p.invalid("max", "0", "must not be zero"));
+        }
+        p.finish()?;
+        Ok(RamL::with_w_and_max(w, max))
+    }
+}
+
+impl Chunk for RamL {
+    type SearchState = RamState;
+
+    fn to_search_state(&self) -> Self::SearchState {
+        Default::default()
+    }
+
+    fn find_chunk_edge(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+    ) -> (Option<usize>, usize) {
+        match state.push(self.w, self.max, data) {
+            Some(i) => (Some(i + 1), i + 1),
+            None => (None, data.len()),
+        }
+    }
+
+    fn find_chunk_edges(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        state.push_all(self.w, self.max, data, out);
+        data.len()
+    }
+}
+
+impl CutReasons for RamL {
+    fn cut_reason(&self, len: u64) -> CutReason {
+        if len >= self.max {
+            CutReason::MaxSize
+        } else {
+            CutReason::Hash
+        }
+    }
+}
+
+impl ToChunkIncr for RamL {
+    type Incr = RamLIncr;
+
+    fn to_chunk_incr(&self) -> Self::Incr {
+        self.into()
+    }
+}
+
+impl From<&RamL> for RamLIncr {
+    fn from(params: &RamL) -> Self {
+        Self {
+            params: params.clone(),
+            state: Default::default(),
+        }
+    }
+}
+
+/// Incremental interface to [`RamL`]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RamLIncr {
+    params: RamL,
+    state: RamState,
+}
+
+impl ChunkIncr for RamLIncr {
+    fn push(&mut self, data: &[u8]) -> Option<usize> {
+        self.state
+            .push(self.params.w, self.params.max, data)
+            .map(|i| i + 1)
+    }
+
+    fn push_all(&mut self, data: &[u8], out: &mut Vec<usize>) {
+        self.state
+            .push_all(self.params.w, self.params.max, data, out);
+    }
+
+    fn reset(&mut self) {
+        self.state = Default::default();
     }
 }
@@ -0,0 +1,171 @@
+//! Why each chunk edge was placed
+//!
+//! Most algorithms place an edge either where their rolling hash matches, or where a chunk reaches
+//! their maximum size. When examining the distribution of chunk sizes, it helps to know which.
+//! [`ReasonTracking`] reports each edge found in a stream as a [`ChunkEdge`], with the
+//! [`CutReason`] for it.
+//!
+//! The reason for an edge is determined from the size of the chunk it ends, using
+//! [`CutReasons::cut_reason()`], so [`ChunkIncr::push()`] and [`Chunk::find_chunk_edge()`] do no
+//! additional work, and there is no cost unless reasons are requested.
+//!
+//! ```rust
+//! # #[cfg(feature = "mii")] {
+//! use hash_roll::reason::{CutReason, ReasonTracking};
+//!
+//! let data: Vec<u8> = (0..100_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+//! let mut rt = ReasonTracking::new(hash_roll::mii::Mii::with_w_and_range(5, ..=2000));
+//! let mut edges = Vec::new();
+//! for piece in data.chunks(1000) {
+//!     rt.push_all(piece, &mut edges);
+//! }
+//! edges.extend(rt.finish());
+//!
+//! let max = edges.iter().filter(|e| e.reason == CutReason::MaxSize).count();
+//! println!("{} of {} chunks reached the maximum size", max, edges.len());
+//! assert_eq!(edges.last().unwrap().offset, data.len() as u64);
+//! # }
+//! ```
+//!
+//! [`ChunkIncr::push()`]: crate::ChunkIncr::push()
+//! [`Chunk::find_chunk_edge()`]: crate::Chunk::find_chunk_edge()
+use crate::offset::{ChunkBoundary, OffsetTracking};
+use crate::ToChunkIncr;
+
+/// Why a chunk edge was placed
+///
+/// Algorithms report the reasons which apply to them (see [`CutReasons`]), and more may be added
+/// for new algorithms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum CutReason {
+    /// The content matched the algorithm's condition for an edge (typically, the rolling hash
+    /// matched its target)
+    Hash,
+
+    /// FastCDC: the hash matched the stricter mask (`MASK_S`) used before the normal size
+    HashStrict,
+
+    /// FastCDC: the hash matched the looser mask (`MASK_L`) used from the normal size
+    HashLoose,
+
+    /// The chunk reached the maximum size
+    MaxSize,
+
+    /// The edge was placed by [`ChunkIncr::force_boundary()`](crate::ChunkIncr::force_boundary())
+    Forced,
+
+    /// The input ended (the final chunk, which does not end on an edge)
+    EndOfData,
+}
+
+/// A chunk edge, and why it was placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChunkEdge {
+    /// Offset in the stream of the byte following the edge (the end of the chunk)
+    pub offset: u64,
+
+    /// Why the edge was placed
+    pub reason: CutReason,
+}
+
+/// Implemented on algorithm instances that can explain their chunk edges
+///
+/// Wrappers like [`bounded::Bounded`](crate::bounded::Bounded) and
+/// [`runs::Runs`](crate::runs::Runs) don't implement this, as the size of a chunk doesn't
+/// determine which of their conditions placed it.
+pub trait CutReasons {
+    /// The reason for an edge found by this algorithm that ends a chunk of `len` bytes
+    ///
+    /// When several conditions hold at once (for example, the hash matches on the last byte of a
+    /// chunk of the maximum size), [`CutReason::MaxSize`] is reported.
+    fn cut_reason(&self, len: u64) -> CutReason;
+}
+
+/// Chunks a stream, reporting the position of each edge and why it was placed
+///
+/// This is [`OffsetTracking`] for algorithms that implement [`CutReasons`]. See the
+/// [module documentation](self) for an example.
+#[derive(Debug, Clone)]
+pub struct ReasonTracking<C: ToChunkIncr> {
+    chunker: C,
+    inner: OffsetTracking<C::Incr>,
+
+    /// chunks found by `push_all()`, kept to reuse the allocation
+    boundaries: Vec<ChunkBoundary>,
+}
+
+impl<C: ToChunkIncr + CutReasons> ReasonTracking<C> {
+    /// Chunk a new stream with `chunker`
+    pub fn new(chunker: C) -> Self {
+        ReasonTracking {
+            inner: OffsetTracking::new(chunker.to_chunk_incr()),
+            chunker,
+            boundaries: Vec::new(),
+        }
+    }
+
+    /// Feed `data` into the chunker
+    ///
+    /// If a chunk ends in `data`, returns the index in `data` immediately following the chunk and
+    /// its edge. Only the data prior to the returned index has been consumed: call `push()` again
+    /// with the remainder of `data` to continue.
+    ///
+    /// If no chunk ends in `data`, all of `data` is consumed and `None` is returned.
+    pub fn push(&mut self, data: &[u8]) -> Option<(usize, ChunkEdge)> {
+        let (i, b) = self.inner.push(data)?;
+        Some((i, self.edge(b)))
+    }
+
+    /// Feed all of `data` into the chunker, appending the edge of each completed chunk to `out`
+    pub fn push_all(&mut self, data: &[u8], out: &mut Vec<ChunkEdge>) {
+        self.boundaries.clear();
+        self.inner.push_all(data, &mut self.boundaries);
+        let chunker = &self.chunker;
+        out.extend(self.boundaries.iter().map(|b| ChunkEdge {
+            offset: b.end,
+            reason: chunker.cut_reason(b.len()),
+        }));
+    }
+
+    /// Number of bytes pushed so far, which is the offset of the next byte to be pushed
+    pub fn offset(&self) -> u64 {
+        self.inner.offset()
+    }
+
+    /// End the current chunk at the current offset, restarting the chunker as if a new source
+    /// started there
+    ///
+    /// Returns an edge with [`CutReason::Forced`] if any data was passed since the last edge. See
+    /// [`ChunkIncr::force_boundary()`](crate::ChunkIncr::force_boundary()).
+    pub fn force_boundary(&mut self) -> Option<ChunkEdge> {
+        self.inner.force_boundary().map(|b| ChunkEdge {
+            offset: b.end,
+            reason: CutReason::Forced,
+        })
+    }
+
+    /// End the stream
+    ///
+    /// Returns an edge with [`CutReason::EndOfData`] if any data was passed since the last edge.
+    pub fn finish(self) -> Option<ChunkEdge> {
+        self.inner.finish().map(|b| ChunkEdge {
+            offset: b.end,
+            reason: CutReason::EndOfData,
+        })
+    }
+
+    /// The configured algorithm instance
+    pub fn chunker(&self) -> &C {
+        &self.chunker
+    }
+
+    fn edge(&self, b: ChunkBoundary) -> ChunkEdge {
+        ChunkEdge {
+            offset: b.end,
+            reason: self.chunker.cut_reason(b.len()),
+        }
+    }
+}
@@ -0,0 +1,335 @@
+//! Cutting long runs of a single repeated byte without hashing them
+//!
+//! Zero filled and other constant regions are handled poorly by many algorithms: [`crate::mii`]
+//! never sees an increment and [`crate::ram`] never sees a larger byte, so neither cuts until some
+//! other data arrives, while hash based algorithms either never match (and cut only at their
+//! maximum size, if any) or match on every byte. They also spend the same time hashing each byte
+//! of such a region as they do for any other data.
+//!
+//! [`Runs`] wraps any algorithm and applies a fixed run-length cut policy instead:
+//!
+//!  1. Data is passed to the wrapped algorithm as normal, and its chunk edges are used as-is.
+//!  2. Once `min_run` consecutive copies of the same byte have been passed to it (counting across
+//!     chunk edges), a chunk edge is placed immediately after them and the wrapped algorithm is
+//!     reset.
+//!  3. The rest of the run is not passed to the wrapped algorithm. It is only compared against
+//!     the repeated byte, and is cut into chunks of `run_chunk_len` bytes.
+//!  4. A chunk edge is placed at the end of the run (unless the run ended exactly on an edge), and
+//!     the data following it is passed to a reset wrapped algorithm.
+//!
+//! Each edge depends only on the data before it, so this works the same for the incremental and
+//! all-at-once interfaces. Runs shorter than `min_run` bytes do not affect the edges at all.
+//!
+//! Unlike the algorithms it wraps, [`Runs`] is not reset at every chunk edge (a run continues
+//! across the edges placed within it), so [`Chunk::resets_at_edges()`] is `false`.
+//!
+//! ```rust
+//! # #[cfg(feature = "mii")] {
+//! use hash_roll::runs::Runs;
+//! use hash_roll::Chunk;
+//!
+//! // 1 MiB of zeros, then some other data
+//! let mut data = vec![0u8; 1 << 20];
+//! data.extend((1..10_000u32).map(|i| (i * 7919 % 251) as u8));
+//!
+//! let chunker = Runs::new(hash_roll::mii::Mii::default(), 64, 1 << 16);
+//! let ranges: Vec<_> = chunker.chunk_ranges(&data).collect();
+//! assert_eq!(ranges[0], 0..64);
+//! assert_eq!(ranges[1], 64..(64 + (1 << 16)));
+//! assert_eq!(ranges[16].end, 1 << 20);
+//! # }
+//! ```
+use crate::config::ConfigError;
+use crate::{Chunk, ChunkIncr, ToChunkIncr};
+
+/// Wrap a chunking algorithm to cut long runs of a repeated byte using a fixed policy
+///
+/// See the [module documentation](self) for the policy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Runs<C> {
+    inner: C,
+    min_run: u64,
+    run_chunk_len: u64,
+}
+
+impl<C> Runs<C> {
+    /// Wrap `inner`, treating `min_run` or more copies of a byte as a run, and cutting the
+    /// remainder of runs into chunks of `run_chunk_len` bytes
+    ///
+    /// # Panics
+    ///
+    /// If `min_run` or `run_chunk_len` is 0. See [`Runs::try_new()`].
+    pub fn new(inner: C, min_run: u64, run_chunk_len: u64) -> Self {
+        Self::try_new(inner, min_run, run_chunk_len).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Wrap `inner`, treating `min_run` or more copies of a byte as a run, and cutting the
+    /// remainder of runs into chunks of `run_chunk_len` bytes, or return an error if either is 0
+    pub fn try_new(inner: C, min_run: u64, run_chunk_len: u64) -> Result<Self, ConfigError> {
+        if min_run == 0 {
+            return Err(ConfigError::invalid(
+                "runs",
+                "min_run",
+                0,
+                "must not be zero",
+            ));
+        }
+        if run_chunk_len == 0 {
+            return Err(ConfigError::invalid(
+                "runs",
+                "run_chunk_len",
+                0,
+                "must not be zero",
+            ));
+        }
+        Ok(Runs {
+            inner,
+            min_run,
+            run_chunk_len,
+        })
+    }
+
+    /// The wrapped algorithm
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+}
+
+/// Position in the run-length cut policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RunTracker {
+    /// The most recent byte
+    byte: u8,
+
+    /// Number of consecutive copies of `byte` passed to the wrapped algorithm
+    len: u64,
+
+    /// Within a run: the wrapped algorithm is not being used
+    in_run: bool,
+
+    /// Within a run: the number of bytes since the last edge
+    fill: u64,
+}
+
+impl RunTracker {
+    fn new() -> Self {
+        RunTracker {
+            byte: 0,
+            len: 0,
+            in_run: false,
+            fill: 0,
+        }
+    }
+
+    /// Index in `data` of the byte that completes a run of `min_run` bytes, if any
+    fn find_run(&self, min_run: u64, data: &[u8]) -> Option<usize> {
+        let mut byte = self.byte;
+        let mut len = self.len;
+        for (i, &b) in data.iter().enumerate() {
+            if b == byte {
+                len += 1;
+            } else {
+                byte = b;
+                len = 1;
+            }
+
+            if len >= min_run {
+                return Some(i);
+            }
+        }
+
+        None
+    }
+
+    /// Account for `data` being passed to the wrapped algorithm, where `data` does not complete a
+    /// run
+    fn advance(&mut self, data: &[u8]) {
+        if let Some(&last) = data.last() {
+            let n = data.iter().rev().take_while(|&&b| b == last).count() as u64;
+            if n == data.len() as u64 && last == self.byte {
+                self.len += n;
+            } else {
+                self.byte = last;
+                self.len = n;
+            }
+        }
+    }
+
+    /// Enter a run of `byte` after an edge following its first `min_run` bytes
+    fn start_run(&mut self, byte: u8) {
+        self.byte = byte;
+        self.in_run = true;
+        self.fill = 0;
+    }
+
+    /// Continue a run with `data`, returning the next edge (if any) and whether the run ended
+    /// there. When the run ends, the wrapped algorithm should be reset and used from the returned
+    /// index.
+    fn continue_run(&mut self, run_chunk_len: u64, data: &[u8]) -> (Option<usize>, bool) {
+        let room = run_chunk_len - self.fill;
+        let limit = std::cmp::min(room, data.len() as u64) as usize;
+        let n = data[..limit]
+            .iter()
+            .take_while(|&&b| b == self.byte)
+            .count();
+
+        if n as u64 == room {
+            self.fill = 0;
+            return (Some(n), false);
+        }
+
+        if n < data.len() {
+            // the run ends at `n`
+            self.in_run = false;
+            self.len = 0;
+            let cut = self.fill + n as u64 > 0;
+            self.fill = 0;
+            return (if cut { Some(n) } else { None }, true);
+        }
+
+        self.fill += n as u64;
+        (None, false)
+    }
+}
+
+impl<C: Chunk> Chunk for Runs<C> {
+    type SearchState = RunsSearchState<C::SearchState>;
+
+    fn to_search_state(&self) -> Self::SearchState {
+        RunsSearchState {
+            inner: self.inner.to_search_state(),
+            runs: RunTracker::new(),
+            seen: 0,
+        }
+    }
+
+    fn reset_search_state(&self, state: &mut Self::SearchState) {
+        self.inner.reset_search_state(&mut state.inner);
+        state.runs = RunTracker::new();
+        state.seen = 0;
+    }
+
+    fn force_boundary(&self, state: &mut Self::SearchState) {
+        self.inner.force_boundary(&mut state.inner);
+        state.runs = RunTracker::new();
+        state.seen = 0;
+    }
+
+    /// `false`: a run continues across the edges placed within it
+    fn resets_at_edges(&self) -> bool {
+        false
+    }
+
+    fn find_chunk_edge(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+    ) -> (Option<usize>, usize) {
+        let start = state.seen;
+        if state.runs.in_run {
+            match state.runs.continue_run(self.run_chunk_len, &data[start..]) {
+                (Some(i), _) => {
+                    state.seen = 0;
+                    return (Some(start + i), start + i);
+                }
+                (None, false) => {
+                    state.seen = 0;
+                    return (None, data.len());
+                }
+                (None, true) => {}
+            }
+        }
+
+        let run = state.runs.find_run(self.min_run, &data[start..]);
+        let end = run.map_or(data.len(), |i| start + i + 1);
+        let (cut, discard_ct) = self.inner.find_chunk_edge(&mut state.inner, &data[..end]);
+        match cut {
+            Some(i) if i < end => {
+                state.runs.advance(&data[start..i]);
+                state.seen = i - discard_ct;
+                (Some(i), discard_ct)
+            }
+            _ if run.is_some() => {
+                self.inner.reset_search_state(&mut state.inner);
+                state.runs.start_run(data[end - 1]);
+                state.seen = 0;
+                (Some(end), end)
+            }
+            cut => {
+                state.runs.advance(&data[start..]);
+                state.seen = data.len() - discard_ct;
+                (cut, discard_ct)
+            }
+        }
+    }
+}
+
+/// Intermediate state for [`Runs::find_chunk_edge()`]
+#[derive(Debug, Clone)]
+pub struct RunsSearchState<S> {
+    inner: S,
+    runs: RunTracker,
+
+    /// Number of bytes at the start of the next `data` that have already been examined
+    seen: usize,
+}
+
+impl<C: ToChunkIncr + Clone> ToChunkIncr for Runs<C> {
+    type Incr = RunsIncr<C>;
+
+    fn to_chunk_incr(&self) -> Self::Incr {
+        RunsIncr {
+            inner: self.inner.to_chunk_incr(),
+            params: self.clone(),
+            runs: RunTracker::new(),
+        }
+    }
+}
+
+/// Incremental interface to [`Runs`]
+#[derive(Debug, Clone)]
+pub struct RunsIncr<C: ToChunkIncr> {
+    params: Runs<C>,
+    inner: C::Incr,
+    runs: RunTracker,
+}
+
+impl<C: ToChunkIncr> ChunkIncr for RunsIncr<C> {
+    fn push(&mut self, data: &[u8]) -> Option<usize> {
+        if self.runs.in_run {
+            match self.runs.continue_run(self.params.run_chunk_len, data) {
+                (Some(i), _) => return Some(i),
+                (None, false) => return None,
+                (None, true) => {}
+            }
+        }
+
+        let run = self.runs.find_run(self.params.min_run, data);
+        let end = run.map_or(data.len(), |i| i + 1);
+        match self.inner.push(&data[..end]) {
+            Some(i) if i < end => {
+                self.runs.advance(&data[..i]);
+                Some(i)
+            }
+            _ if run.is_some() => {
+                self.inner.reset();
+                self.runs.start_run(data[end - 1]);
+                Some(end)
+            }
+            cut => {
+                self.runs.advance(data);
+                cut
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.runs = RunTracker::new();
+    }
+
+    fn force_boundary(&mut self) {
+        self.inner.force_boundary();
+        self.runs = RunTracker::new();
+    }
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "serde")]
+
+//! Serialization of the tables used by some algorithms (gear, buzhash)
+//!
+//! Tables that hash-roll provides are serialized by name (the same names used by [`crate::spec`]),
+//! and other tables are serialized by value.
+//!
+//! Algorithms that borrow their table (like [`crate::fastcdc::FastCdc`]) can only be deserialized
+//! when the table is one provided by hash-roll (either by name, or by a value that matches it).
+//! Algorithms that own their table (like [`crate::buzhash::BuzHashTableBufHash`]) can be
+//! deserialized from any table.
+use serde::{Deserialize, Serialize};
+
+/// Serialized form of a table of 256 entries
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TableRepr<T> {
+    Named(String),
+    Value(Vec<T>),
+}
+
+/// A named table provided by hash-roll
+pub(crate) type KnownTable<T> = (&'static str, &'static [T; 256]);
+
+impl<T: Copy + PartialEq + 'static> TableRepr<T> {
+    pub(crate) fn from_table(table: &[T; 256], known: &[KnownTable<T>]) -> Self {
+        match known.iter().find(|(_, k)| k[..] == table[..]) {
+            Some((name, _)) => TableRepr::Named((*name).to_owned()),
+            None => TableRepr::Value(table.to_vec()),
+        }
+    }
+
+    /// Find the static table this represents
+    pub(crate) fn to_static(&self, known: &[KnownTable<T>]) -> Result<&'static [T; 256], String> {
+        let found = match self {
+            TableRepr::Named(name) => known.iter().find(|(n, _)| n == name),
+            TableRepr::Value(v) => known.iter().find(|(_, k)| k[..] == v[..]),
+        };
+
+        match (found, self) {
+            (Some((_, table)), _) => Ok(table),
+            (None, TableRepr::Named(name)) => Err(format!("unknown table `{}`", name)),
+            (None, TableRepr::Value(_)) => Err(
+                "custom table can't be deserialized into a borrowed table, use an owned table"
+                    .to_owned(),
+            ),
+        }
+    }
+
+    /// Obtain an owned copy of the table this represents
+    pub(crate) fn to_owned_table(&self, known: &[KnownTable<T>]) -> Result<Box<[T; 256]>, String> {
+        match self {
+            TableRepr::Named(_) => self.to_static(known).map(|t| Box::new(*t)),
+            TableRepr::Value(v) => {
+                if v.len() != 256 {
+                    return Err(format!("table has {} entries, expected 256", v.len()));
+                }
+                let mut table = Box::new([v[0]; 256]);
+                table.copy_from_slice(&v[..]);
+                Ok(table)
+            }
+        }
+    }
+}
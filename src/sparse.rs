@@ -0,0 +1,162 @@
+//! Chunking files without reading their holes (Linux only)
+//!
+//! Sparse files (like many VM images) can contain large "holes": regions that were never written
+//! and read back as zeros, but take no space on disk. [`chunk_file()`] uses
+//! `lseek(SEEK_DATA)`/`lseek(SEEK_HOLE)` to find them and feeds zeros to the chunker for each hole
+//! instead of reading it. The regions that contain data are read normally.
+//!
+//! The chunk edges are exactly those that would be found by reading the entire file and passing
+//! it to [`ChunkIncr::push()`]. Only the reading is avoided: the chunker still examines each zero
+//! byte of a hole.
+//!
+//! Filesystems that don't support `SEEK_DATA`/`SEEK_HOLE` report the entire file as data, in which
+//! case this is equivalent to reading the whole file.
+use crate::ChunkIncr;
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io;
+use std::ops::Range;
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::AsRawFd;
+
+/// Size of the reads used for regions containing data
+const READ_LEN: usize = 1 << 20;
+
+/// Zeros fed to the chunker for holes
+static ZEROS: [u8; 1 << 16] = [0; 1 << 16];
+
+/// Chunk the contents of `file`, returning the range of each chunk
+///
+/// See [`chunk_file_with()`].
+///
+/// # Examples
+///
+/// ```no_run
+/// # #[cfg(feature = "fastcdc")] {
+/// use hash_roll::ToChunkIncr;
+///
+/// let file = std::fs::File::open("disk.img")?;
+/// let chunker = hash_roll::fastcdc::FastCdc::default();
+/// for range in hash_roll::sparse::chunk_file(chunker.to_chunk_incr(), &file)? {
+///     println!("chunk: {:?}", range);
+/// }
+/// # }
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub fn chunk_file<I: ChunkIncr>(incr: I, file: &File) -> io::Result<Vec<Range<u64>>> {
+    let mut ranges = Vec::new();
+    chunk_file_with(incr, file, |r| {
+        ranges.push(r);
+        Ok(())
+    })?;
+    Ok(ranges)
+}
+
+/// Chunk the contents of `file`, calling `f` with the range of each chunk in order
+///
+/// `incr` should be newly created, as the start of `file` is treated as the start of a chunk. The
+/// trailing data that does not end on a chunk edge (if any) is passed to `f` as the last range,
+/// so the ranges always cover the entire file. Errors returned by `f` stop chunking and are
+/// returned.
+///
+/// The file's current offset is changed by the `lseek()` calls used to find holes. Data is read
+/// using positioned reads, so the offset does not otherwise matter.
+pub fn chunk_file_with<I, F>(mut incr: I, file: &File, mut f: F) -> io::Result<()>
+where
+    I: ChunkIncr,
+    F: FnMut(Range<u64>) -> io::Result<()>,
+{
+    let len = file.metadata()?.len();
+    let mut buf = vec![0u8; READ_LEN];
+    let mut edges = Vec::new();
+    let mut last_edge = 0;
+    let mut pos = 0;
+
+    // feed `data` (starting at `offset` in the file) to `incr`, passing completed chunks to `f`
+    let mut feed = |incr: &mut I, offset: u64, data: &[u8]| -> io::Result<()> {
+        edges.clear();
+        incr.push_all(data, &mut edges);
+        for &e in &edges {
+            let e = offset + e as u64;
+            f(last_edge..e)?;
+            last_edge = e;
+        }
+        Ok(())
+    };
+
+    while pos < len {
+        let data_start = std::cmp::min(seek_data(file, pos)?.unwrap_or(len), len);
+        let data_end = if data_start < len {
+            std::cmp::min(seek_hole(file, data_start)?, len)
+        } else {
+            len
+        };
+
+        // the hole
+        while pos < data_start {
+            let l = std::cmp::min(data_start - pos, ZEROS.len() as u64) as usize;
+            feed(&mut incr, pos, &ZEROS[..l])?;
+            pos += l as u64;
+        }
+
+        // the data
+        while pos < data_end {
+            let l = std::cmp::min(data_end - pos, buf.len() as u64) as usize;
+            let n = match file.read_at(&mut buf[..l], pos) {
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "file shrank while chunking",
+                    ))
+                }
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            feed(&mut incr, pos, &buf[..n])?;
+            pos += n as u64;
+        }
+    }
+
+    if last_edge != len {
+        f(last_edge..len)?;
+    }
+
+    Ok(())
+}
+
+/// The offset of the first data at or after `pos`, or `None` if there is only a hole after `pos`
+///
+/// If the filesystem does not support finding holes, `pos` is returned.
+fn seek_data(file: &File, pos: u64) -> io::Result<Option<u64>> {
+    match lseek(file, pos, libc::SEEK_DATA) {
+        Ok(p) => Ok(Some(p)),
+        Err(e) if e.raw_os_error() == Some(libc::ENXIO) => Ok(None),
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => Ok(Some(pos)),
+        Err(e) => Err(e),
+    }
+}
+
+/// The offset of the first hole at or after `pos`. The end of the file counts as a hole.
+///
+/// If the filesystem does not support finding holes, `u64::MAX` is returned.
+fn seek_hole(file: &File, pos: u64) -> io::Result<u64> {
+    match lseek(file, pos, libc::SEEK_HOLE) {
+        Ok(p) => Ok(p),
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => Ok(u64::MAX),
+        Err(e) => Err(e),
+    }
+}
+
+fn lseek(file: &File, pos: u64, whence: libc::c_int) -> io::Result<u64> {
+    let pos = libc::off64_t::try_from(pos)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "offset too large"))?;
+    // SAFETY: `lseek64` has no memory safety requirements, and the descriptor is valid for the
+    // lifetime of `file`
+    let r = unsafe { libc::lseek64(file.as_raw_fd(), pos, whence) };
+    if r < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(r as u64)
+    }
+}
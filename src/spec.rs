@@ -0,0 +1,372 @@
+//! Textual specification of configured algorithm instances
+//!
+//! Each configured algorithm instance implements [`FromStr`](std::str::FromStr) and
+//! [`Display`](std::fmt::Display) using a common format: the algorithm name, optionally followed
+//! by a `:` and a comma separated list of `key=value` parameters. Parameters that are not given
+//! use the same defaults as the algorithm's [`Default`] impl (where it has one).
+//!
+//! ```notrust
+//! fastcdc:min=2KiB,avg=8KiB,max=64KiB
+//! buzhash:k=67,mask=0xfff,max=16MiB,table=nom,salt=3
+//! zpaq:fragment=16
+//! ```
+//!
+//! Byte sizes may be given as plain integers or with a suffix: `B`, binary suffixes (`KiB`, `MiB`,
+//! `GiB`, `TiB`), or decimal suffixes (`kB`, `MB`, `GB`, `TB`). Other integers may be given in
+//! decimal or in hex with a `0x` prefix.
+//!
+//! Formatting a configured algorithm instance and parsing the result produces an equivalent
+//! instance. Instances using custom tables (which can't be named) are formatted with
+//! `table=custom`, which is rejected when parsing.
+//!
+//! Window sizes (`k` for `buzhash`, `window` for `bup` and `gzip`) determine the size of a buffer
+//! allocated by the incremental chunkers, so they are limited to [`MAX_WINDOW`] when parsing.
+//! Larger windows can only be configured using the algorithm's constructors.
+//!
+//! [`DynChunker`](crate::dyn_chunker::DynChunker) parses any of the specifications, selecting the
+//! algorithm by name.
+use std::fmt;
+
+/// The largest window size accepted when parsing a specification (1 MiB)
+pub const MAX_WINDOW: u64 = 1 << 20;
+
+/// Error from parsing a textual algorithm specification
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecError {
+    /// The algorithm name is not known (or its feature is not enabled)
+    UnknownAlgorithm(String),
+
+    /// The specification names a different algorithm than the one being parsed
+    WrongAlgorithm {
+        expected: &'static str,
+        found: String,
+    },
+
+    /// A parameter was not in `key=value` form
+    Malformed {
+        algorithm: &'static str,
+        item: String,
+    },
+
+    /// A parameter is not used by the algorithm
+    UnknownParameter {
+        algorithm: &'static str,
+        param: String,
+    },
+
+    /// A parameter was given more than once
+    DuplicateParameter {
+        algorithm: &'static str,
+        param: String,
+    },
+
+    /// A parameter that has no default was not given
+    MissingParameter {
+        algorithm: &'static str,
+        param: &'static str,
+    },
+
+    /// A parameter value could not be parsed or is out of range
+    InvalidValue {
+        algorithm: &'static str,
+        param: &'static str,
+        value: String,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for SpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpecError::UnknownAlgorithm(name) => write!(f, "unknown algorithm `{}`", name),
+            SpecError::WrongAlgorithm { expected, found } => {
+                write!(f, "expected algorithm `{}`, found `{}`", expected, found)
+            }
+            SpecError::Malformed { algorithm, item } => write!(
+                f,
+                "{}: parameter `{}` is not of the form `key=value`",
+                algorithm, item
+            ),
+            SpecError::UnknownParameter { algorithm, param } => {
+                write!(f, "{}: unknown parameter `{}`", algorithm, param)
+            }
+            SpecError::DuplicateParameter { algorithm, param } => {
+                write!(
+                    f,
+                    "{}: parameter `{}` given more than once",
+                    algorithm, param
+                )
+            }
+            SpecError::MissingParameter { algorithm, param } => {
+                write!(f, "{}: missing required parameter `{}`", algorithm, param)
+            }
+            SpecError::InvalidValue {
+                algorithm,
+                param,
+                value,
+                reason,
+            } => write!(
+                f,
+                "{}: invalid value `{}` for `{}`: {}",
+                algorithm, value, param, reason
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpecError {}
+
+const SIZE_SUFFIXES: &[(&str, u64)] = &[
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+    ("TB", 1_000_000_000_000),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("kB", 1_000),
+    ("KB", 1_000),
+    ("B", 1),
+];
+
+/// Parse a byte size, like `8KiB`, `1MB` or `4096`
+///
+/// # Examples
+///
+/// ```
+/// use hash_roll::spec::parse_size;
+///
+/// assert_eq!(parse_size("8KiB"), Some(8192));
+/// assert_eq!(parse_size("2 MB"), Some(2_000_000));
+/// assert_eq!(parse_size("512"), Some(512));
+/// assert_eq!(parse_size("1.5KiB"), None);
+/// ```
+pub fn parse_size(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if s.starts_with("0x") || s.starts_with("0X") {
+        // hex digits would otherwise be mistaken for a `B` suffix
+        return parse_u64(s);
+    }
+
+    for (suffix, mult) in SIZE_SUFFIXES {
+        if let Some(n) = s.strip_suffix(suffix) {
+            return parse_u64(n.trim_end())?.checked_mul(*mult);
+        }
+    }
+
+    parse_u64(s)
+}
+
+/// Format a byte size using the largest binary suffix that represents it exactly
+///
+/// The output is accepted by [`parse_size()`].
+///
+/// ```
+/// use hash_roll::spec::SizeDisplay;
+///
+/// assert_eq!(SizeDisplay(8192).to_string(), "8KiB");
+/// assert_eq!(SizeDisplay(8193).to_string(), "8193");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SizeDisplay(pub u64);
+
+impl fmt::Display for SizeDisplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let v = self.0;
+        if v != 0 {
+            // only the binary suffixes, which are all powers of 2
+            for (suffix, mult) in SIZE_SUFFIXES.iter().take(4) {
+                if v.trailing_zeros() >= mult.trailing_zeros() {
+                    return write!(f, "{}{}", v / mult, suffix);
+                }
+            }
+        }
+
+        write!(f, "{}", v)
+    }
+}
+
+/// Parse an integer in decimal, or in hex with a `0x` prefix
+fn parse_u64(s: &str) -> Option<u64> {
+    if let Some(h) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        u64::from_str_radix(h, 16).ok()
+    } else if s.starts_with(|c: char| c.is_ascii_digit()) {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+/// Parameters of a single specification, consumed by each algorithm's `FromStr` impl
+#[derive(Debug)]
+pub(crate) struct Params<'s> {
+    algorithm: &'static str,
+    items: Vec<(&'s str, &'s str)>,
+}
+
+/// Split a specification into it's algorithm name and (unparsed) parameters
+pub(crate) fn split_name(s: &str) -> (&str, &str) {
+    let s = s.trim();
+    match s.find(':') {
+        Some(i) => (s[..i].trim(), &s[i + 1..]),
+        None => (s, ""),
+    }
+}
+
+// some helpers are unused depending on which algorithms are enabled
+#[allow(dead_code)]
+impl<'s> Params<'s> {
+    /// Parse `s`, which must name `algorithm`
+    pub(crate) fn parse(algorithm: &'static str, s: &'s str) -> Result<Self, SpecError> {
+        let (name, params) = split_name(s);
+        if name != algorithm {
+            return Err(SpecError::WrongAlgorithm {
+                expected: algorithm,
+                found: name.to_owned(),
+            });
+        }
+
+        let mut items: Vec<(&str, &str)> = Vec::new();
+        for item in params.split(',') {
+            let item = item.trim();
+            if item.is_empty() {
+                continue;
+            }
+
+            let (k, v) = match item.find('=') {
+                Some(i) => (item[..i].trim(), item[i + 1..].trim()),
+                None => {
+                    return Err(SpecError::Malformed {
+                        algorithm,
+                        item: item.to_owned(),
+                    })
+                }
+            };
+
+            if items.iter().any(|(pk, _)| *pk == k) {
+                return Err(SpecError::DuplicateParameter {
+                    algorithm,
+                    param: k.to_owned(),
+                });
+            }
+
+            items.push((k, v));
+        }
+
+        Ok(Params { algorithm, items })
+    }
+
+    /// Remove and return the raw value of `param`
+    pub(crate) fn take(&mut self, param: &'static str) -> Option<&'s str> {
+        let i = self.items.iter().position(|(k, _)| *k == param)?;
+        Some(self.items.remove(i).1)
+    }
+
+    pub(crate) fn invalid(
+        &self,
+        param: &'static str,
+        value: &str,
+        reason: &'static str,
+    ) -> SpecError {
+        SpecError::InvalidValue {
+            algorithm: self.algorithm,
+            param,
+            value: value.to_owned(),
+            reason,
+        }
+    }
+
+    pub(crate) fn missing(&self, param: &'static str) -> SpecError {
+        SpecError::MissingParameter {
+            algorithm: self.algorithm,
+            param,
+        }
+    }
+
+    /// Remove and parse `param` as a byte size
+    pub(crate) fn take_size(&mut self, param: &'static str) -> Result<Option<u64>, SpecError> {
+        match self.take(param) {
+            None => Ok(None),
+            Some(v) => match parse_size(v) {
+                Some(n) => Ok(Some(n)),
+                None => Err(self.invalid(param, v, "expected a byte size")),
+            },
+        }
+    }
+
+    /// Remove and parse `param` as an integer, checking it is no larger than `max`
+    pub(crate) fn take_int(
+        &mut self,
+        param: &'static str,
+        max: u64,
+    ) -> Result<Option<u64>, SpecError> {
+        match self.take(param) {
+            None => Ok(None),
+            Some(v) => match parse_u64(v) {
+                Some(n) if n <= max => Ok(Some(n)),
+                Some(_) => Err(self.invalid(param, v, "too large")),
+                None => Err(self.invalid(param, v, "expected an integer")),
+            },
+        }
+    }
+
+    /// Check that all parameters were used
+    pub(crate) fn finish(self) -> Result<(), SpecError> {
+        match self.items.first() {
+            Some((k, _)) => Err(SpecError::UnknownParameter {
+                algorithm: self.algorithm,
+                param: (*k).to_owned(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sizes() {
+        assert_eq!(parse_size("0"), Some(0));
+        assert_eq!(parse_size("64KiB"), Some(64 * 1024));
+        assert_eq!(parse_size("16MiB"), Some(16 << 20));
+        assert_eq!(parse_size("3kB"), Some(3000));
+        assert_eq!(parse_size("7B"), Some(7));
+        assert_eq!(parse_size("0x10"), Some(16));
+        assert_eq!(parse_size("0x1B"), Some(27));
+        assert_eq!(parse_size("KiB"), None);
+        assert_eq!(parse_size("-1"), None);
+        assert_eq!(parse_size("16777216TiB"), None);
+
+        for &v in &[0, 1, 1023, 1024, 4096, 3 << 20, 5 << 40, 1 << 50] {
+            assert_eq!(parse_size(&SizeDisplay(v).to_string()), Some(v));
+        }
+    }
+
+    #[test]
+    fn params() {
+        let mut p = Params::parse("x", "x: a=1, b=2KiB,").unwrap();
+        assert_eq!(p.take_int("a", 10), Ok(Some(1)));
+        assert_eq!(p.take_size("b"), Ok(Some(2048)));
+        assert_eq!(p.take_size("c"), Ok(None));
+        assert_eq!(p.finish(), Ok(()));
+
+        assert!(matches!(
+            Params::parse("x", "y:a=1"),
+            Err(SpecError::WrongAlgorithm { .. })
+        ));
+        assert!(matches!(
+            Params::parse("x", "x:a=1,a=2"),
+            Err(SpecError::DuplicateParameter { .. })
+        ));
+        assert!(matches!(
+            Params::parse("x", "x:a"),
+            Err(SpecError::Malformed { .. })
+        ));
+        assert!(matches!(
+            Params::parse("x", "x:a=1").unwrap().finish(),
+            Err(SpecError::UnknownParameter { .. })
+        ));
+    }
+}
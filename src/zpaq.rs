@@ -5,9 +5,13 @@
 use std::fmt;
 use std::num::Wrapping;
 
+use crate::config::ConfigError;
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SizeDisplay, SpecError};
 use crate::{Chunk, ChunkIncr, RangeExt, ToChunkIncr};
 use std::ops::Bound;
 use std::ops::RangeBounds;
+use std::str::FromStr;
 
 /**
  * A splitter used in go 'dedup' and zpaq that does not require looking back in the source
@@ -46,11 +50,45 @@ use std::ops::RangeBounds;
  *  - None
  */
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "ZpaqSerde")
+)]
 pub struct Zpaq {
     range: (Bound<u64>, Bound<u64>),
     max_hash: u32,
 }
 
+/// Serialized form of [`Zpaq`], checked by [`Zpaq::try_with_average_and_range()`] when
+/// deserialized
+///
+/// `max_hash` must be one [`Zpaq::max_hash_from_fragment_ave()`] produces.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Zpaq")]
+struct ZpaqSerde {
+    range: (Bound<u64>, Bound<u64>),
+    max_hash: u32,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<ZpaqSerde> for Zpaq {
+    type Error = ConfigError;
+
+    fn try_from(s: ZpaqSerde) -> Result<Self, Self::Error> {
+        if !s.max_hash.is_power_of_two() {
+            return Err(ConfigError::invalid(
+                "zpaq",
+                "max_hash",
+                s.max_hash as u64,
+                "must be a power of 2",
+            ));
+        }
+        Zpaq::try_with_average_and_range(32 - s.max_hash.trailing_zeros() as u8, s.range)
+    }
+}
+
 impl Zpaq {
     /* this is taken from go-dedup */
     fn fragment_ave_from_max(max: u64) -> u8 {
@@ -59,24 +97,34 @@ impl Zpaq {
     }
 
     /* these are based on the zpaq (not go-dedup) calculations */
-    fn fragment_ave_from_range<T: RangeBounds<u64>>(range: T) -> u8 {
-        let v = match range.end_bound() {
+    /// The maximum used to determine the fragment for `range`, if it has a bound
+    fn max_from_range<T: RangeBounds<u64>>(range: T) -> Option<u64> {
+        Some(match range.end_bound() {
             Bound::Included(i) => *i,
-            Bound::Excluded(i) => *i - 1,
+            Bound::Excluded(i) => i.saturating_sub(1),
             Bound::Unbounded => {
                 /* try to guess based on first */
-                64 * match range.start_bound() {
+                64u64.saturating_mul(match range.start_bound() {
                     Bound::Included(i) => *i,
-                    Bound::Excluded(i) => *i + 1,
-                    Bound::Unbounded => {
-                        /* welp, lets use the default */
-                        return 16;
-                    }
-                }
+                    Bound::Excluded(i) => i.saturating_add(1),
+                    Bound::Unbounded => return None,
+                })
             }
-        };
+        })
+    }
 
-        Self::fragment_ave_from_max(v)
+    /// Check that `max` produces a fragment accepted by [`Zpaq::max_hash_from_fragment_ave()`]
+    /// (between 1 and 32)
+    fn check_max(param: &'static str, max: u64) -> Result<(), ConfigError> {
+        match Self::fragment_ave_from_max(max) {
+            1..=32 => Ok(()),
+            _ => Err(ConfigError::invalid(
+                "zpaq",
+                param,
+                max,
+                "maximum must be at least 8KiB and less than 32TiB",
+            )),
+        }
     }
 
     /* these are based on the zpaq (not go-dedup) calculations */
@@ -106,10 +154,32 @@ impl Zpaq {
      *
      * The average block size will be the max block size (if any) divided by 4, using the same
      * algorithm to calculate it as go-dedup.
+     *
+     * Panics if the average block size can't be determined from `range` (see
+     * [`Zpaq::try_with_range()`]).
      */
     pub fn with_range(range: impl RangeBounds<u64> + Clone) -> Self {
-        let f = Self::fragment_ave_from_range(range.clone());
-        Self::with_average_and_range(f, range)
+        Self::try_with_range(range).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /**
+     * Create a splitter using the range of output block sizes, or return an error if the average
+     * block size can't be determined from it.
+     *
+     * The maximum block size (or 64 times the minimum block size if there is no maximum) must be
+     * at least 8 KiB and less than 32 TiB. A range without either bound uses an average size of
+     * 2**16.
+     */
+    pub fn try_with_range(range: impl RangeBounds<u64> + Clone) -> Result<Self, ConfigError> {
+        let f = match Self::max_from_range(range.clone()) {
+            Some(max) => {
+                Self::check_max("range", max)?;
+                Self::fragment_ave_from_max(max)
+            }
+            /* welp, lets use the default */
+            None => 16,
+        };
+        Self::try_with_average_and_range(f, range)
     }
 
     /**
@@ -117,10 +187,28 @@ impl Zpaq {
      * formated as a power of 2.
      *
      * Corresponds to zpaq's argument "-fragment".
+     *
+     * Panics if `average_size_pow_2` is not between 10 and 32.
      */
     pub fn with_average_size_pow_2(average_size_pow_2: u8) -> Self {
+        Self::try_with_average_size_pow_2(average_size_pow_2).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /**
+     * Create a splitter using the defaults from Zpaq (the compressor) given a average size
+     * formated as a power of 2, which must be between 10 and 32.
+     */
+    pub fn try_with_average_size_pow_2(average_size_pow_2: u8) -> Result<Self, ConfigError> {
+        if !(10..=32).contains(&average_size_pow_2) {
+            return Err(ConfigError::invalid(
+                "zpaq",
+                "average_size_pow_2",
+                average_size_pow_2 as u64,
+                "must be between 10 and 32",
+            ));
+        }
         let r = Self::range_from_fragment_ave(average_size_pow_2);
-        Self::with_average_and_range(average_size_pow_2, r)
+        Self::try_with_average_and_range(average_size_pow_2, r)
     }
 
     /**
@@ -128,21 +216,57 @@ impl Zpaq {
      *
      * The average block size will be the max block size (if any) divided by 4, using the same
      * algorithm to calculate it as go-dedup.
+     *
+     * Panics if `max` is less than 8 KiB or not less than 32 TiB.
      */
     pub fn with_max_size(max: u64) -> Self {
-        Self::with_average_and_range(Self::fragment_ave_from_max(max), Self::range_from_max(max))
+        Self::try_with_max_size(max).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /**
+     * Use the defaults from go-dedup to generate a splitter given the max size of a split, which
+     * must be at least 8 KiB and less than 32 TiB.
+     */
+    pub fn try_with_max_size(max: u64) -> Result<Self, ConfigError> {
+        Self::check_max("max", max)?;
+        Self::try_with_average_and_range(
+            Self::fragment_ave_from_max(max),
+            Self::range_from_max(max),
+        )
     }
 
     /**
      * Create a splitter with control of all parameters
      *
      * All the other constructors use this internally
+     *
+     * Panics if `average_size_pow_2` is not between 1 and 32.
      */
     pub fn with_average_and_range(average_size_pow_2: u8, range: impl RangeBounds<u64>) -> Self {
-        Zpaq {
+        Self::try_with_average_and_range(average_size_pow_2, range)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /**
+     * Create a splitter with control of all parameters, or return an error if
+     * `average_size_pow_2` is not between 1 and 32.
+     */
+    pub fn try_with_average_and_range(
+        average_size_pow_2: u8,
+        range: impl RangeBounds<u64>,
+    ) -> Result<Self, ConfigError> {
+        if !(1..=32).contains(&average_size_pow_2) {
+            return Err(ConfigError::invalid(
+                "zpaq",
+                "average_size_pow_2",
+                average_size_pow_2 as u64,
+                "must be between 1 and 32",
+            ));
+        }
+        Ok(Zpaq {
             range: range.into_tuple(),
             max_hash: Self::max_hash_from_fragment_ave(average_size_pow_2),
-        }
+        })
     }
 
     /*
@@ -152,9 +276,130 @@ impl Zpaq {
     }
     */
 
+    /// Whether to split after the byte which produced `hash`, where `index` is the number of bytes
+    /// in the chunk including that byte (like `sz` in zpaq)
     fn split_here(&self, hash: u32, index: u64) -> bool {
         (hash < self.max_hash && !self.range.under_min(&index)) || self.range.exceeds_max(&index)
     }
+
+    /// The range as plain indexes for use with [`Zpaq::scan()`]: the first index which is not
+    /// under the minimum, and the first index which exceeds the maximum (`u64::MAX` if none).
+    fn split_bounds(&self) -> (u64, u64) {
+        let min = match self.range.0 {
+            Bound::Included(m) => m,
+            Bound::Excluded(m) => m.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
+        let max = match self.range.1 {
+            Bound::Included(m) => m.saturating_add(1),
+            Bound::Excluded(m) => m,
+            Bound::Unbounded => u64::MAX,
+        };
+        (min, max)
+    }
+
+    /// Feed bytes from `data` into `hash` until [`Zpaq::split_here()`] would be true for `idx`
+    /// (the number of bytes fed), returning the index of the last byte fed
+    ///
+    /// Equivalent to checking `split_here()` for each byte, with the range converted by
+    /// [`Zpaq::split_bounds()`] ahead of time.
+    #[inline]
+    fn scan(
+        &self,
+        hash: &mut ZpaqHash,
+        idx: &mut u64,
+        bounds: (u64, u64),
+        data: &[u8],
+    ) -> Option<usize> {
+        let (min, max) = bounds;
+        let max_hash = self.max_hash;
+        let mut n = *idx;
+        let r = data.iter().position(|&v| {
+            n += 1;
+            let h = hash.feed(v);
+            (h < max_hash && n >= min) || n >= max
+        });
+        *idx = n;
+        r
+    }
+}
+
+/// Formats as `zpaq:fragment=<average_size_pow_2>,min=<size>,max=<size>`
+///
+/// `min` is inclusive and `max` is exclusive. A bound of `none` indicates no bound. `min` and
+/// `max` are omitted if they match the range zpaq uses for the `fragment` (in other words: if
+/// [`Zpaq::with_average_size_pow_2()`] would produce the same range).
+///
+/// See [`crate::spec`] for details.
+impl fmt::Display for Zpaq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fragment = 32 - self.max_hash.trailing_zeros() as u8;
+        write!(f, "zpaq:fragment={}", fragment)?;
+
+        if (10..=32).contains(&fragment)
+            && self.range == Self::range_from_fragment_ave(fragment).into_tuple()
+        {
+            return Ok(());
+        }
+
+        match self.range.0 {
+            Bound::Included(i) => write!(f, ",min={}", SizeDisplay(i))?,
+            Bound::Excluded(i) => write!(f, ",min={}", SizeDisplay(i + 1))?,
+            Bound::Unbounded => write!(f, ",min=none")?,
+        }
+        match self.range.1 {
+            Bound::Included(i) => write!(f, ",max={}", SizeDisplay(i + 1)),
+            Bound::Excluded(i) => write!(f, ",max={}", SizeDisplay(i)),
+            Bound::Unbounded => write!(f, ",max=none"),
+        }
+    }
+}
+
+/// Parses the format emitted by [`Zpaq`]'s `Display` impl
+///
+/// `fragment` defaults to `16`. If `fragment` is between 10 and 32, `min` and `max` default to the
+/// range zpaq uses for that fragment, otherwise `fragment` must be between 1 and 32 and both `min`
+/// and `max` must be given.
+impl FromStr for Zpaq {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("zpaq", s)?;
+        let fragment = match p.take_int("fragment", 32)? {
+            None => 16,
+            Some(0) => return Err(p.invalid("fragment", "0", "must be between 1 and 32")),
+            Some(f) => f as u8,
+        };
+
+        let default_range = if (10..=32).contains(&fragment) {
+            Some(Self::range_from_fragment_ave(fragment).into_tuple())
+        } else {
+            None
+        };
+
+        let min = match (p.take("min"), default_range) {
+            (Some("none"), _) => Bound::Unbounded,
+            (Some(v), _) => match crate::spec::parse_size(v) {
+                Some(i) => Bound::Included(i),
+                None => return Err(p.invalid("min", v, "expected a byte size or `none`")),
+            },
+            (None, Some(r)) => r.0,
+            (None, None) => return Err(p.missing("min")),
+        };
+
+        let max = match (p.take("max"), default_range) {
+            (Some("none"), _) => Bound::Unbounded,
+            (Some(v), _) => match crate::spec::parse_size(v) {
+                Some(i) => Bound::Excluded(i),
+                None => return Err(p.invalid("max", v, "expected a byte size or `none`")),
+            },
+            (None, Some(r)) => r.1,
+            (None, None) => return Err(p.missing("max")),
+        };
+        p.finish()?;
+
+        Ok(Self::with_average_and_range(fragment, (min, max)))
+    }
 }
 
 impl Default for Zpaq {
@@ -172,7 +417,7 @@ impl Default for Zpaq {
 ///
 /// `Zpaq` doesn't require input look back, so the incrimental and non-incrimental performance
 /// should be similar.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ZpaqIncr {
     params: Zpaq,
     state: ZpaqHash,
@@ -207,7 +452,7 @@ impl Chunk for Zpaq {
     ) -> (Option<usize>, usize) {
         for (i, v) in data.iter().enumerate() {
             let h = state.feed(*v);
-            if self.split_here(h, (state.idx + 1) as u64) {
+            if self.split_here(h, state.idx) {
                 *state = self.to_search_state();
                 return (Some(i + 1), i + 1);
             }
@@ -215,6 +460,23 @@ impl Chunk for Zpaq {
 
         (None, data.len())
     }
+
+    fn find_chunk_edges(
+        &self,
+        state: &mut Self::SearchState,
+        data: &[u8],
+        out: &mut Vec<usize>,
+    ) -> usize {
+        let bounds = self.split_bounds();
+        let mut pos = 0;
+        while let Some(i) = self.scan(&mut state.state, &mut state.idx, bounds, &data[pos..]) {
+            *state = self.to_search_state();
+            pos += i + 1;
+            out.push(pos);
+        }
+
+        data.len()
+    }
 }
 
 impl From<&Zpaq> for ZpaqSearchState {
@@ -229,6 +491,16 @@ impl From<&Zpaq> for ZpaqIncr {
     }
 }
 
+impl CutReasons for Zpaq {
+    fn cut_reason(&self, len: u64) -> CutReason {
+        if len >= self.split_bounds().1 {
+            CutReason::MaxSize
+        } else {
+            CutReason::Hash
+        }
+    }
+}
+
 impl ToChunkIncr for Zpaq {
     type Incr = ZpaqIncr;
     fn to_chunk_incr(&self) -> Self::Incr {
@@ -241,11 +513,6 @@ impl ZpaqIncr {
         self.idx += 1;
         self.state.feed(v)
     }
-
-    fn reset(&mut self) {
-        self.idx = 0;
-        self.state = Default::default();
-    }
 }
 
 impl ChunkIncr for ZpaqIncr {
@@ -260,6 +527,24 @@ impl ChunkIncr for ZpaqIncr {
 
         None
     }
+
+    fn push_all(&mut self, data: &[u8], out: &mut Vec<usize>) {
+        let bounds = self.params.split_bounds();
+        let mut pos = 0;
+        while let Some(i) = self
+            .params
+            .scan(&mut self.state, &mut self.idx, bounds, &data[pos..])
+        {
+            self.reset();
+            pos += i + 1;
+            out.push(pos);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.idx = 0;
+        self.state = Default::default();
+    }
 }
 
 impl From<Zpaq> for ZpaqIncr {
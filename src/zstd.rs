@@ -21,21 +21,74 @@
 //!
 //! The zstd code _does_ include in it's context information about _previous_ block that was
 //! emitted. In other words: the rolling hash isn't "reset" on block emittion. (Most chunking
-//! algorithms are reset on block emittion).
+//! algorithms are reset on block emittion). [`Zstd`] does the same, so it returns `false` from
+//! [`Chunk::resets_at_edges()`]. As the hash only covers the last 32 bytes, it returns that window
+//! from [`Chunk::edge_window()`].
+use crate::config::ConfigError;
+use crate::lookback::Lookback;
+use crate::reason::{CutReason, CutReasons};
+use crate::spec::{Params, SizeDisplay, SpecError};
 use crate::{Chunk, ChunkIncr, ToChunkIncr};
 use std::convert::TryInto;
+use std::fmt;
 use std::num::Wrapping;
+use std::str::FromStr;
 
 const RSYNC_LENGTH: usize = 32;
 const PRIME_8_BYTES: Wrapping<u64> = Wrapping(0xCF1BBCDCB7A56463);
 const ROLL_HASH_CHAR_OFFSET: Wrapping<u64> = Wrapping(10);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "ZstdSerde")
+)]
 pub struct Zstd {
     hit_mask: u64,
     prime_power: u64,
 }
 
+/// Serialized form of [`Zstd`]
+///
+/// When deserialized, the fields must be ones [`Zstd::try_with_target_section_size()`] produces.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Zstd")]
+struct ZstdSerde {
+    hit_mask: u64,
+    prime_power: u64,
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<ZstdSerde> for Zstd {
+    type Error = ConfigError;
+
+    fn try_from(s: ZstdSerde) -> Result<Self, Self::Error> {
+        // `hit_mask` is one less than the target section size, rounded down to a power of 2
+        let z = Zstd::try_with_target_section_size(s.hit_mask.saturating_add(1))
+            .ok()
+            .filter(|z| z.hit_mask == s.hit_mask)
+            .ok_or_else(|| {
+                ConfigError::invalid(
+                    "zstd",
+                    "hit_mask",
+                    s.hit_mask,
+                    "must be one less than a power of 2 between 1MiB and 4PiB",
+                )
+            })?;
+        if z.prime_power != s.prime_power {
+            return Err(ConfigError::invalid(
+                "zstd",
+                "prime_power",
+                s.prime_power,
+                "must be the power used by zstd",
+            ));
+        }
+        Ok(z)
+    }
+}
+
 impl Default for Zstd {
     fn default() -> Self {
         // ../lib/compress/zstdmt_compress.c: jobSizeMB: 8, rsyncBits: 23, hitMask: 7fffff, primePower: f5507fe35f91f8cb
@@ -44,6 +97,17 @@ impl Default for Zstd {
 }
 
 impl Zstd {
+    /// Create an instance aiming for sections of `target_section_size` bytes (zstd's
+    /// `targetSectionSize`)
+    ///
+    /// # Panics
+    ///
+    /// If `target_section_size` is less than 1 MiB, or not less than 4 PiB. See
+    /// [`Zstd::try_with_target_section_size()`].
+    pub fn with_target_section_size(target_section_size: u64) -> Self {
+        Self::try_with_target_section_size(target_section_size).unwrap_or_else(|e| panic!("{}", e))
+    }
+
     /*
      * ```notrust
         /* Aim for the targetsectionSize as the average job size. */
@@ -56,18 +120,67 @@ impl Zstd {
         mtctx->rsync.primePower = ZSTD_rollingHash_primePower(RSYNC_LENGTH);
         ```
     */
-    pub fn with_target_section_size(target_section_size: u64) -> Self {
-        let job_size_mb: u32 = (target_section_size >> 20).try_into().unwrap();
-        assert_ne!(job_size_mb, 0);
+    /// Create an instance aiming for sections of `target_section_size` bytes, which must be at
+    /// least 1 MiB and less than 4 PiB
+    pub fn try_with_target_section_size(target_section_size: u64) -> Result<Self, ConfigError> {
+        let job_size_mb: u32 = match (target_section_size >> 20).try_into() {
+            Ok(0) | Err(_) => {
+                return Err(ConfigError::invalid(
+                    "zstd",
+                    "target_section_size",
+                    target_section_size,
+                    "must be at least 1MiB and less than 4PiB",
+                ))
+            }
+            Ok(v) => v,
+        };
         let rsync_bits = (job_size_mb.leading_zeros() ^ 31) + 20;
         let hit_mask = (1u64 << rsync_bits) - 1;
         let prime_power = PRIME_8_BYTES
             .0
             .wrapping_pow((RSYNC_LENGTH - 1).try_into().unwrap());
-        Self {
+        Ok(Self {
             hit_mask,
             prime_power,
-        }
+        })
+    }
+}
+
+/// Formats as `zstd:target=<size>`
+///
+/// `target` is the target section size, rounded down to a power of 2 (which doesn't change the
+/// chunking).
+///
+/// See [`crate::spec`] for details.
+impl fmt::Display for Zstd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "zstd:target={}",
+            SizeDisplay(1 << self.hit_mask.count_ones())
+        )
+    }
+}
+
+/// Parses the format emitted by [`Zstd`]'s `Display` impl
+///
+/// `target` must be at least 1 MiB and less than 4 PiB.
+impl FromStr for Zstd {
+    type Err = SpecError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut p = Params::parse("zstd", s)?;
+        let target = match p.take("target") {
+            None => 8 << 20,
+            Some(v) => match crate::spec::parse_size(v) {
+                Some(t) if t >> 20 != 0 && t >> 20 <= u32::MAX as u64 => t,
+                _ => {
+                    return Err(p.invalid("target", v, "must be at least 1MiB and less than 4PiB"))
+                }
+            },
+        };
+        p.finish()?;
+        Ok(Zstd::with_target_section_size(target))
     }
 }
 
@@ -81,7 +194,7 @@ mod test {
     }
 }
 
-#[derive(Default, Debug, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
 struct ZstdState {
     hash: Wrapping<u64>,
 }
@@ -129,29 +242,33 @@ impl ZstdSearchState {
 
 /// Incrimental chunking using Zstd's rsyncable algorithm
 ///
-/// Performance note: Zstd's chunking requires buffer look back to remove previously inserted data,
-/// and as a result requires `ZstdIncr` to maintain an internal buffer. This internal buffer may
-/// reduce performance.
-#[derive(Debug, PartialEq, Eq)]
+/// Zstd's chunking requires look back to remove previously inserted data. `ZstdIncr` reads it from
+/// the data passed to `push()` where possible, and only keeps the last `RSYNC_LENGTH` (32) bytes
+/// of each slice.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ZstdIncr {
     params: Zstd,
 
     state: ZstdState,
 
-    window: Box<[u8]>,
-    // insert into the window at this offset
-    window_offs: usize,
-    // if true, we need to remove bytes from the window when inserting
+    // once full, we need to remove bytes from the hash when inserting
     //
     // NOTE: by pre-filling `self.hash` with an appropriate value, we might be able to remove this
-    // variable and always treat the window as full (of zeros initially).
-    window_full: bool,
+    // distinction and always treat the window as full (of zeros initially).
+    lookback: Lookback,
 
     // how many byte since last emitted block
     // used to cap the block size as zstd does
     input_offs: u64,
 }
 
+impl CutReasons for Zstd {
+    /// Always [`CutReason::Hash`]: the maximum section size is not implemented
+    fn cut_reason(&self, _len: u64) -> CutReason {
+        CutReason::Hash
+    }
+}
+
 impl ToChunkIncr for Zstd {
     type Incr = ZstdIncr;
 
@@ -165,9 +282,7 @@ impl From<Zstd> for ZstdIncr {
         Self {
             params,
             state: Default::default(),
-            window: vec![0; RSYNC_LENGTH].into_boxed_slice(),
-            window_offs: 0,
-            window_full: false,
+            lookback: Lookback::new(RSYNC_LENGTH),
             input_offs: 0,
         }
     }
@@ -186,6 +301,16 @@ impl Chunk for Zstd {
         Self::SearchState::default()
     }
 
+    /// `false`: the rolling hash continues from one section into the next
+    fn resets_at_edges(&self) -> bool {
+        false
+    }
+
+    /// An edge depends only on the `RSYNC_LENGTH` (32) bytes the rolling hash covers
+    fn edge_window(&self) -> Option<usize> {
+        Some(RSYNC_LENGTH)
+    }
+
     fn find_chunk_edge(
         &self,
         state: &mut Self::SearchState,
@@ -211,8 +336,9 @@ impl Chunk for Zstd {
             let to_add = data[i];
             state.rotate(to_remove, to_add, self.prime_power);
             if state.at_split(self) {
-                let discard_ct = data.len().saturating_sub(RSYNC_LENGTH);
-                return (Some(i + 1), discard_ct);
+                // like zstd, the hash continues into the next section, so keep its window
+                state.offset = RSYNC_LENGTH;
+                return (Some(i + 1), i + 1 - RSYNC_LENGTH);
             }
         }
 
@@ -225,41 +351,30 @@ impl Chunk for Zstd {
 
 impl ChunkIncr for ZstdIncr {
     fn push(&mut self, data: &[u8]) -> Option<usize> {
-        let use_len = if !self.window_full {
-            let use_len = std::cmp::min(self.window.len() - self.window_offs, data.len());
-            self.window[self.window_offs..(self.window_offs + use_len)]
-                .copy_from_slice(&data[..use_len]);
-            self.window_offs += use_len;
-
-            if self.window_offs != self.window.len() {
-                return None;
-            }
-
-            self.window_full = true;
-            self.window_offs = 0;
-            self.state.append(&self.window[..]);
-            use_len
-        } else {
-            0
-        };
+        // fill the window before rotating data through
+        let use_len = std::cmp::min(RSYNC_LENGTH - self.lookback.seen(), data.len());
+        self.state.append(&data[..use_len]);
 
         // TODO: track input_offs to split over-size blocks
 
-        // we have a full window, now rotate data through
-        for (i, &v) in data[use_len..].iter().enumerate() {
-            let to_remove = self.window[self.window_offs];
-            let to_add = v;
-            self.state
-                .rotate(to_remove, to_add, self.params.prime_power);
-            self.window[self.window_offs] = to_add;
-            self.window_offs = (self.window_offs + 1) % self.window.len();
+        for (i, &v) in data.iter().enumerate().skip(use_len) {
+            let to_remove = self.lookback.dropped(data, i).unwrap();
+            self.state.rotate(to_remove, v, self.params.prime_power);
 
             if self.state.at_split(&self.params) {
-                // NOTE: don't clear window
-                return Some(i + use_len);
+                // like zstd, the hash continues into the next section, so keep its window
+                self.lookback.extend(&data[..i + 1]);
+                return Some(i + 1);
             }
         }
 
+        self.lookback.extend(data);
         None
     }
+
+    fn reset(&mut self) {
+        self.state = Default::default();
+        self.lookback.clear();
+        self.input_offs = 0;
+    }
 }
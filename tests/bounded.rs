@@ -0,0 +1,209 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zstd"
+))]
+
+mod common;
+
+use common::{incr_edges, low_entropy_data, pieces, test_data};
+use hash_roll::bounded::{Bounded, BoundedIncr};
+use hash_roll::{Chunk, ToChunkIncr};
+
+/// Edges found by `find_chunk_edge()`, appending each piece to a buffer of undiscarded data
+fn chunk_edges<C: Chunk>(chunker: &C, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut state = chunker.to_search_state();
+    let mut edges = Vec::new();
+    let mut buf = Vec::new();
+    let mut buf_offset = 0;
+    for piece in pieces {
+        buf.extend_from_slice(piece);
+        loop {
+            let (cut, discard_ct) = chunker.find_chunk_edge(&mut state, &buf);
+            if let Some(cut) = cut {
+                edges.push(buf_offset + cut);
+            }
+            buf.drain(..discard_ct);
+            buf_offset += discard_ct;
+            if cut.is_none() {
+                break;
+            }
+        }
+    }
+    edges
+}
+
+fn check_sizes(edges: &[usize], min: usize, max: usize) {
+    let mut prev = 0;
+    for &e in edges {
+        let l = e - prev;
+        assert!(l >= min && l <= max, "chunk {}..{} of {} bytes", prev, e, l);
+        prev = e;
+    }
+}
+
+fn data_sets() -> Vec<Vec<u8>> {
+    vec![
+        test_data(1, 1 << 18),
+        low_entropy_data(2, 1 << 18, 10_000),
+        vec![0u8; 100_000],
+    ]
+}
+
+/// Check the chunk sizes are within the bounds, and the edges are the same however the input is
+/// split
+fn chunk_test<C: Chunk>(chunker: &C, min: usize, max: usize) {
+    for data in data_sets() {
+        let expected = chunk_edges(chunker, &[&data[..]]);
+        check_sizes(&expected, min, max);
+        for &p_max in &[50, 3000, 1 << 16] {
+            let p = pieces(&data, p_max, p_max as u64);
+            assert_eq!(chunk_edges(chunker, &p), expected, "max piece: {}", p_max);
+        }
+    }
+}
+
+fn incr_test<C: ToChunkIncr>(chunker: &C, min: usize, max: usize) {
+    for data in data_sets() {
+        let expected = incr_edges(chunker.to_chunk_incr(), &[&data[..]]);
+        check_sizes(&expected, min, max);
+        for &p_max in &[50, 3000, 1 << 16] {
+            let p = pieces(&data, p_max, p_max as u64);
+            assert_eq!(
+                incr_edges(chunker.to_chunk_incr(), &p),
+                expected,
+                "max piece: {}",
+                p_max
+            );
+        }
+    }
+}
+
+fn bounded_test<C>(inner: C, window: Option<u64>)
+where
+    C: Chunk + ToChunkIncr + Clone,
+    C::Incr: Clone,
+{
+    const MIN: usize = 1000;
+    const MAX: usize = 6000;
+    let chunker = Bounded::new(inner.clone(), MIN as u64..=MAX as u64);
+    chunk_test(&chunker, MIN, MAX);
+    incr_test(&chunker, MIN, MAX);
+
+    let incr = BoundedIncr::new(inner.to_chunk_incr(), MIN as u64..=MAX as u64);
+    let data = test_data(3, 1 << 18);
+    assert_eq!(
+        incr_edges(incr, &[&data[..]]),
+        incr_edges(chunker.to_chunk_incr(), &[&data[..]])
+    );
+
+    if let Some(window) = window {
+        let skipping = Bounded::with_window(inner, MIN as u64..=MAX as u64, window);
+        chunk_test(&skipping, MIN, MAX);
+        incr_test(&skipping, MIN, MAX);
+    }
+}
+
+/// Check skipping bytes outside the window doesn't change the edges, for algorithms that rarely
+/// find edges in the window before the minimum size
+#[cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "gear",
+    feature = "pigz",
+    feature = "zstd"
+))]
+fn window_test<C>(inner: C, window: u64)
+where
+    C: Chunk + ToChunkIncr + Clone,
+    C::Incr: Clone,
+{
+    let chunker = Bounded::new(inner.clone(), 1000..=6000);
+    let skipping = Bounded::with_window(inner, 1000..=6000, window);
+    for data in data_sets() {
+        assert_eq!(
+            chunk_edges(&skipping, &[&data[..]]),
+            chunk_edges(&chunker, &[&data[..]])
+        );
+        assert_eq!(
+            incr_edges(skipping.to_chunk_incr(), &[&data[..]]),
+            incr_edges(chunker.to_chunk_incr(), &[&data[..]])
+        );
+    }
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn bounded_constant() {
+    // `Mii` never cuts in constant data
+    let data = vec![3u8; 20_000];
+    let chunker = Bounded::new(hash_roll::mii::Mii::default(), 100..4096);
+    let ranges: Vec<_> = chunker.chunk_ranges(&data).collect();
+    assert_eq!(ranges.len(), 5);
+    assert_eq!(ranges[0], 0..4095);
+    assert_eq!(ranges[4], 4095 * 4..20_000);
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn bounded_bup() {
+    bounded_test(hash_roll::bup::RollSum::default(), Some(64));
+    window_test(hash_roll::bup::RollSum::default(), 64);
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn bounded_buzhash() {
+    bounded_test(hash_roll::buzhash::BuzHash::new_nom(0), Some(67));
+    window_test(hash_roll::buzhash::BuzHash::new_nom(0), 67);
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn bounded_gear() {
+    bounded_test(hash_roll::gear::Gear32::default(), Some(32));
+    window_test(hash_roll::gear::Gear32::default(), 32);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn bounded_gzip() {
+    bounded_test(
+        hash_roll::gzip::GzipRsyncable::with_window_and_modulus(512, 256),
+        Some(512),
+    );
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn bounded_mii() {
+    bounded_test(hash_roll::mii::Mii::default(), None);
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn bounded_pigz() {
+    bounded_test(hash_roll::pigz::PigzRsyncable::default(), Some(12));
+    window_test(hash_roll::pigz::PigzRsyncable::default(), 12);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn bounded_ram() {
+    bounded_test(hash_roll::ram::Ram::with_w(512), None);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn bounded_zstd() {
+    bounded_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        Some(32),
+    );
+    window_test(hash_roll::zstd::Zstd::with_target_section_size(1 << 20), 32);
+}
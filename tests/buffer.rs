@@ -0,0 +1,192 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+
+mod common;
+
+use common::{pieces, test_data};
+use hash_roll::buffer::{ChunkBuffer, ChunkIncrFromChunk};
+use hash_roll::{Chunk, ChunkIncr, ToChunkIncr};
+
+/// Chunks of `pieces` found by `buf`, including the final chunk
+fn buffer_chunks<C: Chunk>(buf: &mut ChunkBuffer<C>, pieces: &[&[u8]]) -> Vec<Vec<u8>> {
+    let mut chunks = Vec::new();
+    for piece in pieces {
+        buf.push(piece);
+        while let Some(chunk) = buf.next_chunk() {
+            chunks.push(chunk.to_vec());
+        }
+    }
+    chunks.extend(buf.finish().map(|c| c.to_vec()));
+    assert!(buf.is_empty());
+    chunks
+}
+
+/// Edges found by pushing each of `pieces` into `incr`, as offsets in the entire input
+fn incr_edges<I: ChunkIncr>(incr: &mut I, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut edges = Vec::new();
+    let mut base = 0;
+    for piece in pieces {
+        let start = edges.len();
+        incr.push_all(piece, &mut edges);
+        for e in &mut edges[start..] {
+            *e += base;
+        }
+        base += piece.len();
+    }
+    edges
+}
+
+/// Check that a `ChunkBuffer` finds the same chunks as `chunk_slices()` however the data is
+/// split, and can be reused for another input, and that `ChunkIncrFromChunk` finds the same edges
+/// as the algorithm's own `ChunkIncr`
+fn buffer_test<C: Chunk + ToChunkIncr>(chunker: C, size: usize) {
+    let data = test_data(1, size);
+    let expected: Vec<Vec<u8>> = chunker.chunk_slices(&data).map(|c| c.to_vec()).collect();
+    assert!(expected.len() > 1);
+
+    let incr = incr_edges(&mut chunker.to_chunk_incr(), &[&data[..]]);
+    let mut adapter = ChunkIncrFromChunk::new(&chunker);
+    for (seed, &max) in [1, 7, 300, 5000, 1 << 16].iter().enumerate() {
+        let p = pieces(&data, max, seed as u64);
+        assert_eq!(
+            incr_edges(&mut adapter, &p),
+            incr,
+            "ChunkIncrFromChunk with pieces of up to {}",
+            max
+        );
+        adapter.reset();
+    }
+
+    let mut buf = ChunkBuffer::new(chunker);
+    for (seed, &max) in [1, 7, 300, 5000, 1 << 16].iter().enumerate() {
+        let p = pieces(&data, max, seed as u64);
+        assert_eq!(
+            buffer_chunks(&mut buf, &p),
+            expected,
+            "pieces of up to {}",
+            max
+        );
+    }
+    assert_eq!(buffer_chunks(&mut buf, &[&data[..]]), expected);
+    assert_eq!(buf.finish(), None);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn buffer_empty() {
+    let mut buf = ChunkBuffer::new(hash_roll::ram::Ram::with_w(64));
+    buf.push(&[]);
+    assert_eq!(buf.next_chunk(), None);
+    assert_eq!(buf.finish(), None);
+    assert!(buf.is_empty());
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn buffer_len() {
+    let chunker = hash_roll::ram::Ram::with_w(64);
+    let data = test_data(2, 1 << 16);
+    let mut buf = ChunkBuffer::with_capacity(chunker.clone(), 1024);
+    buf.push(&data);
+    assert_eq!(buf.len(), data.len());
+    let first = buf.next_chunk().unwrap().len();
+    assert_eq!(first, chunker.chunk_ranges(&data).next().unwrap().len());
+    assert_eq!(buf.len(), data.len() - first);
+    assert_eq!(buf.chunker(), &chunker);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn buffer_compacts() {
+    // chunks are much smaller than the data pushed in total, so the buffer only needs to hold a
+    // few pieces at once
+    let chunker = hash_roll::ram::RamL::with_w_and_max(64, 1000);
+    let data = test_data(3, 1 << 20);
+    let mut buf = ChunkBuffer::new(chunker);
+    let mut total = 0;
+    for piece in data.chunks(100) {
+        buf.push(piece);
+        while let Some(chunk) = buf.next_chunk() {
+            total += chunk.len();
+        }
+        assert!(buf.len() < 1000);
+    }
+    assert!(buf.capacity() < 4096);
+    total += buf.finish().map_or(0, |c| c.len());
+    assert_eq!(total, data.len());
+    assert!(format!("{:?}", buf).contains("ChunkBuffer"));
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn buffer_bup() {
+    buffer_test(hash_roll::bup::RollSum::default(), 1 << 17);
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn buffer_buzhash() {
+    buffer_test(hash_roll::buzhash::BuzHash::new_nom(0), 1 << 17);
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn buffer_fastcdc() {
+    buffer_test(hash_roll::fastcdc::FastCdc::default(), 1 << 17);
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn buffer_gear() {
+    buffer_test(hash_roll::gear::Gear32::default(), 1 << 17);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn buffer_gzip() {
+    buffer_test(hash_roll::gzip::GzipRsyncable::default(), 1 << 17);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn buffer_mii() {
+    buffer_test(hash_roll::mii::Mii::default(), 1 << 16);
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn buffer_pigz() {
+    buffer_test(hash_roll::pigz::PigzRsyncable::default(), 1 << 17);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn buffer_ram() {
+    buffer_test(hash_roll::ram::Ram::with_w(1024), 1 << 17);
+    buffer_test(hash_roll::ram::RamL::with_w_and_max(1024, 3000), 1 << 17);
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn buffer_zpaq() {
+    buffer_test(hash_roll::zpaq::Zpaq::with_average_size_pow_2(12), 1 << 17);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn buffer_zstd() {
+    buffer_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        1 << 21,
+    );
+}
@@ -0,0 +1,101 @@
+#![cfg(feature = "buzhash-n")]
+
+use hash_roll::buzhash::{BuzHash, BuzHashN, BuzHashTableByteSaltHash};
+use hash_roll::{Chunk, ChunkIncr, ToChunkIncr};
+use rand::{Rng, RngCore, SeedableRng};
+use rand_pcg::Pcg64;
+
+fn test_data(seed: u128, size: usize) -> Vec<u8> {
+    let mut fill_rng = Pcg64::new(seed, 0xa02bdbf7bb3c0a7ac28fa16a64abf96);
+    let mut buf = vec![0u8; size];
+    fill_rng.fill_bytes(&mut buf);
+    buf
+}
+
+/// Random data with repeating runs, where the window contents cancel
+fn low_entropy_data(seed: u128, size: usize) -> Vec<u8> {
+    let mut buf = test_data(seed, size);
+    for c in buf.chunks_mut(20000).skip(1) {
+        let l = std::cmp::min(10000, c.len());
+        for (i, b) in c[..l].iter_mut().enumerate() {
+            *b = (i % 3) as u8;
+        }
+    }
+    buf
+}
+
+/// Edges found by pushing `data` in randomly sized pieces
+fn incr_edges<I: ChunkIncr>(mut incr: I, data: &[u8], seed: u64) -> Vec<usize> {
+    let mut rng = Pcg64::seed_from_u64(seed);
+    let mut edges = Vec::new();
+    let mut offset = 0;
+    while offset < data.len() {
+        let l = std::cmp::min(rng.gen_range(1, 300), data.len() - offset);
+        let piece = &data[offset..offset + l];
+        let mut pos = 0;
+        while let Some(i) = incr.push(&piece[pos..]) {
+            pos += i;
+            edges.push(offset + pos);
+        }
+        offset += l;
+    }
+    edges
+}
+
+fn same_as_dynamic<const K: usize>(mask: u32, max_chunk_size: u64) {
+    let hash = || BuzHashTableByteSaltHash::from((3, &hash_roll::buzhash_table::GO_BUZHASH));
+    let fixed = BuzHashN::<K, _>::new(mask, hash(), max_chunk_size);
+    let dynamic = BuzHash::new(K, mask, hash(), max_chunk_size);
+    assert_eq!(
+        BuzHash::from(fixed.clone()).to_string(),
+        dynamic.to_string()
+    );
+
+    for (seed, data) in [test_data(0, 200_000), low_entropy_data(1, 200_000)]
+        .iter()
+        .enumerate()
+    {
+        let expected: Vec<_> = dynamic.chunk_ranges(data).collect();
+        assert!(expected.len() > 2);
+        assert_eq!(fixed.chunk_ranges(data).collect::<Vec<_>>(), expected);
+
+        let expected = incr_edges(dynamic.to_chunk_incr(), data, seed as u64);
+        assert!(expected.len() > 2);
+        assert_eq!(
+            incr_edges(fixed.to_chunk_incr(), data, seed as u64),
+            expected
+        );
+    }
+}
+
+#[test]
+fn same_as_dynamic_48() {
+    same_as_dynamic::<48>((1 << 10) - 1, 1 << 24);
+}
+
+#[test]
+fn same_as_dynamic_64() {
+    same_as_dynamic::<64>((1 << 12) - 1, 1 << 24);
+}
+
+#[test]
+fn same_as_dynamic_67() {
+    same_as_dynamic::<67>((1 << 12) - 1, 1 << 24);
+}
+
+#[test]
+fn same_as_dynamic_max_chunk_size() {
+    same_as_dynamic::<67>((1 << 14) - 1, 3000);
+}
+
+#[test]
+fn nom() {
+    let data = test_data(2, 100_000);
+    let fixed = BuzHashN::<67, _>::new_nom(5);
+    let dynamic = BuzHash::new_nom(5);
+    assert_eq!(
+        BuzHash::from(fixed.clone()).to_string(),
+        dynamic.to_string()
+    );
+    assert!(fixed.chunk_ranges(&data).eq(dynamic.chunk_ranges(&data)));
+}
@@ -0,0 +1,55 @@
+//! Helpers shared by the integration tests
+//!
+//! Each test crate includes this module and uses only some of the helpers.
+#![allow(dead_code)]
+
+use hash_roll::ChunkIncr;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_pcg::Pcg64;
+
+pub fn test_data(seed: u128, size: usize) -> Vec<u8> {
+    let mut fill_rng = Pcg64::new(seed, 0xa02bdbf7bb3c0a7ac28fa16a64abf96);
+    let mut buf = vec![0u8; size];
+    fill_rng.fill_bytes(&mut buf);
+    buf
+}
+
+/// Random data with long runs of zeros
+pub fn low_entropy_data(seed: u128, size: usize, run: usize) -> Vec<u8> {
+    let mut buf = test_data(seed, size);
+    for c in buf.chunks_mut(run * 2).skip(1) {
+        let l = std::cmp::min(run, c.len());
+        for b in &mut c[..l] {
+            *b = 0;
+        }
+    }
+    buf
+}
+
+/// Split `data` into pieces of between 0 and `max` bytes
+pub fn pieces(data: &[u8], max: usize, seed: u64) -> Vec<&[u8]> {
+    let mut rng = Pcg64::seed_from_u64(seed);
+    let mut pieces = Vec::new();
+    let mut rem = data;
+    while !rem.is_empty() {
+        let l = std::cmp::min(rng.gen_range(0, max + 1), rem.len());
+        pieces.push(&rem[..l]);
+        rem = &rem[l..];
+    }
+    pieces
+}
+
+/// Edges (as offsets in the entire input) found by calling `push()` repeatedly on each piece
+pub fn incr_edges<I: ChunkIncr>(mut incr: I, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut edges = Vec::new();
+    let mut offset = 0;
+    for piece in pieces {
+        let mut pos = 0;
+        while let Some(i) = incr.push(&piece[pos..]) {
+            pos += i;
+            edges.push(offset + pos);
+        }
+        offset += piece.len();
+    }
+    edges
+}
@@ -0,0 +1,257 @@
+use hash_roll::config::ConfigError;
+
+/// Check that `r` is an error naming `param` of `algorithm`
+#[cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+fn assert_invalid<T: std::fmt::Debug>(r: Result<T, ConfigError>, algorithm: &str, param: &str) {
+    let e = r.unwrap_err();
+    assert_eq!(e.algorithm(), algorithm, "{}", e);
+    assert_eq!(e.param(), param, "{}", e);
+    assert!(e.to_string().contains(param), "{}", e);
+}
+
+#[test]
+fn config_error_display() {
+    let e = ConfigError::InvalidValue {
+        algorithm: "zstd",
+        param: "target_section_size",
+        value: 4096,
+        reason: "must be at least 1MiB and less than 4PiB",
+    };
+    assert_eq!(
+        e.to_string(),
+        "zstd: invalid value `4096` for `target_section_size`: must be at least 1MiB and less than 4PiB"
+    );
+
+    let e = ConfigError::EmptyRange {
+        algorithm: "mii",
+        param: "range",
+    };
+    assert_eq!(
+        e.to_string(),
+        "mii: `range` must contain a chunk size other than 0"
+    );
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn config_bup() {
+    use hash_roll::bup::RollSum;
+    assert_eq!(RollSum::try_with_window(128), Ok(RollSum::with_window(128)));
+    assert!(RollSum::try_with_window(1 << 31).is_ok());
+    for &w in &[0, 63, 100, (1 << 31) + 1] {
+        assert_invalid(RollSum::try_with_window(w), "bup", "window_len");
+    }
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn config_buzhash() {
+    use hash_roll::buzhash::{BuzHash, BuzHashTableByteSaltHash};
+    let hash = || BuzHashTableByteSaltHash::from((0, &hash_roll::buzhash_table::GO_BUZHASH));
+    assert!(BuzHash::try_new(67, 0xfff, hash(), 1 << 24).is_ok());
+    assert_invalid(
+        BuzHash::try_new(0, 0xfff, hash(), 1 << 24),
+        "buzhash",
+        "capacity",
+    );
+}
+
+#[cfg(feature = "buzhash-n")]
+#[test]
+fn config_buzhash_n() {
+    use hash_roll::buzhash::{BuzHashN, BuzHashTableByteSaltHash};
+    let hash = || BuzHashTableByteSaltHash::from((0, &hash_roll::buzhash_table::GO_BUZHASH));
+    assert!(BuzHashN::<67, _>::try_new(0xfff, hash(), 1 << 24).is_ok());
+    assert_invalid(
+        BuzHashN::<0, _>::try_new(0xfff, hash(), 1 << 24),
+        "buzhash",
+        "K",
+    );
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn config_gear() {
+    use hash_roll::gear::Gear32;
+    assert!(Gear32::try_with_average_size_log2(1).is_ok());
+    assert!(Gear32::try_with_average_size_log2(31).is_ok());
+    for &bits in &[0, 32, 64] {
+        assert_invalid(
+            Gear32::try_with_average_size_log2(bits),
+            "gear",
+            "average_size_log2",
+        );
+    }
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn config_gzip() {
+    use hash_roll::gzip::GzipRsyncable;
+    assert_eq!(
+        GzipRsyncable::try_with_window_and_modulus(8192, 4096),
+        Ok(GzipRsyncable::default())
+    );
+    assert_invalid(
+        GzipRsyncable::try_with_window_and_modulus(8192, 0),
+        "gzip",
+        "modulus",
+    );
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn config_mii() {
+    use hash_roll::mii::Mii;
+    use std::ops::Bound;
+    assert!(Mii::try_with_w_and_range(5, 1..=1).is_ok());
+    assert!(Mii::try_with_w_and_range(5, ..).is_ok());
+    assert_invalid(Mii::try_with_w_and_range(5, ..0), "mii", "range");
+    assert_invalid(Mii::try_with_w_and_range(5, ..=0), "mii", "range");
+    assert_invalid(Mii::try_with_w_and_range(5, 10..10), "mii", "range");
+    assert_invalid(
+        Mii::try_with_w_and_range(5, (Bound::Excluded(u64::MAX), Bound::Unbounded)),
+        "mii",
+        "range",
+    );
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn config_pigz() {
+    use hash_roll::pigz::PigzRsyncable;
+    assert_eq!(
+        PigzRsyncable::try_with_bits(12),
+        Ok(PigzRsyncable::default())
+    );
+    for &bits in &[0, 32, 255] {
+        assert_invalid(PigzRsyncable::try_with_bits(bits), "pigz", "bits");
+    }
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn config_ram() {
+    use hash_roll::ram::RamL;
+    assert_eq!(
+        RamL::try_with_w_and_max(1024, 1),
+        Ok(RamL::with_w_and_max(1024, 1))
+    );
+    assert_invalid(RamL::try_with_w_and_max(1024, 0), "raml", "max");
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn config_zpaq() {
+    use hash_roll::zpaq::Zpaq;
+    assert_eq!(Zpaq::try_with_average_size_pow_2(16), Ok(Zpaq::default()));
+    for &f in &[0, 9, 33, 255] {
+        assert_invalid(
+            Zpaq::try_with_average_size_pow_2(f),
+            "zpaq",
+            "average_size_pow_2",
+        );
+    }
+
+    assert!(Zpaq::try_with_average_and_range(1, ..).is_ok());
+    assert!(Zpaq::try_with_average_and_range(32, ..).is_ok());
+    for &f in &[0, 33] {
+        assert_invalid(
+            Zpaq::try_with_average_and_range(f, ..),
+            "zpaq",
+            "average_size_pow_2",
+        );
+    }
+
+    assert_eq!(
+        Zpaq::try_with_max_size(1 << 20),
+        Ok(Zpaq::with_max_size(1 << 20))
+    );
+    assert!(Zpaq::try_with_max_size(8192).is_ok());
+    for &max in &[0, 8191, 1 << 45, u64::MAX] {
+        assert_invalid(Zpaq::try_with_max_size(max), "zpaq", "max");
+    }
+
+    assert_eq!(Zpaq::try_with_range(..), Ok(Zpaq::with_range(..)));
+    assert!(Zpaq::try_with_range(1024..).is_ok());
+    assert!(Zpaq::try_with_range(..1 << 20).is_ok());
+    for r in &[..0, ..8192, ..u64::MAX] {
+        assert_invalid(Zpaq::try_with_range(*r), "zpaq", "range");
+    }
+    assert_invalid(Zpaq::try_with_range(100..), "zpaq", "range");
+    assert_invalid(Zpaq::try_with_range(u64::MAX..), "zpaq", "range");
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn config_zstd() {
+    use hash_roll::zstd::Zstd;
+    assert_eq!(
+        Zstd::try_with_target_section_size(8 << 20),
+        Ok(Zstd::default())
+    );
+    assert!(Zstd::try_with_target_section_size(1 << 20).is_ok());
+    assert!(Zstd::try_with_target_section_size((1 << 52) - 1).is_ok());
+    for &t in &[0, (1 << 20) - 1, 1 << 52, u64::MAX] {
+        assert_invalid(
+            Zstd::try_with_target_section_size(t),
+            "zstd",
+            "target_section_size",
+        );
+    }
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+#[should_panic(expected = "zstd: invalid value `4096` for `target_section_size`")]
+fn config_zstd_panics() {
+    hash_roll::zstd::Zstd::with_target_section_size(4096);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn config_wrappers() {
+    use hash_roll::bounded::{Bounded, BoundedIncr};
+    use hash_roll::mii::Mii;
+    use hash_roll::runs::Runs;
+    use hash_roll::ToChunkIncr;
+    let mii = Mii::default();
+
+    assert!(Bounded::try_new(mii.clone(), 1..=1).is_ok());
+    assert_invalid(Bounded::try_new(mii.clone(), 10..10), "bounded", "range");
+    assert_invalid(
+        Bounded::try_with_window(mii.clone(), ..=0, 8),
+        "bounded",
+        "range",
+    );
+    assert_invalid(
+        BoundedIncr::try_new(mii.to_chunk_incr(), ..0),
+        "bounded",
+        "range",
+    );
+    assert_invalid(
+        BoundedIncr::try_with_window(mii.to_chunk_incr(), 10..10, 8),
+        "bounded",
+        "range",
+    );
+
+    assert!(Runs::try_new(mii.clone(), 1, 1).is_ok());
+    assert_invalid(Runs::try_new(mii.clone(), 0, 1), "runs", "min_run");
+    assert_invalid(Runs::try_new(mii, 1, 0), "runs", "run_chunk_len");
+}
+
+#[test]
+#[should_panic(expected = "bounded: `range` must contain a chunk size other than 0")]
+fn config_bounded_panics() {
+    hash_roll::bounded::Bounded::new((), 100..100);
+}
@@ -0,0 +1,160 @@
+#![cfg(all(
+    feature = "testing",
+    any(
+        feature = "bup",
+        feature = "buzhash",
+        feature = "fastcdc",
+        feature = "gear",
+        feature = "gzip",
+        feature = "mii",
+        feature = "pigz",
+        feature = "ram",
+        feature = "zpaq",
+        feature = "zstd"
+    )
+))]
+
+mod common;
+
+use common::{low_entropy_data, test_data};
+use hash_roll::conformance::check;
+use hash_roll::{Chunk, ToChunkIncr};
+
+/// Check `chunker` on random data, data with runs of zeros, and all zeros, each of `size` bytes
+fn conformance_test<C: Chunk + ToChunkIncr>(chunker: C, size: usize) {
+    check(&chunker, &test_data(1, size));
+    check(&chunker, &low_entropy_data(2, size, size / 20));
+    check(&chunker, &vec![0u8; size]);
+    check(&chunker, &[]);
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn conformance_bup() {
+    conformance_test(hash_roll::bup::RollSum::default(), 1 << 18);
+    conformance_test(hash_roll::bup::RollSum::with_window(16), 1 << 18);
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn conformance_buzhash() {
+    use hash_roll::buzhash::BuzHash;
+    conformance_test(BuzHash::new_nom(0), 1 << 18);
+    // small enough to reach the maximum chunk size
+    conformance_test(
+        "buzhash:k=16,max=5000".parse::<BuzHash<_>>().unwrap(),
+        1 << 18,
+    );
+}
+
+#[cfg(feature = "buzhash-n")]
+#[test]
+fn conformance_buzhash_n() {
+    use hash_roll::buzhash::BuzHashN;
+    conformance_test(BuzHashN::<67, _>::new_nom(0), 1 << 18);
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn conformance_fastcdc() {
+    use hash_roll::fastcdc::FastCdc;
+    conformance_test(FastCdc::default(), 1 << 18);
+    conformance_test(
+        FastCdc::new(&hash_roll::gear_table::GEAR_64, 64, 256, 1024),
+        1 << 16,
+    );
+    conformance_test(FastCdc::default().with_cut_before_match(), 1 << 18);
+    conformance_test(
+        FastCdc::new(&hash_roll::gear_table::GEAR_64, 0, 256, 1024).with_cut_before_match(),
+        1 << 16,
+    );
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn conformance_gear() {
+    use hash_roll::gear::Gear32;
+    conformance_test(Gear32::default(), 1 << 18);
+    conformance_test(Gear32::with_average_size_log2(4), 1 << 14);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn conformance_gzip() {
+    use hash_roll::gzip::GzipRsyncable;
+    conformance_test(GzipRsyncable::default(), 1 << 18);
+    conformance_test(GzipRsyncable::with_window_and_modulus(64, 100), 1 << 16);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn conformance_mii() {
+    use hash_roll::mii::Mii;
+    conformance_test(Mii::default(), 1 << 16);
+    conformance_test(Mii::with_w_and_range(4, 100..1000), 1 << 16);
+    conformance_test(Mii::with_w_and_range(5, ..=2000), 1 << 16);
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn conformance_pigz() {
+    use hash_roll::pigz::PigzRsyncable;
+    conformance_test(PigzRsyncable::default(), 1 << 18);
+    conformance_test(PigzRsyncable::with_bits(5), 1 << 12);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn conformance_ram() {
+    use hash_roll::ram::{Ram, RamL};
+    conformance_test(Ram::with_w(1024), 1 << 18);
+    conformance_test(Ram::with_w(16), 1 << 14);
+    conformance_test(RamL::with_w_and_max(1024, 3000), 1 << 18);
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn conformance_zpaq() {
+    use hash_roll::zpaq::Zpaq;
+    conformance_test(Zpaq::with_average_size_pow_2(13), 1 << 18);
+    conformance_test(Zpaq::with_average_and_range(10, 100..2000), 1 << 16);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn conformance_zstd() {
+    conformance_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        1 << 21,
+    );
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn conformance_bounded() {
+    use hash_roll::bounded::Bounded;
+    let pigz = hash_roll::pigz::PigzRsyncable::with_bits(8);
+    conformance_test(Bounded::new(pigz.clone(), 100..=400), 1 << 16);
+    conformance_test(Bounded::with_window(pigz, 100..=400, 8), 1 << 16);
+}
+
+#[cfg(all(feature = "mii", feature = "pigz"))]
+#[test]
+fn conformance_runs() {
+    use hash_roll::runs::Runs;
+    // `Runs` stays in a run across the edges it places, so `check_reset()` is skipped
+    let pigz = hash_roll::pigz::PigzRsyncable::with_bits(8);
+    let mii = hash_roll::mii::Mii::with_w_and_range(4, 50..500);
+    conformance_test(Runs::new(pigz, 64, 1000), 1 << 16);
+    conformance_test(Runs::new(mii, 32, 300), 1 << 16);
+}
+
+#[cfg(all(feature = "ram", feature = "zpaq"))]
+#[test]
+fn conformance_dyn() {
+    use hash_roll::dyn_chunker::DynChunker;
+    use hash_roll::DynChunk;
+    conformance_test("ram:w=64".parse::<DynChunker<'_>>().unwrap(), 1 << 14);
+    let boxed: Box<dyn DynChunk> = Box::new(hash_roll::zpaq::Zpaq::with_average_size_pow_2(10));
+    conformance_test(boxed, 1 << 16);
+}
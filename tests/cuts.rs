@@ -1,14 +1,22 @@
-use hash_roll::{Chunk, ChunkIncr, ToChunkIncr};
-use rand::RngCore;
-use rand_pcg::Pcg64;
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
 
-fn test_data(seed: u128, size: usize) -> Vec<u8> {
-    let mut fill_rng = Pcg64::new(seed, 0xa02bdbf7bb3c0a7ac28fa16a64abf96);
-    let mut buf = vec![0u8; size];
-    fill_rng.fill_bytes(&mut buf);
-    buf
-}
+mod common;
 
+use common::test_data;
+use hash_roll::{Chunk, ChunkIncr, ToChunkIncr};
+
+#[cfg(feature = "fastcdc")]
 fn cut_test_incr<C: ChunkIncr>(seed: u128, size: usize, chunker: C, expected_splits: &[usize]) {
     let buf = test_data(seed, size);
 
@@ -20,13 +28,12 @@ fn cut_test_incr<C: ChunkIncr>(seed: u128, size: usize, chunker: C, expected_spl
         let buf = &buf[..];
         let mut last_split = 0;
         for (i, v) in buf.iter().enumerate() {
-            match incr.push(&[*v]) {
-                Some(_split_point) => {
-                    let sp = i + 1;
-                    incr_splits.push(sp - last_split);
-                    last_split = sp;
-                }
-                None => {}
+            let mut data = &[*v][..];
+            while let Some(split_point) = incr.push(data) {
+                let sp = i + 1 - data.len() + split_point;
+                incr_splits.push(sp - last_split);
+                last_split = sp;
+                data = &data[split_point..];
             }
         }
     }
@@ -78,21 +85,42 @@ fn cut_test_sz<C: Chunk + ToChunkIncr>(
         let buf = &buf[..];
         let mut last_split = 0;
         for (i, v) in buf.iter().enumerate() {
-            match incr.push(&[*v]) {
-                Some(_split_point) => {
-                    let sp = i + 1;
-                    incr_splits.push(sp - last_split);
-                    last_split = sp;
-                }
-                None => {}
+            let mut data = &[*v][..];
+            while let Some(split_point) = incr.push(data) {
+                let sp = i + 1 - data.len() + split_point;
+                incr_splits.push(sp - last_split);
+                last_split = sp;
+                data = &data[split_point..];
             }
         }
     }
 
+    // `chunk_ranges()` includes the trailing remainder (if any) as a final chunk
+    let mut range_splits: Vec<usize> = chunker.chunk_ranges(&buf[..]).map(|r| r.len()).collect();
+    let splits_total: usize = splits.iter().sum();
+    if splits_total != buf.len() {
+        assert_eq!(range_splits.pop(), Some(buf.len() - splits_total));
+    }
+
     assert_eq!(&splits[..], &incr_splits[..]);
+    assert_eq!(&splits[..], &range_splits[..]);
     assert_eq!(expected_splits, &splits[..]);
+
+    let slices_len: usize = chunker.chunk_slices(&buf[..]).map(|s| s.len()).sum();
+    assert_eq!(slices_len, buf.len());
 }
 
+#[cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq"
+))]
 fn cut_test<C: Chunk + ToChunkIncr>(seed: u128, chunker: C, expected_splits: &[usize]) {
     cut_test_sz(seed, 8192 * 4, chunker, expected_splits)
 }
@@ -0,0 +1,175 @@
+#![cfg(any(
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "mii",
+    feature = "zpaq"
+))]
+
+mod common;
+
+use common::test_data;
+use hash_roll::digest::{ChunkDigest, HashedChunk, HashingChunker};
+use hash_roll::{ChunkIncr, ToChunkIncr};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+/// FNV-1a, as a simple digest that is always avaliable
+#[derive(Debug, Clone)]
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Fnv(0xcbf29ce484222325)
+    }
+}
+
+impl ChunkDigest for Fnv {
+    type Output = u64;
+
+    fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.0 ^= b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish_reset(&mut self) -> u64 {
+        std::mem::take(self).0
+    }
+}
+
+/// Digest each chunk found by `iter_slices()` directly
+fn expected<I: ChunkIncr, D: ChunkDigest + Clone>(
+    incr: I,
+    digest: &D,
+    data: &[u8],
+) -> Vec<HashedChunk<D::Output>> {
+    let mut offset = 0;
+    incr.iter_slices(data)
+        .map(|s| {
+            let mut d = digest.clone();
+            d.update(s);
+            let c = HashedChunk {
+                offset,
+                len: s.len() as u64,
+                digest: d.finish_reset(),
+            };
+            offset += s.len() as u64;
+            c
+        })
+        .collect()
+}
+
+/// Feed `data` to a `HashingChunker` in randomly sized pieces
+fn hashed<I: ChunkIncr, D: ChunkDigest>(
+    incr: I,
+    digest: D,
+    data: &[u8],
+    seed: u64,
+) -> Vec<HashedChunk<D::Output>> {
+    let mut rng = Pcg64::seed_from_u64(seed);
+    let mut hc = HashingChunker::new(incr, digest);
+    let mut chunks = Vec::new();
+    let mut rem = data;
+    while !rem.is_empty() {
+        let l = std::cmp::min(rng.gen_range(1, 20000), rem.len());
+        chunks.extend(hc.push_all(&rem[..l]));
+        rem = &rem[l..];
+    }
+    chunks.extend(hc.finish());
+    chunks
+}
+
+fn digest_test<C: ToChunkIncr, D: ChunkDigest + Clone>(chunker: C, digest: D)
+where
+    D::Output: PartialEq + std::fmt::Debug,
+{
+    let data = test_data(0, 1 << 18);
+    let e = expected(chunker.to_chunk_incr(), &digest, &data[..]);
+    assert!(e.len() > 1);
+    for seed in 0..4 {
+        let h = hashed(chunker.to_chunk_incr(), digest.clone(), &data[..], seed);
+        assert_eq!(e, h);
+    }
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn digest_fastcdc() {
+    digest_test(hash_roll::fastcdc::FastCdc::default(), Fnv::default());
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn digest_zpaq() {
+    digest_test(
+        hash_roll::zpaq::Zpaq::with_average_size_pow_2(13),
+        Fnv::default(),
+    );
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn digest_buzhash() {
+    digest_test(hash_roll::buzhash::BuzHash::new_nom(0), Fnv::default());
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn digest_mii() {
+    digest_test(hash_roll::mii::Mii::default(), Fnv::default());
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn digest_push() {
+    let data = test_data(1, 1 << 16);
+    let mut hc = HashingChunker::new(
+        hash_roll::fastcdc::FastCdc::default().to_chunk_incr(),
+        Fnv::default(),
+    );
+    let (i, c) = hc.push(&data[..]).unwrap();
+    assert_eq!(c.offset, 0);
+    assert_eq!(c.len, i as u64);
+
+    // dropping `push_all()`'s iterator early still consumes all the data
+    let rest = &data[i..];
+    let first = hc.push_all(rest).next().unwrap();
+    assert_eq!(first.offset, i as u64);
+    let last = hc.finish().unwrap();
+    assert_eq!(last.offset + last.len, data.len() as u64);
+}
+
+#[cfg(all(feature = "blake3", feature = "fastcdc"))]
+#[test]
+fn digest_blake3() {
+    digest_test(
+        hash_roll::fastcdc::FastCdc::default(),
+        blake3::Hasher::new(),
+    );
+}
+
+#[cfg(all(feature = "sha256", feature = "fastcdc"))]
+#[test]
+fn digest_sha256() {
+    use sha2::Digest;
+    digest_test(hash_roll::fastcdc::FastCdc::default(), sha2::Sha256::new());
+
+    let mut d = sha2::Sha256::new();
+    ChunkDigest::update(&mut d, b"abc");
+    let h = d.finish_reset();
+    assert_eq!(h[..4], [0xba, 0x78, 0x16, 0xbf]);
+}
+
+#[cfg(all(feature = "xxh3", feature = "fastcdc"))]
+#[test]
+fn digest_xxh3() {
+    digest_test(
+        hash_roll::fastcdc::FastCdc::default(),
+        xxhash_rust::xxh3::Xxh3::new(),
+    );
+
+    let mut d = xxhash_rust::xxh3::Xxh3::new();
+    ChunkDigest::update(&mut d, b"abc");
+    assert_eq!(d.finish_reset(), xxhash_rust::xxh3::xxh3_64(b"abc"));
+}
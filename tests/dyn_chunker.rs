@@ -0,0 +1,132 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+
+mod common;
+
+use common::test_data;
+use hash_roll::dyn_chunker::DynChunker;
+use hash_roll::{Chunk, ChunkIncr, DynChunk, ToChunkIncr};
+
+fn incr_lens<I: ChunkIncr>(incr: I, data: &[u8]) -> Vec<usize> {
+    incr.iter_slices(data).map(|s| s.len()).collect()
+}
+
+fn chunk_lens<C: Chunk>(chunk: &C, data: &[u8]) -> Vec<usize> {
+    chunk.chunk_ranges(data).map(|r| r.len()).collect()
+}
+
+fn dyn_test<C>(chunker: C)
+where
+    C: Chunk + ToChunkIncr + Clone + 'static,
+    DynChunker<'static>: From<C>,
+{
+    let data = test_data(0, 8192 * 8);
+
+    let expected = chunk_lens(&chunker, &data[..]);
+    let expected_incr = incr_lens(chunker.to_chunk_incr(), &data[..]);
+
+    // enum dispatch
+    let dc = DynChunker::from(chunker.clone());
+    assert_eq!(chunk_lens(&dc, &data[..]), expected);
+    assert_eq!(incr_lens(dc.to_chunk_incr(), &data[..]), expected_incr);
+    assert_eq!(dc.resets_at_edges(), chunker.resets_at_edges());
+    // a used state finds the same edge as a new one once reset
+    let mut ss = dc.to_search_state();
+    dc.find_chunk_edge(&mut ss, &data[..100]);
+    dc.reset_search_state(&mut ss);
+    assert_eq!(
+        dc.find_chunk_edge(&mut ss, &data[..]),
+        dc.find_chunk_edge(&mut dc.to_search_state(), &data[..])
+    );
+
+    // trait objects
+    let bc: Box<dyn DynChunk> = Box::new(chunker.clone());
+    assert_eq!(chunk_lens(&bc, &data[..]), expected);
+    let bi: Box<dyn ChunkIncr> = bc.to_chunk_incr();
+    assert_eq!(incr_lens(bi, &data[..]), expected_incr);
+
+    // borrowed incrimental
+    let mut incr = chunker.to_chunk_incr();
+    assert_eq!(incr_lens(&mut incr, &data[..]), expected_incr);
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn dyn_bup() {
+    dyn_test(hash_roll::bup::RollSum::default());
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn dyn_buzhash() {
+    dyn_test(hash_roll::buzhash::BuzHash::new_nom(0));
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn dyn_fastcdc() {
+    dyn_test(hash_roll::fastcdc::FastCdc::default());
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn dyn_gear() {
+    dyn_test(hash_roll::gear::Gear32::default());
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn dyn_gzip() {
+    dyn_test(hash_roll::gzip::GzipRsyncable::default());
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn dyn_mii() {
+    dyn_test(hash_roll::mii::Mii::default());
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn dyn_pigz() {
+    dyn_test(hash_roll::pigz::PigzRsyncable::default());
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn dyn_ram() {
+    dyn_test(hash_roll::ram::Ram::with_w(8192));
+    dyn_test(hash_roll::ram::RamL::with_w_and_max(4096, 10_000));
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn dyn_zpaq() {
+    dyn_test(hash_roll::zpaq::Zpaq::with_average_size_pow_2(13));
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn dyn_zstd() {
+    dyn_test(hash_roll::zstd::Zstd::default());
+}
+
+#[cfg(all(feature = "fastcdc", feature = "zpaq"))]
+#[test]
+#[should_panic(expected = "search state is from a different chunker")]
+fn dyn_mismatched_state() {
+    let a = DynChunker::from(hash_roll::fastcdc::FastCdc::default());
+    let b = DynChunker::from(hash_roll::zpaq::Zpaq::default());
+    let mut ss = a.to_search_state();
+    b.find_chunk_edge(&mut ss, &[0u8; 16]);
+}
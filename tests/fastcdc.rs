@@ -1,7 +1,7 @@
 #![cfg(feature = "fastcdc")]
 
-use hash_roll::fastcdc::FastCdcIncr;
-use hash_roll::ChunkIncr;
+use hash_roll::fastcdc::{FastCdc, FastCdcIncr};
+use hash_roll::{ChunkIncr, ToChunkIncr};
 use rand_pcg::Pcg64;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -29,8 +29,13 @@ impl quickcheck::Arbitrary for Vec8K {
     }
 }
 
+/// Like the reference, the matching byte starts the next chunk
+fn reference_incr() -> FastCdcIncr<'static> {
+    FastCdc::default().with_cut_before_match().to_chunk_incr()
+}
+
 fn oracle_1(d: Vec8K) -> bool {
-    let mut cdc = FastCdcIncr::default();
+    let mut cdc = reference_incr();
     let v1 = fast_cdc_8kb(&d.data[..]);
     let v2 = cdc.push(&d.data[..]);
 
@@ -38,7 +43,7 @@ fn oracle_1(d: Vec8K) -> bool {
 }
 
 fn oracle_1_test(data: &[u8]) {
-    let mut cdc = FastCdcIncr::default();
+    let mut cdc = reference_incr();
     let v1 = fast_cdc_8kb(&data[..]);
     let v2 = cdc.push(&data[..]).unwrap_or(0);
     assert_eq!(v1, v2);
@@ -0,0 +1,236 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+
+mod common;
+
+use common::test_data;
+use hash_roll::buffer::ChunkBuffer;
+use hash_roll::offset::{ChunkBoundary, OffsetTracking};
+use hash_roll::{Chunk, ChunkIncr, ToChunkIncr};
+
+/// Sources of various sizes (including an empty one), concatenated into a single stream
+fn sources(size: usize) -> Vec<Vec<u8>> {
+    vec![
+        test_data(1, size),
+        test_data(2, 5),
+        Vec::new(),
+        test_data(3, size / 2),
+        test_data(4, size),
+    ]
+}
+
+/// Boundaries of each source chunked separately, as offsets in the concatenated stream
+fn expected<C: Chunk>(chunker: &C, sources: &[Vec<u8>]) -> Vec<ChunkBoundary> {
+    let mut out = Vec::new();
+    let mut base = 0;
+    for s in sources {
+        out.extend(chunker.chunk_ranges(s).map(|r| ChunkBoundary {
+            start: base + r.start as u64,
+            end: base + r.end as u64,
+        }));
+        base += s.len() as u64;
+    }
+    out
+}
+
+/// Boundaries found by `ChunkIncr::push()`, forcing a boundary at the start of each source
+fn incr_boundaries<I: ChunkIncr>(mut incr: I, sources: &[Vec<u8>]) -> Vec<ChunkBoundary> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut offset = 0;
+    for s in sources {
+        let mut rem = &s[..];
+        while let Some(i) = incr.push(rem) {
+            offset += i as u64;
+            out.push(ChunkBoundary { start, end: offset });
+            start = offset;
+            rem = &rem[i..];
+        }
+        offset += rem.len() as u64;
+        incr.force_boundary();
+        if start != offset {
+            out.push(ChunkBoundary { start, end: offset });
+            start = offset;
+        }
+    }
+    out
+}
+
+/// Boundaries found by `OffsetTracking`, forcing a boundary at the start of each source
+fn offset_boundaries<I: ChunkIncr>(incr: I, sources: &[Vec<u8>]) -> Vec<ChunkBoundary> {
+    let mut ot = OffsetTracking::new(incr);
+    let mut out = Vec::new();
+    for s in sources {
+        // split each source so that edges are found across calls
+        for piece in s.chunks(1000) {
+            ot.push_all(piece, &mut out);
+        }
+        out.extend(ot.force_boundary());
+    }
+    assert_eq!(ot.finish(), None);
+    out
+}
+
+/// Boundaries found by `ChunkBuffer`, forcing a boundary at the start of each source
+fn buffer_boundaries<C: Chunk>(chunker: C, sources: &[Vec<u8>]) -> Vec<ChunkBoundary> {
+    let mut buf = ChunkBuffer::new(chunker);
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut emit = |len: usize| {
+        let end = start + len as u64;
+        out.push(ChunkBoundary { start, end });
+        start = end;
+    };
+    for s in sources {
+        for piece in s.chunks(1000) {
+            buf.push(piece);
+            while let Some(chunk) = buf.next_chunk() {
+                emit(chunk.len());
+            }
+        }
+        if let Some(chunk) = buf.force_boundary() {
+            emit(chunk.len());
+        }
+    }
+    assert_eq!(buf.finish(), None);
+    out
+}
+
+fn force_boundary_test<C: Chunk + ToChunkIncr + Clone>(chunker: C, sources: &[Vec<u8>]) {
+    let expected = expected(&chunker, sources);
+    assert!(expected.len() > sources.len());
+
+    assert_eq!(
+        incr_boundaries(chunker.to_chunk_incr(), sources),
+        expected,
+        "push()"
+    );
+    assert_eq!(
+        offset_boundaries(chunker.to_chunk_incr(), sources),
+        expected,
+        "OffsetTracking"
+    );
+    assert_eq!(buffer_boundaries(chunker, sources), expected, "ChunkBuffer");
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn force_boundary_ends_runs() {
+    // a run spanning two sources is not continued by the second one
+    let mut a = test_data(5, 1 << 15);
+    a.extend(vec![0u8; 100]);
+    let mut b = vec![0u8; 100];
+    b.extend(test_data(6, 1 << 15));
+    let runs = hash_roll::runs::Runs::new(hash_roll::mii::Mii::default(), 64, 30);
+    force_boundary_test(runs, &[a, b]);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn force_boundary_wrappers() {
+    let mii = hash_roll::mii::Mii::default();
+    force_boundary_test(
+        hash_roll::bounded::Bounded::with_window(mii.clone(), 100..=2000, 32),
+        &sources(1 << 15),
+    );
+    force_boundary_test(hash_roll::runs::Runs::new(mii, 8, 1024), &sources(1 << 15));
+}
+
+#[cfg(all(feature = "bup", feature = "mii"))]
+#[test]
+fn force_boundary_dyn() {
+    use hash_roll::dyn_chunker::DynChunker;
+    use hash_roll::DynChunk;
+
+    force_boundary_test(
+        DynChunker::from(hash_roll::bup::RollSum::default()),
+        &sources(1 << 17),
+    );
+    let boxed: Box<dyn DynChunk> = Box::new(hash_roll::mii::Mii::default());
+    let expected = expected(&boxed, &sources(1 << 15));
+    assert_eq!(
+        incr_boundaries(boxed.to_chunk_incr(), &sources(1 << 15)),
+        expected
+    );
+    assert_eq!(buffer_boundaries(boxed, &sources(1 << 15)), expected);
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn force_boundary_bup() {
+    force_boundary_test(hash_roll::bup::RollSum::default(), &sources(1 << 17));
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn force_boundary_buzhash() {
+    force_boundary_test(hash_roll::buzhash::BuzHash::new_nom(0), &sources(1 << 17));
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn force_boundary_fastcdc() {
+    force_boundary_test(hash_roll::fastcdc::FastCdc::default(), &sources(1 << 17));
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn force_boundary_gear() {
+    force_boundary_test(hash_roll::gear::Gear32::default(), &sources(1 << 17));
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn force_boundary_gzip() {
+    force_boundary_test(hash_roll::gzip::GzipRsyncable::default(), &sources(1 << 17));
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn force_boundary_mii() {
+    force_boundary_test(hash_roll::mii::Mii::default(), &sources(1 << 15));
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn force_boundary_pigz() {
+    force_boundary_test(hash_roll::pigz::PigzRsyncable::default(), &sources(1 << 17));
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn force_boundary_ram() {
+    force_boundary_test(hash_roll::ram::Ram::with_w(1024), &sources(1 << 17));
+    force_boundary_test(
+        hash_roll::ram::RamL::with_w_and_max(1024, 3000),
+        &sources(1 << 17),
+    );
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn force_boundary_zpaq() {
+    force_boundary_test(
+        hash_roll::zpaq::Zpaq::with_average_size_pow_2(12),
+        &sources(1 << 17),
+    );
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn force_boundary_zstd() {
+    force_boundary_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        &sources(1 << 21),
+    );
+}
@@ -0,0 +1,53 @@
+#![cfg(any(feature = "fastcdc", feature = "gear"))]
+
+//! Gear32 and FastCdc search for edges several bytes at a time using SIMD when a slice is long
+//! enough to fill the hash window. Passing a single byte at a time to `find_chunk_edge()` only
+//! uses the scalar implementation, so we compare against that.
+mod common;
+
+use common::{low_entropy_data, test_data};
+use hash_roll::Chunk;
+
+fn scalar_edges<C: Chunk>(chunker: &C, data: &[u8]) -> Vec<usize> {
+    let mut state = chunker.to_search_state();
+    let mut edges = Vec::new();
+    let mut discard = 0;
+    while discard < data.len() {
+        let (edge, discard_ct) = chunker.find_chunk_edge(&mut state, &data[discard..=discard]);
+        if let Some(edge) = edge {
+            edges.push(discard + edge);
+        }
+        discard += discard_ct;
+    }
+    edges
+}
+
+fn simd_test<C: Chunk>(chunker: C) {
+    for data in &[test_data(1, 1 << 18), low_entropy_data(2, 1 << 18, 20000)] {
+        let expected = scalar_edges(&chunker, data);
+        assert!(expected.len() > 2);
+
+        let edges: Vec<usize> = chunker.chunk_ranges(data).map(|r| r.end).collect();
+        assert_eq!(&edges[..expected.len()], &expected[..]);
+        assert!(edges.len() == expected.len() || edges.len() == expected.len() + 1);
+    }
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn gear_simd_matches_scalar() {
+    for &bits in &[4, 9, 13] {
+        simd_test(hash_roll::gear::Gear32::with_average_size_log2(bits));
+    }
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn fastcdc_simd_matches_scalar() {
+    use hash_roll::fastcdc::FastCdc;
+    let gear = &hash_roll::gear_table::GEAR_64;
+    simd_test(FastCdc::default());
+    simd_test(FastCdc::new(gear, 64, 4096, 8192));
+    simd_test(FastCdc::new(gear, 0, 100, 30000));
+    simd_test(FastCdc::new(gear, 1000, 1000, 1500));
+}
@@ -0,0 +1,107 @@
+#![cfg(feature = "mii")]
+
+mod common;
+
+use common::test_data;
+use hash_roll::mii::Mii;
+use hash_roll::{Chunk, ChunkIncr, ToChunkIncr};
+
+/// Lengths of all chunks but the last (which is cut short by the end of the data)
+fn complete_lens<C: Chunk>(chunker: &C, data: &[u8]) -> Vec<usize> {
+    let mut lens: Vec<_> = chunker.chunk_ranges(data).map(|r| r.len()).collect();
+    lens.pop();
+    lens
+}
+
+fn mean(lens: &[usize]) -> f64 {
+    lens.iter().sum::<usize>() as f64 / lens.len() as f64
+}
+
+#[test]
+fn mii_expected_size() {
+    let data = test_data(1, 16 << 20);
+    for w in 1..=5 {
+        let chunker = Mii::with_w(w);
+        let expected = chunker.expected_chunk_size();
+        let actual = mean(&complete_lens(&chunker, &data));
+        assert!(
+            (actual - expected).abs() < expected * 0.02,
+            "w: {}, expected: {}, actual: {}",
+            w,
+            expected,
+            actual
+        );
+    }
+
+    let chunker = Mii::with_w_and_range(4, 1000..);
+    let expected = chunker.expected_chunk_size();
+    assert!((expected - (999.0 + Mii::with_w(4).expected_chunk_size())).abs() < 1e-6);
+    let actual = mean(&complete_lens(&chunker, &data));
+    assert!((actual - expected).abs() < expected * 0.02);
+
+    assert_eq!(Mii::with_w(0).expected_chunk_size(), f64::INFINITY);
+    assert_eq!(Mii::with_w(256).expected_chunk_size(), f64::INFINITY);
+    assert!(Mii::with_w(20).expected_chunk_size() > 1e18);
+}
+
+#[test]
+fn mii_range() {
+    let (min, max) = (1000, 4000);
+    let chunker = Mii::with_w_and_range(5, min as u64..=max as u64);
+    let mut monotonic = test_data(2, 1 << 20);
+    monotonic.extend((0..1 << 20).map(|i| (i >> 10) as u8));
+    monotonic.resize(3 << 20, 7);
+
+    for data in &[test_data(3, 1 << 20), monotonic] {
+        let lens = complete_lens(&chunker, data);
+        assert!(lens.iter().all(|&l| l >= min && l <= max));
+        assert!(lens.contains(&max));
+
+        // the same chunks are found incrementally, in any size of piece
+        let mut expected: Vec<_> = chunker.chunk_ranges(data).map(|r| r.len()).collect();
+        expected.pop();
+        for &piece_len in &[1, 700, 5000] {
+            let mut incr = chunker.to_chunk_incr();
+            let mut lens = Vec::new();
+            let mut len = 0;
+            for piece in data.chunks(piece_len) {
+                let mut piece = piece;
+                while let Some(i) = incr.push(piece) {
+                    lens.push(len + i);
+                    len = 0;
+                    piece = &piece[i..];
+                }
+                len += piece.len();
+            }
+            assert_eq!(lens, expected, "piece length: {}", piece_len);
+        }
+    }
+
+    // without a range, constant data is never split
+    assert_eq!(Mii::default().chunk_ranges(&[7u8; 100_000]).count(), 1);
+}
+
+#[test]
+fn mii_min_skips_increments() {
+    // the increasing bytes at the start are inside the minimum, so can't end the chunk
+    let mut data: Vec<u8> = (0..100).collect();
+    data.resize(200, 0);
+    data.extend(0..10);
+    let lens: Vec<_> = Mii::with_w_and_range(5, 150..)
+        .chunk_ranges(&data)
+        .map(|r| r.len())
+        .collect();
+    assert_eq!(lens, [206, 4]);
+
+    let lens: Vec<_> = Mii::with_w(5)
+        .chunk_ranges(&data)
+        .map(|r| r.len())
+        .collect();
+    assert_eq!(lens[0], 6);
+}
+
+#[test]
+#[should_panic]
+fn mii_empty_range() {
+    Mii::with_w_and_range(5, 10..10);
+}
@@ -0,0 +1,157 @@
+#![cfg(any(
+    feature = "gear",
+    feature = "ram",
+    all(feature = "bup", feature = "mii", feature = "zpaq")
+))]
+
+mod common;
+
+#[cfg(any(
+    feature = "gear",
+    all(feature = "bup", feature = "mii", feature = "zpaq")
+))]
+use common::pieces;
+use common::test_data;
+use hash_roll::multi::{MultiChunker, MultiIncr};
+use hash_roll::offset::ChunkBoundary;
+#[cfg(any(
+    feature = "gear",
+    all(feature = "bup", feature = "mii", feature = "zpaq")
+))]
+use hash_roll::Chunk;
+#[cfg(any(
+    feature = "ram",
+    all(
+        feature = "mii",
+        feature = "zpaq",
+        any(feature = "bup", feature = "gear")
+    )
+))]
+use hash_roll::ToChunkIncr;
+
+/// Boundaries of each chunk found by `chunk_ranges()`
+#[cfg(any(
+    feature = "gear",
+    all(feature = "bup", feature = "mii", feature = "zpaq")
+))]
+fn expected<C: Chunk>(chunker: &C, data: &[u8]) -> Vec<ChunkBoundary> {
+    chunker
+        .chunk_ranges(data)
+        .map(|r| ChunkBoundary {
+            start: r.start as u64,
+            end: r.end as u64,
+        })
+        .collect()
+}
+
+/// Boundaries found by each configuration of `multi`, pushing each of `pieces` in turn
+fn multi_boundaries<M: MultiIncr>(multi: M, pieces: &[&[u8]]) -> Vec<Vec<ChunkBoundary>> {
+    let mut mc = MultiChunker::new(multi);
+    let mut out = vec![Vec::new(); mc.configs()];
+    for piece in pieces {
+        mc.push(piece, &mut out);
+    }
+    let total: usize = pieces.iter().map(|p| p.len()).sum();
+    assert_eq!(mc.offset(), total as u64);
+    mc.finish(&mut out);
+    out
+}
+
+#[cfg(all(feature = "bup", feature = "mii", feature = "zpaq"))]
+#[test]
+fn multi_boxed() {
+    let data = test_data(1, 1 << 18);
+    let bup = hash_roll::bup::RollSum::default();
+    let mii = hash_roll::mii::Mii::default();
+    let zpaq = hash_roll::zpaq::Zpaq::with_average_size_pow_2(12);
+    let expected = vec![
+        expected(&bup, &data),
+        expected(&mii, &data),
+        expected(&zpaq, &data),
+    ];
+
+    for (seed, &max) in [7, 5000, 1 << 17].iter().enumerate() {
+        let configs: Vec<Box<dyn hash_roll::ChunkIncr>> = vec![
+            Box::new(bup.to_chunk_incr()),
+            Box::new(mii.to_chunk_incr()),
+            Box::new(zpaq.to_chunk_incr()),
+        ];
+        let p = pieces(&data, max, seed as u64);
+        assert_eq!(
+            multi_boundaries(configs, &p),
+            expected,
+            "pieces of up to {}",
+            max
+        );
+    }
+}
+
+#[cfg(all(feature = "gear", feature = "mii", feature = "zpaq"))]
+#[test]
+fn multi_tuple() {
+    use hash_roll::gear::{Gear32, GearMultiIncr32};
+
+    let data = test_data(2, 1 << 18);
+    let gears: Vec<_> = (10..14).map(Gear32::with_average_size_log2).collect();
+    let mii = hash_roll::mii::Mii::default();
+    let zpaq = hash_roll::zpaq::Zpaq::with_average_size_pow_2(12);
+    let mut expected_all: Vec<_> = gears.iter().map(|g| expected(g, &data)).collect();
+    expected_all.push(expected(&mii, &data));
+    expected_all.push(expected(&zpaq, &data));
+
+    let multi = (
+        GearMultiIncr32::new(&gears),
+        vec![mii.to_chunk_incr()],
+        zpaq.to_chunk_incr(),
+    );
+    assert_eq!(multi.configs(), 6);
+    assert_eq!(
+        multi_boundaries(multi, &pieces(&data, 5000, 1)),
+        expected_all
+    );
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn multi_gear() {
+    use hash_roll::gear::{Gear32, GearMultiIncr32};
+
+    let data = test_data(3, 1 << 19);
+    let mut gears: Vec<_> = (4..16).map(Gear32::with_average_size_log2).collect();
+    gears.push(Gear32::default());
+    // the same configuration twice
+    gears.push(Gear32::with_average_size_log2(6));
+    let expected: Vec<_> = gears.iter().map(|g| expected(g, &data)).collect();
+
+    for (seed, &max) in [1, 7, 300, 5000, 1 << 17].iter().enumerate() {
+        let p = pieces(&data, max, seed as u64);
+        assert_eq!(
+            multi_boundaries(GearMultiIncr32::new(&gears), &p),
+            expected,
+            "pieces of up to {}",
+            max
+        );
+    }
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn multi_empty() {
+    let none: Vec<Box<dyn hash_roll::ChunkIncr>> = Vec::new();
+    assert_eq!(
+        multi_boundaries(none, &[&test_data(4, 1000)]),
+        Vec::<Vec<_>>::new()
+    );
+
+    let ram = hash_roll::ram::Ram::with_w(64).to_chunk_incr();
+    assert_eq!(multi_boundaries(ram, &[&[], &[]]), vec![vec![]]);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+#[should_panic(expected = "one output per configuration")]
+fn multi_outputs() {
+    let ram = hash_roll::ram::Ram::with_w(64);
+    let mut mc = MultiChunker::new(vec![ram.to_chunk_incr(), ram.to_chunk_incr()]);
+    mc.push(&[1, 2, 3], &mut [Vec::new()]);
+}
@@ -0,0 +1,151 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+
+mod common;
+
+use common::{pieces, test_data};
+use hash_roll::offset::{ChunkBoundary, OffsetTracking};
+use hash_roll::{Chunk, ToChunkIncr};
+
+/// Boundaries of each chunk found by `chunk_ranges()`
+fn expected<C: Chunk>(chunker: &C, data: &[u8]) -> Vec<ChunkBoundary> {
+    chunker
+        .chunk_ranges(data)
+        .map(|r| ChunkBoundary {
+            start: r.start as u64,
+            end: r.end as u64,
+        })
+        .collect()
+}
+
+/// Boundaries found by `OffsetTracking::push()`
+fn push_boundaries<C: ToChunkIncr>(chunker: &C, pieces: &[&[u8]]) -> Vec<ChunkBoundary> {
+    let mut ot = OffsetTracking::new(chunker.to_chunk_incr());
+    let mut out = Vec::new();
+    for piece in pieces {
+        let mut rem = *piece;
+        while let Some((i, b)) = ot.push(rem) {
+            out.push(b);
+            rem = &rem[i..];
+        }
+    }
+    out.extend(ot.finish());
+    out
+}
+
+/// Boundaries found by `OffsetTracking::push_all()`
+fn push_all_boundaries<C: ToChunkIncr>(chunker: &C, pieces: &[&[u8]]) -> Vec<ChunkBoundary> {
+    let mut ot = OffsetTracking::new(chunker.to_chunk_incr());
+    let mut out = Vec::new();
+    for piece in pieces {
+        ot.push_all(piece, &mut out);
+    }
+    let total: usize = pieces.iter().map(|p| p.len()).sum();
+    assert_eq!(ot.offset(), total as u64);
+    out.extend(ot.finish());
+    out
+}
+
+fn offset_test<C: Chunk + ToChunkIncr>(chunker: C, size: usize) {
+    let data = test_data(1, size);
+    let expected = expected(&chunker, &data);
+    assert!(expected.len() > 1);
+
+    for (seed, &max) in [1, 7, 300, 5000, 1 << 16].iter().enumerate() {
+        let p = pieces(&data, max, seed as u64);
+        assert_eq!(
+            push_boundaries(&chunker, &p),
+            expected,
+            "push() with pieces of up to {}",
+            max
+        );
+        assert_eq!(
+            push_all_boundaries(&chunker, &p),
+            expected,
+            "push_all() with pieces of up to {}",
+            max
+        );
+    }
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn offset_empty() {
+    let chunker = hash_roll::ram::Ram::with_w(64);
+    assert_eq!(push_boundaries(&chunker, &[]), []);
+    assert_eq!(push_all_boundaries(&chunker, &[&[], &[]]), []);
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn offset_bup() {
+    offset_test(hash_roll::bup::RollSum::default(), 1 << 17);
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn offset_buzhash() {
+    offset_test(hash_roll::buzhash::BuzHash::new_nom(0), 1 << 17);
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn offset_fastcdc() {
+    offset_test(hash_roll::fastcdc::FastCdc::default(), 1 << 17);
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn offset_gear() {
+    offset_test(hash_roll::gear::Gear32::default(), 1 << 17);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn offset_gzip() {
+    offset_test(hash_roll::gzip::GzipRsyncable::default(), 1 << 17);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn offset_mii() {
+    offset_test(hash_roll::mii::Mii::default(), 1 << 16);
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn offset_pigz() {
+    offset_test(hash_roll::pigz::PigzRsyncable::default(), 1 << 17);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn offset_ram() {
+    offset_test(hash_roll::ram::Ram::with_w(1024), 1 << 17);
+    offset_test(hash_roll::ram::RamL::with_w_and_max(1024, 3000), 1 << 17);
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn offset_zpaq() {
+    offset_test(hash_roll::zpaq::Zpaq::with_average_size_pow_2(12), 1 << 17);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn offset_zstd() {
+    offset_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        1 << 21,
+    );
+}
@@ -0,0 +1,119 @@
+#![cfg(all(
+    feature = "rayon",
+    any(
+        feature = "bup",
+        feature = "buzhash",
+        feature = "fastcdc",
+        feature = "gear",
+        feature = "gzip",
+        feature = "mii",
+        feature = "pigz",
+        feature = "ram",
+        feature = "zpaq",
+        feature = "zstd"
+    )
+))]
+
+mod common;
+
+use common::{low_entropy_data, test_data};
+use hash_roll::par::{par_chunk_ranges, par_chunk_ranges_with_segment_len};
+use hash_roll::Chunk;
+
+/// Check `par_chunk_ranges()` against `chunk_ranges()` using segments around `avg`, the
+/// approximate average chunk size
+fn par_test<C: Chunk + Sync>(chunker: C, avg: usize) {
+    let size = avg * 24;
+    for data in &[test_data(0, size), low_entropy_data(1, size, avg * 3)] {
+        let expected: Vec<_> = chunker.chunk_ranges(&data[..]).collect();
+        assert!(expected.len() > 1);
+
+        for &segment_len in &[avg / 3 + 1, avg * 2 + 7, avg * 10, size] {
+            let got = par_chunk_ranges_with_segment_len(&chunker, &data[..], segment_len);
+            assert_eq!(got, expected, "segment_len: {}", segment_len);
+        }
+
+        assert_eq!(par_chunk_ranges(&chunker, &data[..]), expected);
+        let short = &data[..avg / 2];
+        let expected: Vec<_> = chunker.chunk_ranges(short).collect();
+        assert_eq!(par_chunk_ranges(&chunker, short), expected);
+    }
+
+    assert!(par_chunk_ranges(&chunker, &[]).is_empty());
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn par_bup() {
+    par_test(hash_roll::bup::RollSum::default(), 8192);
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn par_buzhash() {
+    par_test(hash_roll::buzhash::BuzHash::new_nom(0), 4096);
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn par_fastcdc() {
+    par_test(hash_roll::fastcdc::FastCdc::default(), 8192);
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn par_gear() {
+    par_test(hash_roll::gear::Gear32::default(), 8192);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn par_gzip() {
+    par_test(hash_roll::gzip::GzipRsyncable::default(), 4096);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn par_mii() {
+    par_test(hash_roll::mii::Mii::default(), 1024);
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn par_pigz() {
+    par_test(hash_roll::pigz::PigzRsyncable::default(), 4096);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn par_ram() {
+    par_test(hash_roll::ram::Ram::with_w(8192), 8192);
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn par_zpaq() {
+    par_test(hash_roll::zpaq::Zpaq::default(), 65536);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn par_zstd() {
+    // continues its hash across edges, but each segment is chunked starting 32 bytes early
+    let zstd = hash_roll::zstd::Zstd::default();
+    assert!(!zstd.resets_at_edges());
+    assert_eq!(zstd.edge_window(), Some(32));
+    par_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        1 << 20,
+    );
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn par_runs() {
+    // stays in a run across the edges it places, so is chunked sequentially
+    let runs = hash_roll::runs::Runs::new(hash_roll::pigz::PigzRsyncable::default(), 64, 1000);
+    assert!(!runs.resets_at_edges());
+    par_test(runs, 4096);
+}
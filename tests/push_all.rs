@@ -0,0 +1,180 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+
+mod common;
+
+use common::{incr_edges, low_entropy_data, pieces, test_data};
+use hash_roll::{Chunk, ChunkIncr, ToChunkIncr};
+
+/// Edges (as offsets in the entire input) found by `push_all()` on each piece
+fn incr_edges_all<I: ChunkIncr>(mut incr: I, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut edges = Vec::new();
+    let mut offset = 0;
+    let mut out = Vec::new();
+    for piece in pieces {
+        out.clear();
+        incr.push_all(piece, &mut out);
+        edges.extend(out.iter().map(|e| offset + e));
+        offset += piece.len();
+    }
+    edges
+}
+
+/// Edges found by calling `find_chunk_edge()` repeatedly on the entire input
+fn chunk_edges<C: Chunk>(chunker: &C, data: &[u8]) -> Vec<usize> {
+    let mut state = chunker.to_search_state();
+    let mut edges = Vec::new();
+    let mut discard = 0;
+    while let (Some(cut), discard_ct) = chunker.find_chunk_edge(&mut state, &data[discard..]) {
+        edges.push(discard + cut);
+        discard += discard_ct;
+    }
+    edges
+}
+
+/// Edges found by `find_chunk_edges()`, appending each piece to a buffer of undiscarded data
+fn chunk_edges_all<C: Chunk>(chunker: &C, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut state = chunker.to_search_state();
+    let mut edges = Vec::new();
+    let mut buf = Vec::new();
+    // offset in the entire input of `buf[0]`
+    let mut buf_offset = 0;
+    for piece in pieces {
+        buf.extend_from_slice(piece);
+        let mut out = vec![usize::MAX];
+        let discard_ct = chunker.find_chunk_edges(&mut state, &buf, &mut out);
+        assert_eq!(out[0], usize::MAX, "existing elements must be preserved");
+        edges.extend(out[1..].iter().map(|e| buf_offset + e));
+        buf.drain(..discard_ct);
+        buf_offset += discard_ct;
+    }
+    edges
+}
+
+fn push_all_test<C: Chunk + ToChunkIncr>(chunker: C, avg: usize) {
+    find_chunk_edges_test(&chunker, avg);
+
+    let size = avg * 16;
+    for (seed, data) in [test_data(2, size), low_entropy_data(3, size, avg * 3)]
+        .iter()
+        .enumerate()
+    {
+        for &max in &[avg / 4 + 2, avg * 3] {
+            let p = pieces(&data[..], max, seed as u64);
+            let single = incr_edges(chunker.to_chunk_incr(), &p);
+            assert!(single.len() > 2);
+            assert_eq!(incr_edges_all(chunker.to_chunk_incr(), &p), single);
+            let mut dyn_incr: Box<dyn ChunkIncr> = Box::new(chunker.to_chunk_incr());
+            assert_eq!(incr_edges_all(&mut dyn_incr, &p), single);
+        }
+    }
+}
+
+fn find_chunk_edges_test<C: Chunk>(chunker: &C, avg: usize) {
+    let size = avg * 16;
+    for (seed, data) in [test_data(2, size), low_entropy_data(3, size, avg * 3)]
+        .iter()
+        .enumerate()
+    {
+        let expected = chunk_edges(chunker, &data[..]);
+        assert!(expected.len() > 2);
+
+        let mut state = chunker.to_search_state();
+        let mut whole = Vec::new();
+        chunker.find_chunk_edges(&mut state, &data[..], &mut whole);
+        assert_eq!(whole, expected);
+
+        for &max in &[avg / 4 + 2, avg * 3] {
+            let p = pieces(&data[..], max, seed as u64);
+            assert_eq!(chunk_edges_all(chunker, &p), expected, "max piece: {}", max);
+        }
+    }
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn push_all_bup() {
+    push_all_test(hash_roll::bup::RollSum::default(), 8192);
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn push_all_buzhash() {
+    push_all_test(hash_roll::buzhash::BuzHash::new_nom(0), 4096);
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn push_all_fastcdc() {
+    push_all_test(hash_roll::fastcdc::FastCdc::default(), 8192);
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn push_all_gear() {
+    push_all_test(hash_roll::gear::Gear32::default(), 8192);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn push_all_gzip() {
+    push_all_test(hash_roll::gzip::GzipRsyncable::default(), 4096);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn push_all_mii() {
+    push_all_test(hash_roll::mii::Mii::default(), 1024);
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn push_all_pigz() {
+    push_all_test(hash_roll::pigz::PigzRsyncable::default(), 4096);
+    push_all_test(hash_roll::pigz::PigzRsyncable::with_bits(5), 32);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn push_all_ram() {
+    push_all_test(hash_roll::ram::Ram::with_w(8192), 8192);
+    push_all_test(hash_roll::ram::Ram::with_w(16), 32);
+    push_all_test(hash_roll::ram::RamL::with_w_and_max(4096, 10_000), 8192);
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn push_all_zpaq() {
+    push_all_test(hash_roll::zpaq::Zpaq::default(), 65536);
+    push_all_test(hash_roll::zpaq::Zpaq::with_average_size_pow_2(10), 1024);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn push_all_zstd() {
+    push_all_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        1 << 20,
+    );
+}
+
+#[cfg(all(feature = "ram", feature = "zpaq"))]
+#[test]
+fn push_all_dyn() {
+    use hash_roll::dyn_chunker::DynChunker;
+    use hash_roll::DynChunk;
+
+    push_all_test("ram:w=64".parse::<DynChunker<'_>>().unwrap(), 64);
+    let boxed: Box<dyn DynChunk> = Box::new(hash_roll::zpaq::Zpaq::with_average_size_pow_2(10));
+    push_all_test(boxed, 1024);
+}
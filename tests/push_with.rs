@@ -0,0 +1,142 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+
+mod common;
+
+use common::{pieces, test_data};
+use hash_roll::{Chunk, ChunkIncr, Fragment, ToChunkIncr};
+
+/// Chunks assembled from the fragments passed by `push_with()`, including the final chunk
+fn push_with_chunks<I: ChunkIncr>(mut incr: I, pieces: &[&[u8]]) -> Vec<Vec<u8>> {
+    let mut chunks = vec![Vec::new()];
+    for piece in pieces {
+        let mut end = piece.as_ptr();
+        incr.push_with(piece, |Fragment { bytes, ends_chunk }| {
+            // fragments are consecutive pieces of the data pushed
+            assert_eq!(bytes.as_ptr(), end);
+            end = bytes.as_ptr().wrapping_add(bytes.len());
+            assert!(!bytes.is_empty() || ends_chunk);
+
+            chunks.last_mut().unwrap().extend_from_slice(bytes);
+            if ends_chunk {
+                chunks.push(Vec::new());
+            }
+        });
+        assert_eq!(end, piece.as_ptr().wrapping_add(piece.len()));
+    }
+    if chunks.last().unwrap().is_empty() {
+        chunks.pop();
+    }
+    chunks
+}
+
+fn push_with_test<C: Chunk + ToChunkIncr>(chunker: C, size: usize) {
+    let data = test_data(1, size);
+    let expected: Vec<Vec<u8>> = chunker.chunk_slices(&data).map(|c| c.to_vec()).collect();
+    assert!(expected.len() > 1);
+
+    for (seed, &max) in [1, 7, 300, 5000, 1 << 16].iter().enumerate() {
+        let p = pieces(&data, max, seed as u64);
+        assert_eq!(
+            push_with_chunks(chunker.to_chunk_incr(), &p),
+            expected,
+            "pieces of up to {}",
+            max
+        );
+    }
+    assert_eq!(
+        push_with_chunks(chunker.to_chunk_incr(), &[&data[..]]),
+        expected
+    );
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn push_with_empty() {
+    let mut incr = hash_roll::ram::Ram::with_w(64).to_chunk_incr();
+    incr.push_with(&[], |f| panic!("unexpected fragment {:?}", f));
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn push_with_dyn() {
+    let mut incr: Box<dyn ChunkIncr> = Box::new(hash_roll::mii::Mii::default().to_chunk_incr());
+    let data = test_data(2, 1 << 16);
+    let mut total = 0;
+    incr.push_with(&data, |f| total += f.bytes.len());
+    assert_eq!(total, data.len());
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn push_with_bup() {
+    push_with_test(hash_roll::bup::RollSum::default(), 1 << 17);
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn push_with_buzhash() {
+    push_with_test(hash_roll::buzhash::BuzHash::new_nom(0), 1 << 17);
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn push_with_fastcdc() {
+    push_with_test(hash_roll::fastcdc::FastCdc::default(), 1 << 17);
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn push_with_gear() {
+    push_with_test(hash_roll::gear::Gear32::default(), 1 << 17);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn push_with_gzip() {
+    push_with_test(hash_roll::gzip::GzipRsyncable::default(), 1 << 17);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn push_with_mii() {
+    push_with_test(hash_roll::mii::Mii::default(), 1 << 16);
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn push_with_pigz() {
+    push_with_test(hash_roll::pigz::PigzRsyncable::default(), 1 << 17);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn push_with_ram() {
+    push_with_test(hash_roll::ram::Ram::with_w(1024), 1 << 17);
+    push_with_test(hash_roll::ram::RamL::with_w_and_max(1024, 3000), 1 << 17);
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn push_with_zpaq() {
+    push_with_test(hash_roll::zpaq::Zpaq::with_average_size_pow_2(12), 1 << 17);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn push_with_zstd() {
+    push_with_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        1 << 21,
+    );
+}
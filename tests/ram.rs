@@ -0,0 +1,117 @@
+#![cfg(feature = "ram")]
+
+mod common;
+
+use common::{low_entropy_data, test_data};
+use hash_roll::ram::{Ram, RamL};
+use hash_roll::{Chunk, ChunkIncr, ToChunkIncr};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+fn chunk_lens<C: Chunk>(chunker: &C, data: &[u8]) -> Vec<usize> {
+    chunker.chunk_ranges(data).map(|r| r.len()).collect()
+}
+
+/// Lengths of all chunks but the last (which is cut short by the end of the data)
+fn complete_lens<C: Chunk>(chunker: &C, data: &[u8]) -> Vec<usize> {
+    let mut lens = chunk_lens(chunker, data);
+    lens.pop();
+    lens
+}
+
+fn mean(lens: &[usize]) -> f64 {
+    lens.iter().sum::<usize>() as f64 / lens.len() as f64
+}
+
+#[test]
+fn raml_random() {
+    let data = test_data(1, 1 << 22);
+    let ram = complete_lens(&Ram::with_w(1024), &data);
+    let ram_max = *ram.iter().max().unwrap();
+
+    // a limit above every chunk found by `Ram` changes nothing
+    let raml = complete_lens(&RamL::with_w_and_max(1024, ram_max as u64 + 1), &data);
+    assert_eq!(raml, ram);
+
+    // a limit near the average only shortens the longest chunks
+    let max = 2 * mean(&ram) as usize;
+    let raml = complete_lens(&RamL::with_w_and_max(1024, max as u64), &data);
+    assert!(raml.iter().all(|&l| l <= max));
+    let at_max = raml.iter().filter(|&&l| l == max).count();
+    let over_max = ram.iter().filter(|&&l| l >= max).count();
+    assert!(at_max > 0);
+    assert!(at_max >= over_max);
+    assert!(mean(&raml) < mean(&ram));
+    assert!(mean(&raml) > mean(&ram) * 0.75);
+    assert!(raml.len() > ram.len());
+
+    // chunks between splits at the maximum size are unchanged
+    let ram_set: std::collections::HashSet<_> = Ram::with_w(1024)
+        .chunk_ranges(&data)
+        .map(|r| r.end)
+        .collect();
+    let same = RamL::with_w_and_max(1024, max as u64)
+        .chunk_ranges(&data)
+        .filter(|r| r.len() < max && ram_set.contains(&r.end))
+        .count();
+    assert!(same * 2 > raml.len());
+}
+
+#[test]
+fn raml_low_entropy() {
+    // zeros never reach the running maximum, so `Ram` places each run in a single chunk
+    let data = low_entropy_data(2, 1 << 22, 100_000);
+    let ram = complete_lens(&Ram::with_w(1024), &data);
+    assert!(ram.iter().any(|&l| l > 100_000));
+
+    let max = 16384;
+    let raml = complete_lens(&RamL::with_w_and_max(1024, max as u64), &data);
+    assert!(raml.iter().all(|&l| l <= max));
+    // each run is split at the maximum size, and then (as the running maximum is cleared) into
+    // chunks just larger than the window
+    assert!(raml.contains(&max));
+    assert!(raml.iter().filter(|&&l| l == 1026).count() >= 20 * (100_000 / 1026 - 20));
+    assert!(mean(&raml) < mean(&ram));
+}
+
+#[test]
+fn raml_incr() {
+    let chunker = RamL::with_w_and_max(256, 2000);
+    for data in &[test_data(3, 1 << 20), low_entropy_data(4, 1 << 20, 10_000)] {
+        let expected = chunk_lens(&chunker, data);
+
+        let incr: Vec<_> = chunker
+            .to_chunk_incr()
+            .iter_slices(data)
+            .map(|s| s.len())
+            .collect();
+        assert_eq!(incr, expected);
+
+        // split into small pieces
+        let mut rng = Pcg64::seed_from_u64(5);
+        let mut incr = chunker.to_chunk_incr();
+        let mut lens = Vec::new();
+        let mut len = 0;
+        let mut rem = &data[..];
+        while !rem.is_empty() {
+            let mut piece = &rem[..std::cmp::min(rng.gen_range(0, 700), rem.len())];
+            rem = &rem[piece.len()..];
+            while let Some(i) = incr.push(piece) {
+                lens.push(len + i);
+                len = 0;
+                piece = &piece[i..];
+            }
+            len += piece.len();
+        }
+        lens.push(len);
+        assert_eq!(lens, expected);
+    }
+}
+
+#[test]
+fn raml_max_is_window() {
+    // with the maximum at or below the window, chunks are fixed size
+    let data = test_data(6, 10_000);
+    let lens = chunk_lens(&RamL::with_w_and_max(1024, 1000), &data);
+    assert_eq!(lens, [1000; 10]);
+}
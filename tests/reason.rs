@@ -0,0 +1,258 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "mii",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+
+mod common;
+
+use common::{pieces, test_data};
+use hash_roll::reason::{ChunkEdge, CutReason, CutReasons, ReasonTracking};
+use hash_roll::{Chunk, ToChunkIncr};
+
+/// Edges found by `ReasonTracking::push()`
+fn push_edges<C: ToChunkIncr + CutReasons>(chunker: C, pieces: &[&[u8]]) -> Vec<ChunkEdge> {
+    let mut rt = ReasonTracking::new(chunker);
+    let mut out = Vec::new();
+    for piece in pieces {
+        let mut rem = *piece;
+        while let Some((i, e)) = rt.push(rem) {
+            out.push(e);
+            rem = &rem[i..];
+        }
+    }
+    out.extend(rt.finish());
+    out
+}
+
+/// Edges found by `ReasonTracking::push_all()`
+fn push_all_edges<C: ToChunkIncr + CutReasons>(chunker: C, pieces: &[&[u8]]) -> Vec<ChunkEdge> {
+    let mut rt = ReasonTracking::new(chunker);
+    let mut out = Vec::new();
+    for piece in pieces {
+        rt.push_all(piece, &mut out);
+    }
+    out.extend(rt.finish());
+    out
+}
+
+/// Check that the edges reported are those found by `chunk_ranges()`, that only the final chunk
+/// is `EndOfData`, and that chunks are `MaxSize` exactly when they have `max_len` bytes
+///
+/// Returns the number of chunks cut for each reason.
+fn reason_test<C: Chunk + ToChunkIncr + CutReasons + Clone>(
+    chunker: C,
+    data: &[u8],
+    max_len: Option<u64>,
+) -> Vec<(CutReason, usize)> {
+    let edges = push_all_edges(chunker.clone(), &[data]);
+    let ends: Vec<_> = chunker.chunk_ranges(data).map(|r| r.end as u64).collect();
+    assert_eq!(edges.iter().map(|e| e.offset).collect::<Vec<_>>(), ends);
+    assert_eq!(
+        edges
+            .iter()
+            .filter(|e| e.reason == CutReason::EndOfData)
+            .count(),
+        1
+    );
+    assert_eq!(edges.last().unwrap().reason, CutReason::EndOfData);
+
+    let mut start = 0;
+    for e in &edges[..edges.len() - 1] {
+        assert_eq!(
+            e.reason == CutReason::MaxSize,
+            Some(e.offset - start) == max_len,
+            "{:?} at {} of a chunk of {} bytes",
+            e.reason,
+            e.offset,
+            e.offset - start
+        );
+        start = e.offset;
+    }
+
+    for (seed, &max) in [7, 5000].iter().enumerate() {
+        let p = pieces(data, max, seed as u64);
+        assert_eq!(push_edges(chunker.clone(), &p), edges, "push()");
+        assert_eq!(push_all_edges(chunker.clone(), &p), edges, "push_all()");
+    }
+
+    let mut counts = Vec::new();
+    for e in &edges {
+        match counts.iter_mut().find(|(r, _)| *r == e.reason) {
+            Some((_, n)) => *n += 1,
+            None => counts.push((e.reason, 1)),
+        }
+    }
+    counts
+}
+
+fn count(counts: &[(CutReason, usize)], reason: CutReason) -> usize {
+    counts
+        .iter()
+        .find(|(r, _)| *r == reason)
+        .map_or(0, |&(_, n)| n)
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn reason_forced() {
+    let mut rt = ReasonTracking::new(hash_roll::mii::Mii::default());
+    let mut out = Vec::new();
+    rt.push_all(&[1, 2, 3], &mut out);
+    assert!(out.is_empty());
+    assert_eq!(
+        rt.force_boundary(),
+        Some(ChunkEdge {
+            offset: 3,
+            reason: CutReason::Forced
+        })
+    );
+    assert_eq!(rt.force_boundary(), None);
+    assert_eq!(rt.offset(), 3);
+    assert_eq!(rt.finish(), None);
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn reason_bup() {
+    let counts = reason_test(
+        hash_roll::bup::RollSum::default(),
+        &test_data(1, 1 << 17),
+        None,
+    );
+    assert!(count(&counts, CutReason::Hash) > 1);
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn reason_buzhash() {
+    use hash_roll::buzhash::{BuzHash, BuzHashTableByteSaltHash};
+    let hash = BuzHashTableByteSaltHash::from((0, &hash_roll::buzhash_table::GO_BUZHASH));
+    // chunks of more than `max_chunk_size` bytes are cut after one more byte
+    let counts = reason_test(
+        BuzHash::new(67, 0x1fff, hash, 8192),
+        &test_data(2, 1 << 18),
+        Some(8193),
+    );
+    assert!(count(&counts, CutReason::Hash) > 1);
+    assert!(count(&counts, CutReason::MaxSize) > 1);
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn reason_fastcdc() {
+    use hash_roll::fastcdc::FastCdc;
+    // the byte following the first `max_size` bytes ends the chunk
+    let counts = reason_test(
+        FastCdc::default(),
+        &test_data(3, 1 << 20),
+        Some(64 * 1024 + 1),
+    );
+    assert!(count(&counts, CutReason::HashStrict) > 1);
+    assert!(count(&counts, CutReason::HashLoose) > 1);
+
+    let small = FastCdc::new(&hash_roll::gear_table::GEAR_64, 64, 256, 1024);
+    let counts = reason_test(small, &test_data(4, 1 << 18), Some(1025));
+    assert!(count(&counts, CutReason::MaxSize) > 1);
+    let counts = reason_test(
+        small.with_cut_before_match(),
+        &test_data(4, 1 << 18),
+        Some(1024),
+    );
+    assert!(count(&counts, CutReason::MaxSize) > 1);
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn reason_gear() {
+    let counts = reason_test(
+        hash_roll::gear::Gear32::default(),
+        &test_data(5, 1 << 17),
+        None,
+    );
+    assert!(count(&counts, CutReason::Hash) > 1);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn reason_mii() {
+    use hash_roll::mii::Mii;
+    let counts = reason_test(Mii::default(), &test_data(6, 1 << 16), None);
+    assert!(count(&counts, CutReason::Hash) > 1);
+
+    // runs of zeros are never split by the hash
+    let mut data = test_data(7, 1 << 16);
+    data.extend(vec![0; 10_000]);
+    let counts = reason_test(Mii::with_w_and_range(5, ..=2000), &data, Some(2000));
+    assert!(count(&counts, CutReason::Hash) > 1);
+    assert!(count(&counts, CutReason::MaxSize) >= 5);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn reason_ram() {
+    use hash_roll::ram::{Ram, RamL};
+    let data = test_data(8, 1 << 17);
+    let counts = reason_test(Ram::with_w(1024), &data, None);
+    assert!(count(&counts, CutReason::Hash) > 1);
+
+    let counts = reason_test(RamL::with_w_and_max(1024, 1100), &data, Some(1100));
+    assert!(count(&counts, CutReason::Hash) > 1);
+    assert!(count(&counts, CutReason::MaxSize) > 1);
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn reason_zpaq() {
+    use hash_roll::zpaq::Zpaq;
+    let data = test_data(9, 1 << 18);
+    let counts = reason_test(Zpaq::with_average_size_pow_2(12), &data, None);
+    assert!(count(&counts, CutReason::Hash) > 1);
+
+    // like zpaq, chunks are cut once they exceed the maximum
+    let counts = reason_test(Zpaq::with_average_and_range(13, ..=8192), &data, Some(8193));
+    assert!(count(&counts, CutReason::Hash) > 1);
+    assert!(count(&counts, CutReason::MaxSize) > 1);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn reason_zstd() {
+    let counts = reason_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        &test_data(10, 1 << 21),
+        None,
+    );
+    assert!(count(&counts, CutReason::Hash) >= 1);
+}
+
+#[cfg(all(feature = "gzip", feature = "pigz", feature = "mii"))]
+#[test]
+fn reason_dyn() {
+    use hash_roll::dyn_chunker::DynChunker;
+    let data = test_data(11, 1 << 17);
+    for (c, max_len) in [
+        (
+            DynChunker::from(hash_roll::gzip::GzipRsyncable::default()),
+            None,
+        ),
+        (
+            DynChunker::from(hash_roll::pigz::PigzRsyncable::default()),
+            None,
+        ),
+        (
+            DynChunker::from(hash_roll::mii::Mii::with_w_and_range(5, ..=2000)),
+            Some(2000),
+        ),
+    ]
+    .iter()
+    {
+        let counts = reason_test(c.clone(), &data, *max_len);
+        assert!(count(&counts, CutReason::Hash) > 1);
+    }
+}
@@ -0,0 +1,162 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+
+mod common;
+
+use common::test_data;
+use hash_roll::{Chunk, ChunkIncr, ToChunkIncr};
+
+/// Edges found by pushing all of `data` into `incr`
+fn incr_edges<I: ChunkIncr>(incr: &mut I, data: &[u8]) -> Vec<usize> {
+    let mut out = Vec::new();
+    incr.push_all(data, &mut out);
+    out
+}
+
+/// Edges found by `find_chunk_edge()` when all of `data` is passed at once
+fn search_edges<C: Chunk>(chunker: &C, state: &mut C::SearchState, data: &[u8]) -> Vec<usize> {
+    let mut out = Vec::new();
+    let mut discard = 0;
+    loop {
+        let (cut, discard_ct) = chunker.find_chunk_edge(state, &data[discard..]);
+        match cut {
+            Some(i) => {
+                out.push(discard + i);
+                discard += discard_ct;
+            }
+            None => break,
+        }
+    }
+    out
+}
+
+/// Check that after being fed part of some other input, a reset `ChunkIncr` and search state
+/// find the same edges as fresh ones
+fn reset_test<C: Chunk + ToChunkIncr>(chunker: C, size: usize) {
+    let data = test_data(1, size);
+    let other = test_data(2, size);
+    let expected = incr_edges(&mut chunker.to_chunk_incr(), &data);
+    assert!(!expected.is_empty());
+
+    for &l in &[1, 7, 300, size / 3] {
+        let mut incr = chunker.to_chunk_incr();
+        incr.push(&other[..l]);
+        incr.reset();
+        assert_eq!(incr_edges(&mut incr, &data), expected, "incr after {}", l);
+
+        // reset after finding edges, and again at the end of the input
+        incr.reset();
+        assert_eq!(incr_edges(&mut incr, &data), expected, "incr reused");
+
+        let mut state = chunker.to_search_state();
+        chunker.find_chunk_edge(&mut state, &other[..l]);
+        chunker.reset_search_state(&mut state);
+        assert_eq!(
+            search_edges(&chunker, &mut state, &data),
+            expected,
+            "search state after {}",
+            l
+        );
+    }
+}
+
+#[cfg(all(feature = "bup", feature = "mii"))]
+#[test]
+fn reset_dyn() {
+    use hash_roll::dyn_chunker::DynChunker;
+    use hash_roll::DynChunk;
+
+    reset_test(DynChunker::from(hash_roll::mii::Mii::default()), 1 << 16);
+    reset_test(
+        DynChunker::from(hash_roll::bup::RollSum::default()),
+        1 << 17,
+    );
+
+    let boxed: Box<dyn DynChunk> = Box::new(hash_roll::bup::RollSum::default());
+    reset_test(boxed, 1 << 17);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn reset_wrappers() {
+    let mii = hash_roll::mii::Mii::default();
+    reset_test(
+        hash_roll::bounded::Bounded::new(mii.clone(), 100..=2000),
+        1 << 16,
+    );
+    reset_test(hash_roll::runs::Runs::new(mii, 8, 1024), 1 << 16);
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn reset_bup() {
+    reset_test(hash_roll::bup::RollSum::default(), 1 << 17);
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn reset_buzhash() {
+    reset_test(hash_roll::buzhash::BuzHash::new_nom(0), 1 << 17);
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn reset_fastcdc() {
+    reset_test(hash_roll::fastcdc::FastCdc::default(), 1 << 17);
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn reset_gear() {
+    reset_test(hash_roll::gear::Gear32::default(), 1 << 17);
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn reset_gzip() {
+    reset_test(hash_roll::gzip::GzipRsyncable::default(), 1 << 17);
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn reset_mii() {
+    reset_test(hash_roll::mii::Mii::default(), 1 << 16);
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn reset_pigz() {
+    reset_test(hash_roll::pigz::PigzRsyncable::default(), 1 << 17);
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn reset_ram() {
+    reset_test(hash_roll::ram::Ram::with_w(1024), 1 << 17);
+    reset_test(hash_roll::ram::RamL::with_w_and_max(1024, 3000), 1 << 17);
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn reset_zpaq() {
+    reset_test(hash_roll::zpaq::Zpaq::with_average_size_pow_2(12), 1 << 17);
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn reset_zstd() {
+    reset_test(
+        hash_roll::zstd::Zstd::with_target_section_size(1 << 20),
+        1 << 21,
+    );
+}
@@ -0,0 +1,172 @@
+#![cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq"
+))]
+
+mod common;
+
+use common::{incr_edges, pieces, test_data};
+use hash_roll::runs::Runs;
+use hash_roll::{Chunk, ToChunkIncr};
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64;
+
+/// Random data interrupted by runs of a repeated byte, returning the data and the `(start, end)`
+/// of each run
+fn run_data(seed: u128, size: usize, max_run: usize) -> (Vec<u8>, Vec<(usize, usize)>) {
+    let mut rng = Pcg64::seed_from_u64(seed as u64);
+    let mut buf = test_data(seed, size);
+    let mut runs = Vec::new();
+    let mut pos = rng.gen_range(0, max_run);
+    while pos < size {
+        let l = std::cmp::min(rng.gen_range(1, max_run), size - pos);
+        let b = buf[pos];
+        for x in &mut buf[pos..pos + l] {
+            *x = b;
+        }
+        // extend over any random bytes that happen to match
+        let start = buf[..pos].iter().rev().take_while(|&&x| x == b).count();
+        let end = buf[pos + l..].iter().take_while(|&&x| x == b).count();
+        runs.push((pos - start, pos + l + end));
+        pos += l + end + rng.gen_range(1, max_run);
+    }
+    (buf, runs)
+}
+
+/// Edges found by `find_chunk_edge()`, appending each piece to a buffer of undiscarded data
+fn chunk_edges<C: Chunk>(chunker: &C, pieces: &[&[u8]]) -> Vec<usize> {
+    let mut state = chunker.to_search_state();
+    let mut edges = Vec::new();
+    let mut buf = Vec::new();
+    let mut buf_offset = 0;
+    for piece in pieces {
+        buf.extend_from_slice(piece);
+        loop {
+            let (cut, discard_ct) = chunker.find_chunk_edge(&mut state, &buf);
+            buf.drain(..discard_ct);
+            buf_offset += discard_ct;
+            match cut {
+                Some(cut) => edges.push(buf_offset - discard_ct + cut),
+                None => break,
+            }
+        }
+    }
+    edges
+}
+
+/// Check `edges` follow the run policy for each run in `runs`, where the data is `len` bytes long
+fn check_runs(edges: &[usize], runs: &[(usize, usize)], len: usize) {
+    for &(start, end) in runs {
+        if end - start < MIN_RUN {
+            continue;
+        }
+
+        // the first `MIN_RUN` bytes might be split by the wrapped algorithm, but the rest of the run
+        // is cut only by the policy
+        let a = start + MIN_RUN;
+        let in_run: Vec<_> = edges
+            .iter()
+            .copied()
+            .filter(|&e| e >= a && e <= end)
+            .collect();
+        assert_eq!(in_run.first(), Some(&a));
+        if end != a && end != len {
+            assert_eq!(in_run.last(), Some(&end));
+        }
+        for w in in_run.windows(2) {
+            assert!(w[1] - w[0] == RUN_CHUNK_LEN || w[1] == end, "{:?}", w);
+        }
+    }
+}
+
+const MIN_RUN: usize = 100;
+const RUN_CHUNK_LEN: usize = 1000;
+
+/// Check the edges found follow the run policy, and are the same for each way of splitting the
+/// input
+fn runs_test<C: Chunk + ToChunkIncr + Clone>(inner: C) {
+    let chunker = Runs::new(inner, MIN_RUN as u64, RUN_CHUNK_LEN as u64);
+
+    let (data, runs) = run_data(1, 1 << 18, 5000);
+    let expected = chunk_edges(&chunker, &[&data[..]]);
+    check_runs(&expected, &runs, data.len());
+
+    for &max in &[50, 3000, 1 << 16] {
+        let p = pieces(&data, max, max as u64);
+        assert_eq!(chunk_edges(&chunker, &p), expected, "max piece: {}", max);
+        assert_eq!(
+            incr_edges(chunker.to_chunk_incr(), &p),
+            expected,
+            "max piece: {}",
+            max
+        );
+    }
+
+    // data without runs is chunked by the wrapped algorithm alone
+    let data = test_data(2, 1 << 18);
+    let expected = chunk_edges(chunker.inner(), &[&data[..]]);
+    assert_eq!(chunk_edges(&chunker, &[&data[..]]), expected);
+    assert_eq!(incr_edges(chunker.to_chunk_incr(), &[&data[..]]), expected);
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn runs_constant() {
+    let data = vec![7u8; 10_300];
+    let chunker = Runs::new(hash_roll::fastcdc::FastCdc::default(), 500, 1000);
+    let edges: Vec<_> = chunker.chunk_ranges(&data).map(|r| r.end).collect();
+    let mut expected: Vec<_> = (0..10).map(|i| 500 + i * 1000).collect();
+    expected.push(10_300);
+    assert_eq!(edges, expected);
+
+    let p = pieces(&data, 300, 0);
+    let incr = incr_edges(chunker.to_chunk_incr(), &p);
+    assert_eq!(incr, &expected[..10]);
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn runs_bup() {
+    runs_test(hash_roll::bup::RollSum::default());
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn runs_buzhash() {
+    runs_test(hash_roll::buzhash::BuzHash::new_nom(0));
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn runs_fastcdc() {
+    runs_test(hash_roll::fastcdc::FastCdc::default());
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn runs_mii() {
+    runs_test(hash_roll::mii::Mii::default());
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn runs_pigz() {
+    runs_test(hash_roll::pigz::PigzRsyncable::default());
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn runs_ram() {
+    runs_test(hash_roll::ram::Ram::with_w(1024));
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn runs_zpaq() {
+    runs_test(hash_roll::zpaq::Zpaq::with_average_size_pow_2(12));
+}
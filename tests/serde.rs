@@ -0,0 +1,213 @@
+#![cfg(all(
+    feature = "serde",
+    any(
+        feature = "bup",
+        feature = "buzhash",
+        feature = "fastcdc",
+        feature = "gear",
+        feature = "gzip",
+        feature = "mii",
+        feature = "pigz",
+        feature = "ram",
+        feature = "zpaq",
+        feature = "zstd"
+    )
+))]
+
+use serde::{de::DeserializeOwned, Serialize};
+
+fn round_trip<T: Serialize + DeserializeOwned>(v: &T) -> (String, T) {
+    let json = serde_json::to_string(v).unwrap();
+    let back = serde_json::from_str(&json).unwrap();
+    (json, back)
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn serde_fastcdc() {
+    use hash_roll::fastcdc::FastCdc;
+    let c = FastCdc::new(&hash_roll::gear_table::GEAR_64, 1024, 4096, 16384);
+    let (json, back) = round_trip(&c);
+    assert_eq!(
+        json,
+        r#"{"table":{"named":"rsroll"},"min_size":1024,"normal_size":4096,"max_size":16384}"#
+    );
+    assert_eq!(back, c);
+
+    // the cut position isn't part of the serialized form
+    assert!(serde_json::to_string(&c.with_cut_before_match()).is_err());
+
+    // custom tables serialize by value, but can't be deserialized into the borrowed table
+    let table = [7u64; 256];
+    let json = serde_json::to_string(&FastCdc::new(&table, 1, 2, 3)).unwrap();
+    assert!(json.contains(r#""value":[7,7,"#));
+    assert!(serde_json::from_str::<FastCdc<'_>>(&json).is_err());
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn serde_gear() {
+    use hash_roll::gear::Gear32;
+    let c = Gear32::with_average_size_log2(10);
+    let (json, back) = round_trip(&c);
+    assert_eq!(
+        json,
+        r#"{"table":{"named":"rsroll"},"mask":4290772992,"xxx":0}"#
+    );
+    assert_eq!(format!("{:?}", back), format!("{:?}", c));
+
+    // a table given by value that matches a known table is accepted
+    let by_value = json.replace(
+        r#"{"named":"rsroll"}"#,
+        &serde_json::to_string(&serde_json::json!({
+            "value": hash_roll::gear_table::GEAR_32.to_vec()
+        }))
+        .unwrap(),
+    );
+    let back: Gear32<'_> = serde_json::from_str(&by_value).unwrap();
+    assert_eq!(format!("{:?}", back), format!("{:?}", c));
+
+    assert!(
+        serde_json::from_str::<Gear32<'_>>(r#"{"table":{"named":"other"},"mask":0,"xxx":0}"#)
+            .is_err()
+    );
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn serde_buzhash() {
+    use hash_roll::buzhash::{BuzHash, BuzHashTableBufHash, BuzHashTableHash};
+    let c = BuzHash::new_nom(3);
+    let (json, back) = round_trip(&c);
+    assert_eq!(
+        json,
+        r#"{"k":67,"h":{"table":{"named":"nom"},"salt":3},"mask":4095,"max_chunk_size":16777216}"#
+    );
+    assert_eq!(back.to_string(), c.to_string());
+
+    let c = BuzHash::new(
+        5,
+        0xff,
+        BuzHashTableHash::from(&hash_roll::buzhash_table::GO_BUZHASH),
+        1 << 20,
+    );
+    let (json, back) = round_trip(&c);
+    assert_eq!(
+        json,
+        r#"{"k":5,"h":{"table":{"named":"nom"}},"mask":255,"max_chunk_size":1048576}"#
+    );
+    assert_eq!(format!("{:?}", back), format!("{:?}", c));
+
+    // owned tables can be deserialized by value
+    let mut table = Box::new([0u32; 256]);
+    for (i, v) in table.iter_mut().enumerate() {
+        *v = (i as u32).wrapping_mul(0x9e37_79b9);
+    }
+    let c = BuzHash::new(5, 0xff, BuzHashTableBufHash::from(table.clone()), 1 << 20);
+    let (json, back) = round_trip(&c);
+    assert!(json.contains(r#""h":{"table":{"value":[0,2654435769,"#));
+
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i * 7 % 251) as u8).collect();
+    use hash_roll::Chunk;
+    let a: Vec<_> = c.chunk_ranges(&data).collect();
+    let b: Vec<_> = back.chunk_ranges(&data).collect();
+    assert_eq!(a, b);
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn serde_zpaq() {
+    use hash_roll::zpaq::Zpaq;
+    for c in &[
+        Zpaq::default(),
+        Zpaq::with_max_size(1 << 20),
+        Zpaq::with_average_and_range(12, 1024..),
+        Zpaq::with_average_and_range(12, ..=4096),
+    ] {
+        let (_, back) = round_trip(c);
+        assert_eq!(&back, c);
+    }
+}
+
+#[test]
+fn serde_others() {
+    #[cfg(feature = "bup")]
+    {
+        let c = hash_roll::bup::RollSum::with_window(128);
+        assert_eq!(round_trip(&c).1, c);
+    }
+    #[cfg(feature = "zstd")]
+    {
+        let c = hash_roll::zstd::Zstd::with_target_section_size(2 << 20);
+        assert_eq!(round_trip(&c).1, c);
+    }
+    #[cfg(feature = "gzip")]
+    {
+        let c = hash_roll::gzip::GzipRsyncable::with_window_and_modulus(4, 8);
+        assert_eq!(round_trip(&c).1, c);
+    }
+    #[cfg(feature = "pigz")]
+    {
+        let c = hash_roll::pigz::PigzRsyncable::with_bits(10);
+        let (json, back) = round_trip(&c);
+        assert_eq!(json, r#"{"bits":10}"#);
+        assert_eq!(back, c);
+    }
+    #[cfg(feature = "ram")]
+    {
+        let c = hash_roll::ram::Ram::with_w(700);
+        assert_eq!(round_trip(&c).1, c);
+        let c = hash_roll::ram::RamL::with_w_and_max(700, 9000);
+        assert_eq!(round_trip(&c).1, c);
+    }
+    #[cfg(feature = "mii")]
+    {
+        let c = hash_roll::mii::Mii::with_w(9);
+        let (json, back) = round_trip(&c);
+        assert_eq!(json, r#"{"w":9}"#);
+        assert_eq!(back.to_string(), c.to_string());
+        let c = hash_roll::mii::Mii::with_w_and_range(9, 100..=1000);
+        let (json, back) = round_trip(&c);
+        assert_eq!(json, r#"{"w":9,"min":100,"max":1000}"#);
+        assert_eq!(back.to_string(), c.to_string());
+    }
+}
+
+/// Configurations the `try_*` constructors reject are rejected when deserialized
+#[test]
+fn serde_invalid() {
+    fn invalid<T: DeserializeOwned>(json: &str) {
+        assert!(serde_json::from_str::<T>(json).is_err(), "{}", json);
+    }
+
+    #[cfg(feature = "bup")]
+    invalid::<hash_roll::bup::RollSum>(r#"{"window_len":3}"#);
+    #[cfg(feature = "buzhash")]
+    invalid::<hash_roll::buzhash::BuzHash<hash_roll::buzhash::BuzHashTableByteSaltHash<'_>>>(
+        r#"{"k":0,"h":{"table":{"named":"nom"},"salt":3},"mask":4095,"max_chunk_size":16777216}"#,
+    );
+    #[cfg(feature = "gzip")]
+    invalid::<hash_roll::gzip::GzipRsyncable>(r#"{"window_len":4,"modulus":0}"#);
+    #[cfg(feature = "mii")]
+    {
+        invalid::<hash_roll::mii::Mii>(r#"{"w":5,"min":100,"max":10}"#);
+        invalid::<hash_roll::mii::Mii>(r#"{"w":5,"max":0}"#);
+    }
+    #[cfg(feature = "pigz")]
+    invalid::<hash_roll::pigz::PigzRsyncable>(r#"{"bits":0}"#);
+    #[cfg(feature = "ram")]
+    invalid::<hash_roll::ram::RamL>(r#"{"w":3,"max":0}"#);
+    #[cfg(feature = "zpaq")]
+    {
+        invalid::<hash_roll::zpaq::Zpaq>(r#"{"range":["Unbounded","Unbounded"],"max_hash":3}"#);
+        invalid::<hash_roll::zpaq::Zpaq>(r#"{"range":["Unbounded","Unbounded"],"max_hash":0}"#);
+    }
+    #[cfg(feature = "zstd")]
+    {
+        invalid::<hash_roll::zstd::Zstd>(
+            r#"{"hit_mask":8388606,"prime_power":17676769151966902475}"#,
+        );
+        invalid::<hash_roll::zstd::Zstd>(r#"{"hit_mask":1023,"prime_power":17676769151966902475}"#);
+        invalid::<hash_roll::zstd::Zstd>(r#"{"hit_mask":8388607,"prime_power":1}"#);
+    }
+}
@@ -0,0 +1,162 @@
+#![cfg(all(
+    target_os = "linux",
+    any(
+        feature = "bup",
+        feature = "buzhash",
+        feature = "fastcdc",
+        feature = "ram",
+        feature = "zpaq"
+    )
+))]
+
+mod common;
+
+use common::test_data;
+use hash_roll::sparse::chunk_file;
+use hash_roll::{ChunkIncr, ToChunkIncr};
+use std::fs::{File, OpenOptions};
+use std::io::Read;
+use std::os::unix::fs::{FileExt, MetadataExt};
+use std::path::PathBuf;
+
+/// A file in the temp directory, removed on drop
+struct TempFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl TempFile {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("hash-roll-sparse-{}-{}", std::process::id(), name));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)
+            .unwrap();
+        TempFile { path, file }
+    }
+
+    fn contents(&self) -> Vec<u8> {
+        let mut v = Vec::new();
+        File::open(&self.path).unwrap().read_to_end(&mut v).unwrap();
+        v
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// A sparse file with data at each of `extents` (offset, length), and holes elsewhere, of length
+/// `len`
+fn sparse_file(name: &str, len: u64, extents: &[(u64, usize)]) -> TempFile {
+    let t = TempFile::new(name);
+    for (i, &(offset, l)) in extents.iter().enumerate() {
+        t.file
+            .write_all_at(&test_data(i as u128, l), offset)
+            .unwrap();
+    }
+    t.file.set_len(len).unwrap();
+    t
+}
+
+/// Ranges found by reading the entire file and pushing it in one slice
+fn expected<I: ChunkIncr>(incr: I, data: &[u8]) -> Vec<std::ops::Range<u64>> {
+    let mut offset = 0;
+    incr.iter_slices(data)
+        .map(|s| {
+            let r = offset..offset + s.len() as u64;
+            offset = r.end;
+            r
+        })
+        .collect()
+}
+
+fn sparse_test<C: ToChunkIncr>(chunker: C) {
+    const MIB: u64 = 1 << 20;
+    let files = [
+        // data, hole, data, trailing hole
+        sparse_file("a", 8 * MIB, &[(0, 300_000), (3 * MIB, 1_000_000)]),
+        // leading hole, data straddling block boundaries
+        sparse_file("b", 6 * MIB, &[(2 * MIB + 17, 70_000), (5 * MIB - 5, 10)]),
+        // entirely a hole
+        sparse_file("c", 3 * MIB + 1, &[]),
+        // no holes
+        sparse_file("d", 500_000, &[(0, 500_000)]),
+        // empty
+        sparse_file("e", 0, &[]),
+    ];
+
+    for t in &files {
+        let data = t.contents();
+        let exp = expected(chunker.to_chunk_incr(), &data);
+        let got = chunk_file(chunker.to_chunk_incr(), &t.file).unwrap();
+        assert_eq!(got, exp, "file: {:?}", t.path);
+    }
+}
+
+#[test]
+fn is_sparse() {
+    // check the temp directory supports sparse files, so the other tests examine holes
+    let t = sparse_file("sparse", 64 << 20, &[(1 << 20, 4096)]);
+    let meta = t.file.metadata().unwrap();
+    assert!(meta.blocks() * 512 < meta.len() / 2);
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn sparse_bup() {
+    sparse_test(hash_roll::bup::RollSum::default());
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn sparse_buzhash() {
+    sparse_test(hash_roll::buzhash::BuzHash::new_nom(0));
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn sparse_fastcdc() {
+    sparse_test(hash_roll::fastcdc::FastCdc::default());
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn sparse_zpaq() {
+    sparse_test(hash_roll::zpaq::Zpaq::default());
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn sparse_ram() {
+    sparse_test(hash_roll::ram::Ram::with_w(8192));
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+// `io::Error::other()` is newer than our minimum rust version
+#[allow(clippy::io_other_error)]
+fn callback_error() {
+    let t = sparse_file("err", 4 << 20, &[(1 << 20, 100_000)]);
+    let mut n = 0;
+    let r = hash_roll::sparse::chunk_file_with(
+        hash_roll::fastcdc::FastCdc::default().to_chunk_incr(),
+        &t.file,
+        |_| {
+            n += 1;
+            if n == 3 {
+                Err(std::io::Error::new(std::io::ErrorKind::Other, "stop"))
+            } else {
+                Ok(())
+            }
+        },
+    );
+    assert_eq!(r.unwrap_err().to_string(), "stop");
+    assert_eq!(n, 3);
+}
@@ -0,0 +1,245 @@
+use hash_roll::dyn_chunker::DynChunker;
+use hash_roll::spec::SpecError;
+
+/// Check that `spec` parses, formats as `canonical`, and that `canonical` round trips
+#[cfg(any(
+    feature = "bup",
+    feature = "buzhash",
+    feature = "fastcdc",
+    feature = "gear",
+    feature = "gzip",
+    feature = "mii",
+    feature = "pigz",
+    feature = "ram",
+    feature = "zpaq",
+    feature = "zstd"
+))]
+fn round_trip<C>(spec: &str, canonical: &str) -> C
+where
+    C: std::str::FromStr<Err = SpecError> + std::fmt::Display,
+{
+    let c: C = spec.parse().unwrap();
+    assert_eq!(c.to_string(), canonical);
+    let c2: C = canonical.parse().unwrap();
+    assert_eq!(c2.to_string(), canonical);
+
+    let d: DynChunker<'_> = spec.parse().unwrap();
+    assert_eq!(d.to_string(), canonical);
+    c
+}
+
+#[cfg(feature = "fastcdc")]
+#[test]
+fn spec_fastcdc() {
+    use hash_roll::fastcdc::FastCdc;
+    let c: FastCdc<'_> = round_trip(
+        "fastcdc:min=2KiB,avg=8KiB,max=64KiB",
+        "fastcdc:min=2KiB,avg=8KiB,max=64KiB",
+    );
+    assert_eq!(c, FastCdc::default());
+    let c: FastCdc<'_> = round_trip("fastcdc", "fastcdc:min=2KiB,avg=8KiB,max=64KiB");
+    assert_eq!(c, FastCdc::default());
+    let _: FastCdc<'_> = round_trip(
+        " fastcdc: max = 1MB, min=1000,avg=0x2000 ,table=rsroll",
+        "fastcdc:min=1000,avg=8KiB,max=1000000",
+    );
+
+    let table = [0u64; 256];
+    let custom = FastCdc::new(&table, 1, 2, 3);
+    assert_eq!(custom.to_string(), "fastcdc:min=1,avg=2,max=3,table=custom");
+    assert!(custom.to_string().parse::<FastCdc<'_>>().is_err());
+}
+
+#[cfg(feature = "gear")]
+#[test]
+fn spec_gear() {
+    let _: hash_roll::gear::Gear32<'_> = round_trip("gear", "gear:bits=13");
+    let _: hash_roll::gear::Gear32<'_> = round_trip("gear:bits=20", "gear:bits=20");
+    assert!("gear:bits=32"
+        .parse::<hash_roll::gear::Gear32<'_>>()
+        .is_err());
+    assert!("gear:bits=0"
+        .parse::<hash_roll::gear::Gear32<'_>>()
+        .is_err());
+}
+
+#[cfg(feature = "buzhash")]
+#[test]
+fn spec_buzhash() {
+    let _: hash_roll::buzhash::BuzHash<_> = round_trip(
+        "buzhash:k=67,mask=0xfff,max=16MiB,table=nom,salt=3",
+        "buzhash:k=67,mask=0xfff,max=16MiB,table=nom,salt=3",
+    );
+    let c: hash_roll::buzhash::BuzHash<_> = round_trip(
+        "buzhash",
+        "buzhash:k=67,mask=0xfff,max=16MiB,table=nom,salt=0",
+    );
+    assert_eq!(
+        c.to_string(),
+        hash_roll::buzhash::BuzHash::new_nom(0).to_string()
+    );
+    assert_eq!(
+        "buzhash:k=0"
+            .parse::<hash_roll::buzhash::BuzHash<_>>()
+            .err(),
+        Some(SpecError::InvalidValue {
+            algorithm: "buzhash",
+            param: "k",
+            value: "0".to_owned(),
+            reason: "must not be zero",
+        })
+    );
+    assert!("buzhash:salt=256"
+        .parse::<hash_roll::buzhash::BuzHash<_>>()
+        .is_err());
+    assert!("buzhash:table=borg"
+        .parse::<hash_roll::buzhash::BuzHash<_>>()
+        .is_err());
+
+    // `k` sizes a buffer, so it is limited
+    let _: hash_roll::buzhash::BuzHash<_> = round_trip(
+        "buzhash:k=1048576",
+        "buzhash:k=1048576,mask=0xfff,max=16MiB,table=nom,salt=0",
+    );
+    assert!("buzhash:k=100000000000"
+        .parse::<hash_roll::buzhash::BuzHash<_>>()
+        .is_err());
+}
+
+#[cfg(feature = "zpaq")]
+#[test]
+fn spec_zpaq() {
+    use hash_roll::zpaq::Zpaq;
+    let c: Zpaq = round_trip("zpaq:fragment=16", "zpaq:fragment=16");
+    assert_eq!(c, Zpaq::default());
+    let c: Zpaq = round_trip("zpaq", "zpaq:fragment=16");
+    assert_eq!(c, Zpaq::default());
+    let c: Zpaq = round_trip("zpaq:fragment=13", "zpaq:fragment=13");
+    assert_eq!(c, Zpaq::with_average_size_pow_2(13));
+
+    let c: Zpaq = round_trip(
+        "zpaq:fragment=12,min=1KiB,max=none",
+        "zpaq:fragment=12,min=1KiB,max=none",
+    );
+    assert_eq!(c, Zpaq::with_average_and_range(12, 1024..));
+
+    let c = Zpaq::with_max_size(1 << 20);
+    let s = c.to_string();
+    assert_eq!(s, "zpaq:fragment=8,min=16KiB,max=1MiB");
+    assert_eq!(s.parse::<Zpaq>().unwrap(), c);
+
+    assert!("zpaq:fragment=8".parse::<Zpaq>().is_err());
+    assert!("zpaq:fragment=33".parse::<Zpaq>().is_err());
+}
+
+#[cfg(feature = "ram")]
+#[test]
+fn spec_ram() {
+    let c: hash_roll::ram::Ram = round_trip("ram:w=8192", "ram:w=8KiB");
+    assert_eq!(c, hash_roll::ram::Ram::with_w(8192));
+    assert_eq!(
+        "ram".parse::<hash_roll::ram::Ram>(),
+        Err(SpecError::MissingParameter {
+            algorithm: "ram",
+            param: "w"
+        })
+    );
+
+    let c: hash_roll::ram::RamL = round_trip("raml:w=4096,max=65536", "raml:w=4KiB,max=64KiB");
+    assert_eq!(c, hash_roll::ram::RamL::with_w_and_max(4096, 65536));
+    assert_eq!(
+        "raml:w=4096".parse::<hash_roll::ram::RamL>(),
+        Err(SpecError::MissingParameter {
+            algorithm: "raml",
+            param: "max"
+        })
+    );
+    assert!("raml:w=4096,max=0".parse::<hash_roll::ram::RamL>().is_err());
+}
+
+#[cfg(feature = "mii")]
+#[test]
+fn spec_mii() {
+    let _: hash_roll::mii::Mii = round_trip("mii", "mii:w=5");
+    let _: hash_roll::mii::Mii = round_trip("mii:w=7", "mii:w=7");
+    assert!("mii:w=7KiB".parse::<hash_roll::mii::Mii>().is_err());
+    let _: hash_roll::mii::Mii =
+        round_trip("mii:max=65536,w=5,min=1024", "mii:w=5,min=1KiB,max=64KiB");
+    let _: hash_roll::mii::Mii = round_trip("mii:min=0", "mii:w=5");
+    assert!("mii:max=0".parse::<hash_roll::mii::Mii>().is_err());
+    assert!("mii:min=2KiB,max=1KiB"
+        .parse::<hash_roll::mii::Mii>()
+        .is_err());
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn spec_gzip() {
+    use hash_roll::gzip::GzipRsyncable;
+    let c: GzipRsyncable = round_trip("gzip", "gzip:window=8KiB,modulus=4096");
+    assert_eq!(c, GzipRsyncable::default());
+    let c: GzipRsyncable = round_trip("gzip:window=4,modulus=8", "gzip:window=4,modulus=8");
+    assert_eq!(c, GzipRsyncable::with_window_and_modulus(4, 8));
+    assert!("gzip:modulus=0".parse::<GzipRsyncable>().is_err());
+    let _: GzipRsyncable = round_trip("gzip:window=1MiB", "gzip:window=1MiB,modulus=4096");
+    assert!("gzip:window=100GB".parse::<GzipRsyncable>().is_err());
+}
+
+#[cfg(feature = "pigz")]
+#[test]
+fn spec_pigz() {
+    use hash_roll::pigz::PigzRsyncable;
+    let c: PigzRsyncable = round_trip("pigz", "pigz:bits=12");
+    assert_eq!(c, PigzRsyncable::default());
+    assert!("pigz:bits=32".parse::<PigzRsyncable>().is_err());
+}
+
+#[cfg(feature = "bup")]
+#[test]
+fn spec_bup() {
+    use hash_roll::bup::RollSum;
+    let c: RollSum = round_trip("bup", "bup:window=64");
+    assert_eq!(c, RollSum::default());
+    let c: RollSum = round_trip("bup:window=4KiB", "bup:window=4KiB");
+    assert_eq!(c, RollSum::with_window(4096));
+    assert!("bup:window=63".parse::<RollSum>().is_err());
+    let _: RollSum = round_trip("bup:window=1MiB", "bup:window=1MiB");
+    assert!("bup:window=2MiB".parse::<RollSum>().is_err());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn spec_zstd() {
+    use hash_roll::zstd::Zstd;
+    let c: Zstd = round_trip("zstd", "zstd:target=8MiB");
+    assert_eq!(c, Zstd::default());
+    let c: Zstd = round_trip("zstd:target=3MiB", "zstd:target=2MiB");
+    assert_eq!(c, Zstd::with_target_section_size(3 << 20));
+    assert!("zstd:target=1KiB".parse::<Zstd>().is_err());
+}
+
+#[test]
+fn spec_errors() {
+    assert_eq!(
+        "rabin:k=3".parse::<DynChunker<'_>>().unwrap_err(),
+        SpecError::UnknownAlgorithm("rabin".to_owned())
+    );
+
+    #[cfg(feature = "fastcdc")]
+    {
+        use hash_roll::fastcdc::FastCdc;
+        let e = "fastcdc:min=2KiB,avg=lots"
+            .parse::<FastCdc<'_>>()
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "fastcdc: invalid value `lots` for `avg`: expected a byte size"
+        );
+        let e = "fastcdc:min=2KiB,size=8KiB"
+            .parse::<FastCdc<'_>>()
+            .unwrap_err();
+        assert_eq!(e.to_string(), "fastcdc: unknown parameter `size`");
+        let e = "zpaq:fragment=16".parse::<FastCdc<'_>>().unwrap_err();
+        assert_eq!(e.to_string(), "expected algorithm `fastcdc`, found `zpaq`");
+    }
+}